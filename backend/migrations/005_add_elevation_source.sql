@@ -0,0 +1,7 @@
+-- Add elevation source product column to y_junctions table
+-- Migration 005: Record which GSI DEM product supplied the junction elevation
+
+ALTER TABLE y_junctions
+ADD COLUMN elevation_source TEXT;
+
+COMMENT ON COLUMN y_junctions.elevation_source IS 'ジャンクションノードの標高を供給したDEM製品（DEM5A, DEM5B, DEM5C, DEM10A, DEM10B）';
@@ -0,0 +1,25 @@
+-- Add road arm geometry and elevation profile columns to y_junctions table
+-- Migration 006: 各道路（アーム）沿いの標高プロファイルと勾配
+
+ALTER TABLE y_junctions
+ADD COLUMN arm_geometry_1 GEOGRAPHY(LINESTRING, 4326),
+ADD COLUMN arm_geometry_2 GEOGRAPHY(LINESTRING, 4326),
+ADD COLUMN arm_geometry_3 GEOGRAPHY(LINESTRING, 4326),
+ADD COLUMN elevation_profile JSONB,
+ADD COLUMN grade_1 REAL,
+ADD COLUMN grade_2 REAL,
+ADD COLUMN grade_3 REAL,
+ADD COLUMN max_grade REAL GENERATED ALWAYS AS (GREATEST(grade_1, grade_2, grade_3)) STORED;
+
+CREATE INDEX idx_y_junctions_max_grade
+    ON y_junctions (max_grade)
+    WHERE max_grade IS NOT NULL;
+
+COMMENT ON COLUMN y_junctions.arm_geometry_1 IS 'bearings[0]方向の道路形状（ジャンクションノードから約50m）';
+COMMENT ON COLUMN y_junctions.arm_geometry_2 IS 'bearings[1]方向の道路形状（ジャンクションノードから約50m）';
+COMMENT ON COLUMN y_junctions.arm_geometry_3 IS 'bearings[2]方向の道路形状（ジャンクションノードから約50m）';
+COMMENT ON COLUMN y_junctions.elevation_profile IS '各道路に沿った標高プロファイル（距離ごとの標高、勾配、上り/下り/平坦）';
+COMMENT ON COLUMN y_junctions.grade_1 IS 'bearings[0]方向の勾配（%、ジャンクションから離れる向きに上りが正）';
+COMMENT ON COLUMN y_junctions.grade_2 IS 'bearings[1]方向の勾配（%、ジャンクションから離れる向きに上りが正）';
+COMMENT ON COLUMN y_junctions.grade_3 IS 'bearings[2]方向の勾配（%、ジャンクションから離れる向きに上りが正）';
+COMMENT ON COLUMN y_junctions.max_grade IS '3本の道路の勾配の最大値（%、最も急な上り）';
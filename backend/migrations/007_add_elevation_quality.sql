@@ -0,0 +1,17 @@
+-- Add elevation quality and provenance columns to y_junctions table
+-- Migration 007: 標高の補間方法、データ欠損サンプル数、未取得の理由
+
+ALTER TABLE y_junctions
+ADD COLUMN elevation_quality TEXT
+    CHECK (elevation_quality IN ('bilinear', 'nearest', 'filled')),
+ADD COLUMN elevation_nodata_samples SMALLINT,
+ADD COLUMN elevation_skip_reason TEXT
+    CHECK (elevation_skip_reason IN ('no_tile', 'nodata_at_junction', 'nodata_at_neighbor'));
+
+CREATE INDEX idx_y_junctions_elevation_skip_reason
+    ON y_junctions (elevation_skip_reason)
+    WHERE elevation_skip_reason IS NOT NULL;
+
+COMMENT ON COLUMN y_junctions.elevation_quality IS 'ジャンクションノードの標高の補間方法（bilinear: 双線形補間, nearest: 最寄りセル, filled: 欠損セルを周囲の値で補完）';
+COMMENT ON COLUMN y_junctions.elevation_nodata_samples IS '道路沿いの標高サンプルのうちデータが得られなかった数';
+COMMENT ON COLUMN y_junctions.elevation_skip_reason IS '標高を取得できなかった理由（no_tile: タイルなし, nodata_at_junction: ジャンクションでデータ欠損, nodata_at_neighbor: 隣接点でデータ欠損）';
@@ -0,0 +1,17 @@
+-- Add local slope column and elevation diff index to y_junctions table
+-- Migration 008: ジャンクション周辺の局所勾配と、標高・高低差の範囲検索用インデックス
+
+-- 除数の 10.0 は隣接点までの距離で、インポーターの NEIGHBOR_DISTANCE_M
+-- (src/importer/mod.rs) と一致させること。距離を変える場合は新しいマイグレーションで列を作り直す
+ALTER TABLE y_junctions
+ADD COLUMN local_slope REAL GENERATED ALWAYS AS (max_elevation_diff / 10.0 * 100.0) STORED;
+
+CREATE INDEX idx_y_junctions_max_elevation_diff
+    ON y_junctions (max_elevation_diff)
+    WHERE max_elevation_diff IS NOT NULL;
+
+CREATE INDEX idx_y_junctions_local_slope
+    ON y_junctions (local_slope)
+    WHERE local_slope IS NOT NULL;
+
+COMMENT ON COLUMN y_junctions.local_slope IS 'ジャンクションノードと10m先の隣接点との最大高低差から求めた局所勾配（%）';
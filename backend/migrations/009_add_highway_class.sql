@@ -0,0 +1,11 @@
+-- Add highway class column to y_junctions table
+-- Migration 009: 接続する道路のうち最も上位の道路種別（統計の集計用）
+
+ALTER TABLE y_junctions
+ADD COLUMN highway_class TEXT;
+
+CREATE INDEX idx_y_junctions_highway_class
+    ON y_junctions (highway_class)
+    WHERE highway_class IS NOT NULL;
+
+COMMENT ON COLUMN y_junctions.highway_class IS '接続する3本の道路のうち最も上位の highway タグ（_link は本線と同じ扱い）';
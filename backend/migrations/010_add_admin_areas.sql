@@ -0,0 +1,40 @@
+-- Add administrative areas and assign them to y_junctions
+-- Migration 010: 都道府県・市区町村・区の境界と、各Y字路が含まれる行政区域
+
+CREATE TABLE admin_areas (
+    -- OSM の boundary=administrative リレーションのID
+    relation_id BIGINT PRIMARY KEY,
+    code TEXT UNIQUE NOT NULL,
+    name TEXT NOT NULL,
+    name_en TEXT,
+    admin_level SMALLINT NOT NULL CHECK (admin_level IN (4, 7, 8)),
+    geometry GEOGRAPHY(MULTIPOLYGON, 4326) NOT NULL
+);
+
+CREATE INDEX idx_admin_areas_geometry ON admin_areas USING GIST (geometry);
+CREATE INDEX idx_admin_areas_admin_level ON admin_areas (admin_level);
+
+ALTER TABLE y_junctions
+ADD COLUMN prefecture_code TEXT,
+ADD COLUMN prefecture_name TEXT,
+ADD COLUMN city_code TEXT,
+ADD COLUMN city_name TEXT,
+ADD COLUMN ward_code TEXT,
+ADD COLUMN ward_name TEXT;
+
+CREATE INDEX idx_y_junctions_prefecture_code
+    ON y_junctions (prefecture_code)
+    WHERE prefecture_code IS NOT NULL;
+
+CREATE INDEX idx_y_junctions_city_code
+    ON y_junctions (city_code)
+    WHERE city_code IS NOT NULL;
+
+CREATE INDEX idx_y_junctions_ward_code
+    ON y_junctions (ward_code)
+    WHERE ward_code IS NOT NULL;
+
+COMMENT ON COLUMN admin_areas.code IS '行政区域コード（ISO3166-2 または ref タグ、なければ r + リレーションID）';
+COMMENT ON COLUMN y_junctions.prefecture_code IS 'Y字路が含まれる都道府県（admin_level=4）のコード';
+COMMENT ON COLUMN y_junctions.city_code IS 'Y字路が含まれる市区町村（admin_level=7）のコード';
+COMMENT ON COLUMN y_junctions.ward_code IS 'Y字路が含まれる政令指定都市の区（admin_level=8）のコード';
@@ -0,0 +1,18 @@
+-- Add road names of the connected ways to y_junctions table
+-- Migration 011: 道路名（各道路の name）と、name:en・読みを含む検索用テキスト
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+ALTER TABLE y_junctions
+ADD COLUMN way_1_name TEXT,
+ADD COLUMN way_2_name TEXT,
+ADD COLUMN way_3_name TEXT,
+ADD COLUMN road_search_text TEXT;
+
+-- 部分一致検索（ILIKE '%...%'）用のトライグラムインデックス
+CREATE INDEX idx_y_junctions_road_search_text
+    ON y_junctions USING GIN (road_search_text gin_trgm_ops)
+    WHERE road_search_text IS NOT NULL;
+
+COMMENT ON COLUMN y_junctions.way_1_name IS 'angle_1 の時計回り始点側の道路の name タグ（bearings と同じ順）';
+COMMENT ON COLUMN y_junctions.road_search_text IS '3本の道路の name, alt_name, name:en と読み（name:ja-Hira など）を改行区切りで連結したもの';
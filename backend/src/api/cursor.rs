@@ -0,0 +1,118 @@
+//! Opaque page cursors for `GET /api/junctions`
+//!
+//! A cursor is the sort key and id of the last row of a page, encoded as URL-safe base64
+//! JSON. It also records the sort order it was issued for, so a cursor cannot be reused
+//! with a different `sort` or `center`.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::handlers::AppError;
+use crate::db::repository::{JunctionSort, PageCursor};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorPayload {
+    sort: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    center: Option<[f64; 2]>,
+    key: f64,
+    id: i64,
+}
+
+fn center_of(sort: &JunctionSort) -> Option<[f64; 2]> {
+    match sort {
+        JunctionSort::Distance { lat, lon } => Some([*lat, *lon]),
+        _ => None,
+    }
+}
+
+/// Encodes the position after which the next page starts
+pub fn encode(sort: &JunctionSort, cursor: &PageCursor) -> String {
+    let payload = CursorPayload {
+        sort: sort.as_str().to_string(),
+        center: center_of(sort),
+        key: cursor.key,
+        id: cursor.id,
+    };
+    let json = serde_json::to_vec(&payload).expect("cursor payload is always serializable");
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+/// Decodes a cursor issued for the same sort order
+pub fn decode(token: &str, sort: &JunctionSort) -> Result<PageCursor, AppError> {
+    let payload: CursorPayload = URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or(AppError::BadRequest("Invalid cursor"))?;
+
+    if payload.sort != sort.as_str() || payload.center != center_of(sort) {
+        return Err(AppError::BadRequest("cursor does not match sort"));
+    }
+
+    Ok(PageCursor {
+        key: payload.key,
+        id: payload.id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let sort = JunctionSort::Distance {
+            lat: 35.681236,
+            lon: 139.767125,
+        };
+        let cursor = PageCursor {
+            key: 123.456789,
+            id: 42,
+        };
+
+        let token = encode(&sort, &cursor);
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode(&token, &sort).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_cursor_sort_mismatch() {
+        let cursor = PageCursor { key: 20.0, id: 7 };
+        let token = encode(&JunctionSort::MinAngle, &cursor);
+
+        assert!(matches!(
+            decode(&token, &JunctionSort::Id),
+            Err(AppError::BadRequest("cursor does not match sort"))
+        ));
+
+        // 距離順は中心点も一致する必要がある
+        let token = encode(
+            &JunctionSort::Distance {
+                lat: 35.0,
+                lon: 139.0,
+            },
+            &cursor,
+        );
+        assert!(decode(
+            &token,
+            &JunctionSort::Distance {
+                lat: 34.0,
+                lon: 139.0
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        assert!(matches!(
+            decode("not a cursor", &JunctionSort::Id),
+            Err(AppError::BadRequest("Invalid cursor"))
+        ));
+        assert!(decode(&URL_SAFE_NO_PAD.encode("{}"), &JunctionSort::Id).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use futures::{future, stream, Stream, StreamExt};
+
+use crate::domain::Junction;
+
+// エクスポートで1回に書き出す最大行数
+const CHUNK_ROWS: usize = 500;
+
+/// Output format of `GET /api/junctions/export`
+///
+/// Each format is written as a header, one chunk per junction and a footer, so that rows can be
+/// sent as they are read from the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    GeoJson,
+    Csv,
+    Kml,
+    Gpx,
+}
+
+const CSV_HEADER: &str = "id,osm_node_id,lat,lon,angle_1,angle_2,angle_3,angle_type,\
+                          elevation,min_angle_elevation_diff,label,streetview_url\r\n";
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "geojson" => Some(Self::GeoJson),
+            "csv" => Some(Self::Csv),
+            "kml" => Some(Self::Kml),
+            "gpx" => Some(Self::Gpx),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::GeoJson => "application/geo+json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Kml => "application/vnd.google-earth.kml+xml",
+            Self::Gpx => "application/gpx+xml",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::GeoJson => "geojson",
+            Self::Csv => "csv",
+            Self::Kml => "kml",
+            Self::Gpx => "gpx",
+        }
+    }
+
+    pub fn header(&self) -> String {
+        match self {
+            Self::GeoJson => r#"{"type":"FeatureCollection","features":["#.to_string(),
+            // Excel で文字化けしないよう BOM を付ける
+            Self::Csv => format!("\u{feff}{}", CSV_HEADER),
+            Self::Kml => concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document><name>Y-junctions</name>"#,
+                "\n"
+            )
+            .to_string(),
+            Self::Gpx => concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<gpx version="1.1" creator="y-junctions" xmlns="http://www.topografix.com/GPX/1/1">"#,
+                "\n"
+            )
+            .to_string(),
+        }
+    }
+
+    /// One junction; `index` is its position in the output, starting at 0
+    pub fn row(&self, junction: &Junction, index: usize) -> String {
+        match self {
+            Self::GeoJson => {
+                let separator = if index == 0 { "" } else { "," };
+                format!("{}{}\n", separator, junction.to_feature())
+            }
+            Self::Csv => {
+                let fields = [
+                    junction.id.to_string(),
+                    junction.osm_node_id.to_string(),
+                    junction.lat.to_string(),
+                    junction.lon.to_string(),
+                    junction.angle_1.to_string(),
+                    junction.angle_2.to_string(),
+                    junction.angle_3.to_string(),
+                    angle_type_str(junction).to_string(),
+                    optional(junction.elevation),
+                    optional(junction.min_angle_elevation_diff),
+                    csv_field(&junction.label()),
+                    csv_field(&junction.streetview_url()),
+                ];
+                format!("{}\r\n", fields.join(","))
+            }
+            Self::Kml => format!(
+                "<Placemark><name>{}</name><description>{}</description>\
+                 <ExtendedData>\
+                 <Data name=\"angles\"><value>{}</value></Data>\
+                 <Data name=\"angle_type\"><value>{}</value></Data>\
+                 <Data name=\"streetview_url\"><value>{}</value></Data>\
+                 </ExtendedData>\
+                 <Point><coordinates>{},{}</coordinates></Point></Placemark>\n",
+                xml_escape(&junction.label()),
+                xml_escape(&description(junction)),
+                angles_str(junction),
+                angle_type_str(junction),
+                xml_escape(&junction.streetview_url()),
+                junction.lon,
+                junction.lat
+            ),
+            Self::Gpx => {
+                // GPX 1.1 のスキーマでは ele, name, desc, link, type の順
+                let ele = junction
+                    .elevation
+                    .map(|e| format!("<ele>{}</ele>", e))
+                    .unwrap_or_default();
+                format!(
+                    "<wpt lat=\"{}\" lon=\"{}\">{}<name>{}</name><desc>{}</desc>\
+                     <link href=\"{}\"><text>Street View</text></link>\
+                     <type>{}</type></wpt>\n",
+                    junction.lat,
+                    junction.lon,
+                    ele,
+                    xml_escape(&junction.label()),
+                    xml_escape(&description(junction)),
+                    xml_escape(&junction.streetview_url()),
+                    angle_type_str(junction)
+                )
+            }
+        }
+    }
+
+    pub fn footer(&self) -> String {
+        match self {
+            Self::GeoJson => "]}\n".to_string(),
+            Self::Csv => String::new(),
+            Self::Kml => "</Document></kml>\n".to_string(),
+            Self::Gpx => "</gpx>\n".to_string(),
+        }
+    }
+}
+
+fn angle_type_str(junction: &Junction) -> &'static str {
+    junction.angle_type().as_str()
+}
+
+fn angles_str(junction: &Junction) -> String {
+    format!(
+        "{}/{}/{}",
+        junction.angle_1, junction.angle_2, junction.angle_3
+    )
+}
+
+/// Writes `rows` in `format` as the chunks of a response body
+///
+/// A database error ends the stream with that error and without the footer. The server then
+/// aborts the response instead of finishing it, so clients see an incomplete transfer (e.g. a
+/// missing final chunk) rather than a truncated file with status 200.
+pub fn body_stream(
+    format: ExportFormat,
+    rows: impl Stream<Item = Result<Junction, sqlx::Error>> + Send + 'static,
+) -> impl Stream<Item = Result<String, sqlx::Error>> + Send + 'static {
+    let rows = rows.ready_chunks(CHUNK_ROWS).scan(0, move |index, batch| {
+        let mut chunk = String::new();
+        for junction in batch {
+            match junction {
+                Ok(junction) => {
+                    chunk.push_str(&format.row(&junction, *index));
+                    *index += 1;
+                }
+                Err(err) => {
+                    tracing::error!("Database error during export: {:?}", err);
+                    return future::ready(Some(Err(err)));
+                }
+            }
+        }
+        future::ready(Some(Ok(chunk)))
+    });
+
+    stream::once(future::ready(Ok(format.header())))
+        .chain(rows)
+        .chain(stream::once(future::ready(Ok(format.footer()))))
+        // エラーの後は何も送らない（フッターを付けると正常なファイルに見えてしまう）
+        .scan(false, |failed, chunk| {
+            if *failed {
+                return future::ready(None);
+            }
+            *failed = chunk.is_err();
+            future::ready(Some(chunk))
+        })
+}
+
+// KML・GPX の説明文（角度と Street View の URL）
+fn description(junction: &Junction) -> String {
+    format!(
+        "角度: {}° / {}° / {}°\n{}",
+        junction.angle_1,
+        junction.angle_2,
+        junction.angle_3,
+        junction.streetview_url()
+    )
+}
+
+fn optional(value: Option<f64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::JunctionAreas;
+    use chrono::Utc;
+
+    fn junction(id: i64) -> Junction {
+        Junction {
+            id,
+            osm_node_id: 123456,
+            lat: 35.5,
+            lon: 139.25,
+            angle_1: 20,
+            angle_2: 140,
+            angle_3: 200,
+            bearings: vec![10.0, 30.0, 170.0],
+            created_at: Utc::now(),
+            elevation: Some(12.5),
+            min_elevation_diff: None,
+            max_elevation_diff: None,
+            min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [Some("A, \"B\" & C".to_string()), None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
+        }
+    }
+
+    fn export(format: ExportFormat) -> String {
+        let mut output = format.header();
+        for (index, j) in [junction(1), junction(2)].iter().enumerate() {
+            output.push_str(&format.row(j, index));
+        }
+        output.push_str(&format.footer());
+        output
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ExportFormat::parse("csv"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::parse("gpx"), Some(ExportFormat::Gpx));
+        assert_eq!(ExportFormat::parse("shp"), None);
+    }
+
+    #[test]
+    fn test_geojson_export_is_valid_json() {
+        let output = export(ExportFormat::GeoJson);
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        let features = json["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[1]["properties"]["id"], 2);
+        assert!(features[0]["properties"]["streetview_url"].is_string());
+    }
+
+    #[test]
+    fn test_csv_export_quotes_fields() {
+        let output = export(ExportFormat::Csv);
+        let lines: Vec<&str> = output.trim_end().split("\r\n").collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("\u{feff}id,osm_node_id"));
+        assert!(lines[1].starts_with("1,123456,35.5,139.25,20,140,200,verysharp,12.5,,"));
+        assert!(lines[1].contains(r#""A, ""B"" & Cの分岐""#));
+        // URL は座標のカンマを含むので引用符で囲まれる
+        assert!(lines[1].ends_with("viewpoint=35.5,139.25&heading=20\""));
+    }
+
+    #[test]
+    fn test_kml_and_gpx_export_escape_names() {
+        let kml = export(ExportFormat::Kml);
+        assert!(kml.contains("<name>A, &quot;B&quot; &amp; Cの分岐</name>"));
+        assert!(kml.contains("<coordinates>139.25,35.5</coordinates>"));
+        assert!(kml.contains("<value>20/140/200</value>"));
+        assert!(kml.trim_end().ends_with("</kml>"));
+
+        let gpx = export(ExportFormat::Gpx);
+        assert!(gpx.contains(r#"<wpt lat="35.5" lon="139.25"><ele>12.5</ele><name>"#));
+        assert!(gpx.contains("&amp;heading=20"));
+        assert_eq!(gpx.matches("<wpt ").count(), 2);
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+    }
+
+    #[tokio::test]
+    async fn test_body_stream_stops_at_database_error() {
+        let rows = stream::iter([Ok(junction(1)), Err(sqlx::Error::PoolTimedOut)]);
+        let chunks: Vec<_> = body_stream(ExportFormat::GeoJson, rows).collect().await;
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].as_deref().unwrap(),
+            ExportFormat::GeoJson.header()
+        );
+        assert!(matches!(chunks[1], Err(sqlx::Error::PoolTimedOut)));
+
+        let rows = stream::iter([Ok(junction(1)), Ok(junction(2))]);
+        let body: Vec<_> = body_stream(ExportFormat::GeoJson, rows).collect().await;
+        let body: String = body.into_iter().map(Result::unwrap).collect();
+        assert_eq!(body, export(ExportFormat::GeoJson));
+    }
+}
@@ -0,0 +1,210 @@
+//! Validation of GeoJSON search areas for `POST /api/junctions/search`
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::handlers::AppError;
+
+/// Maximum number of positions across all rings of a search area
+pub const MAX_VERTICES: usize = 10_000;
+
+/// Maximum number of polygons in a MultiPolygon search area
+pub const MAX_POLYGONS: usize = 100;
+
+/// Maximum number of rings (outer ring and holes) per polygon
+pub const MAX_RINGS_PER_POLYGON: usize = 100;
+
+type Ring = Vec<Vec<f64>>;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "coordinates")]
+enum SearchGeometry {
+    Polygon(Vec<Ring>),
+    MultiPolygon(Vec<Vec<Ring>>),
+}
+
+/// Checks the structure, coordinate range and size of a search area
+///
+/// Returns the geometry as GeoJSON with 2D coordinates, ready for `ST_GeomFromGeoJSON`.
+/// Topological validity (e.g. self-intersection) is left to PostGIS.
+pub fn parse_search_area(geometry: &Value) -> Result<String, AppError> {
+    let geometry: SearchGeometry = serde_json::from_value(geometry.clone())
+        .map_err(|_| AppError::BadRequest("geometry must be a GeoJSON Polygon or MultiPolygon"))?;
+
+    let polygons = match geometry {
+        SearchGeometry::Polygon(rings) => vec![rings],
+        SearchGeometry::MultiPolygon(polygons) => polygons,
+    };
+
+    if polygons.is_empty() {
+        return Err(AppError::BadRequest("geometry has no polygons"));
+    }
+    if polygons.len() > MAX_POLYGONS {
+        return Err(AppError::BadRequest(
+            "geometry has too many polygons (max 100)",
+        ));
+    }
+
+    let mut vertices = 0;
+    let mut normalized = Vec::with_capacity(polygons.len());
+    for rings in &polygons {
+        if rings.is_empty() {
+            return Err(AppError::BadRequest("Polygon must have an outer ring"));
+        }
+        if rings.len() > MAX_RINGS_PER_POLYGON {
+            return Err(AppError::BadRequest("Polygon has too many rings (max 100)"));
+        }
+
+        let mut normalized_rings = Vec::with_capacity(rings.len());
+        for ring in rings {
+            vertices += ring.len();
+            if vertices > MAX_VERTICES {
+                return Err(AppError::BadRequest(
+                    "geometry has too many vertices (max 10000)",
+                ));
+            }
+            normalized_rings.push(normalize_ring(ring)?);
+        }
+        normalized.push(normalized_rings);
+    }
+
+    let geometry = serde_json::json!({
+        "type": "MultiPolygon",
+        "coordinates": normalized,
+    });
+    Ok(geometry.to_string())
+}
+
+/// Validates a linear ring and drops any altitude from its positions
+fn normalize_ring(ring: &Ring) -> Result<Vec<[f64; 2]>, AppError> {
+    let positions = ring
+        .iter()
+        .map(|position| match position.as_slice() {
+            [lon, lat, ..] if lon.is_finite() && lat.is_finite() => Ok([*lon, *lat]),
+            _ => Err(AppError::BadRequest(
+                "geometry positions must be [lon, lat] numbers",
+            )),
+        })
+        .collect::<Result<Vec<[f64; 2]>, _>>()?;
+
+    if positions.len() < 4 || positions.first() != positions.last() {
+        return Err(AppError::BadRequest(
+            "Polygon rings must be closed and have at least 4 positions",
+        ));
+    }
+
+    let in_range = positions
+        .iter()
+        .all(|[lon, lat]| (-180.0..=180.0).contains(lon) && (-90.0..=90.0).contains(lat));
+    if !in_range {
+        return Err(AppError::BadRequest(
+            "geometry coordinates out of valid range",
+        ));
+    }
+
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn square(min_lon: f64, min_lat: f64, size: f64) -> Value {
+        json!([
+            [min_lon, min_lat],
+            [min_lon + size, min_lat],
+            [min_lon + size, min_lat + size],
+            [min_lon, min_lat + size],
+            [min_lon, min_lat]
+        ])
+    }
+
+    fn error_message(result: Result<String, AppError>) -> &'static str {
+        match result {
+            Err(AppError::BadRequest(message)) => message,
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_polygon_is_normalized_to_multipolygon() {
+        let geometry = json!({
+            "type": "Polygon",
+            "coordinates": [[
+                [139.0, 35.0, 12.5],
+                [139.1, 35.0, 12.5],
+                [139.1, 35.1, 12.5],
+                [139.0, 35.0, 12.5]
+            ]]
+        });
+
+        let normalized: Value =
+            serde_json::from_str(&parse_search_area(&geometry).unwrap()).unwrap();
+        assert_eq!(normalized["type"], "MultiPolygon");
+        assert_eq!(normalized["coordinates"][0][0][1], json!([139.1, 35.0]));
+    }
+
+    #[test]
+    fn test_multipolygon_with_hole() {
+        let geometry = json!({
+            "type": "MultiPolygon",
+            "coordinates": [
+                [square(139.0, 35.0, 1.0), square(139.2, 35.2, 0.1)],
+                [square(135.0, 34.0, 0.5)]
+            ]
+        });
+
+        let normalized: Value =
+            serde_json::from_str(&parse_search_area(&geometry).unwrap()).unwrap();
+        assert_eq!(normalized["coordinates"].as_array().unwrap().len(), 2);
+        assert_eq!(normalized["coordinates"][0].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_other_geometry_types() {
+        let geometry = json!({ "type": "Point", "coordinates": [139.0, 35.0] });
+        assert_eq!(
+            error_message(parse_search_area(&geometry)),
+            "geometry must be a GeoJSON Polygon or MultiPolygon"
+        );
+    }
+
+    #[test]
+    fn test_rejects_open_ring() {
+        let geometry = json!({
+            "type": "Polygon",
+            "coordinates": [[[139.0, 35.0], [139.1, 35.0], [139.1, 35.1], [139.0, 35.1]]]
+        });
+        assert_eq!(
+            error_message(parse_search_area(&geometry)),
+            "Polygon rings must be closed and have at least 4 positions"
+        );
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_coordinates() {
+        let geometry = json!({ "type": "Polygon", "coordinates": [square(179.5, 35.0, 1.0)] });
+        assert_eq!(
+            error_message(parse_search_area(&geometry)),
+            "geometry coordinates out of valid range"
+        );
+    }
+
+    #[test]
+    fn test_rejects_too_many_vertices() {
+        let mut ring: Vec<[f64; 2]> = (0..MAX_VERTICES)
+            .map(|i| {
+                let angle = i as f64 / MAX_VERTICES as f64 * std::f64::consts::TAU;
+                [139.0 + angle.cos(), 35.0 + angle.sin()]
+            })
+            .collect();
+        ring.push(ring[0]);
+
+        let geometry = json!({ "type": "Polygon", "coordinates": [ring] });
+        assert_eq!(
+            error_message(parse_search_area(&geometry)),
+            "geometry has too many vertices (max 10000)"
+        );
+    }
+}
@@ -1,20 +1,31 @@
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Json, Response},
 };
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
 use std::collections::HashMap;
 
-use crate::db::repository::{self, FilterParams};
-use crate::domain::{AngleType, Junction};
+use super::cursor;
+use super::export::{self, ExportFormat};
+use super::geometry;
+use super::tile::{self, TileCoord};
+use crate::config::parse_bbox;
+use crate::db::repository::{FilterParams, HistogramBin, JunctionSort, Sampling, SearchArea};
+use crate::db::{JunctionRepository, SharedRepository};
+use crate::domain::{AdminLevel, AngleType, AreaSummary, Junction, JunctionCluster};
 
 // エラー型
 #[derive(Debug)]
 pub enum AppError {
     NotFound,
     BadRequest(&'static str),
+    // エンドポイントで使わない一覧用のパラメータ（JunctionsQuery::reject_list_params）
+    UnsupportedParams {
+        params: Vec<&'static str>,
+        endpoint: &'static str,
+    },
     Internal(&'static str),
 }
 
@@ -33,14 +44,24 @@ struct ErrorResponse {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
-            AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found".to_string()),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.to_string()),
+            AppError::UnsupportedParams { params, endpoint } => {
+                // "limit, sort and cursor cannot be used with nearest"
+                let names = match params.split_last() {
+                    Some((last, [])) => last.to_string(),
+                    Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+                    None => String::new(),
+                };
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("{} cannot be used with {}", names, endpoint),
+                )
+            }
+            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.to_string()),
         };
 
-        let body = Json(ErrorResponse {
-            error: message.to_string(),
-        });
+        let body = Json(ErrorResponse { error: message });
 
         (status, body).into_response()
     }
@@ -49,7 +70,7 @@ impl IntoResponse for AppError {
 // GET /api/junctions のクエリパラメータ
 #[derive(Debug, Deserialize)]
 pub struct JunctionsQuery {
-    pub bbox: String,               // "min_lon,min_lat,max_lon,max_lat"
+    pub bbox: Option<String>,       // "min_lon,min_lat,max_lon,max_lat"
     pub angle_type: Option<String>, // "sharp,even" など
     pub min_angle_lt: Option<i16>,
     pub min_angle_gt: Option<i16>,
@@ -58,43 +79,185 @@ pub struct JunctionsQuery {
     pub min_angle_elevation_diff: Option<f64>,
     // 最大角の高低差フィルタ（範囲検索用）
     pub max_angle_elevation_diff: Option<f64>,
+    // いずれかの道路の上り勾配（%）の下限
+    pub min_arm_grade: Option<f64>,
+    // 標高（m）の範囲
+    pub elevation_gte: Option<f64>,
+    pub elevation_lte: Option<f64>,
+    // 最小高低差（m）の範囲
+    pub min_elevation_diff_gte: Option<f64>,
+    pub min_elevation_diff_lte: Option<f64>,
+    // 最大高低差（m）の範囲
+    pub max_elevation_diff_gte: Option<f64>,
+    pub max_elevation_diff_lte: Option<f64>,
+    // 局所勾配（%）の範囲
+    pub local_slope_gte: Option<f64>,
+    pub local_slope_lte: Option<f64>,
+    // 橋・トンネルを含むY字路を含めるか（デフォルト: true）
+    pub include_bridges: Option<bool>,
+    pub include_tunnels: Option<bool>,
+    // 並び順: id（デフォルト）, min_angle, elevation_diff, distance
+    pub sort: Option<String>,
+    // 距離の基準点 "lat,lon"
+    pub center: Option<String>,
+    // center からの検索半径（メートル、bbox の代わりに指定）
+    pub radius_m: Option<f64>,
+    // 前のレスポンスの next_cursor
+    pub cursor: Option<String>,
+    // limit を超えた場合の間引き方: grid, sharpest, random
+    pub sampling: Option<String>,
+    // 都道府県・市区町村・区のコード（指定すると bbox なしでも検索できる）
+    pub area: Option<String>,
+    // 道路名（name, name:en, 読み）の部分一致検索（指定すると bbox なしでも検索できる）
+    pub q: Option<String>,
 }
 
-impl JunctionsQuery {
-    fn parse_bbox(&self) -> Result<(f64, f64, f64, f64), AppError> {
-        let parts: Vec<&str> = self.bbox.split(',').collect();
-        if parts.len() != 4 {
-            return Err(AppError::BadRequest(
-                "bbox must be in format: min_lon,min_lat,max_lon,max_lat",
-            ));
+// GET /api/junctions/nearest のクエリパラメータ（絞り込み条件は JunctionsQuery と共通）
+#[derive(Debug, Deserialize)]
+pub struct NearestQuery {
+    pub lat: f64,
+    pub lon: f64,
+    pub k: Option<i64>,
+}
+
+// POST /api/junctions/search のリクエストボディ（絞り込み条件は JunctionsQuery と共通）
+#[derive(Debug, Deserialize)]
+pub struct SearchRequest {
+    pub geometry: serde_json::Value,
+    #[serde(flatten)]
+    pub filters: JunctionsQuery,
+}
+
+// GET /api/junctions/export のクエリパラメータ（絞り込み条件は JunctionsQuery と共通）
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    // geojson（デフォルト）, csv, kml, gpx
+    pub format: Option<String>,
+}
+
+// GET /api/junctions/clusters のクエリパラメータ（絞り込み条件は JunctionsQuery と共通）
+#[derive(Debug, Deserialize)]
+pub struct ClustersQuery {
+    pub zoom: u32,
+}
+
+// GET /api/areas, GET /api/areas/ranking のクエリパラメータ
+// ランキングの絞り込み条件と件数（limit）は JunctionsQuery と共通
+#[derive(Debug, Deserialize)]
+pub struct AreasQuery {
+    // prefecture（デフォルト）, city, ward または admin_level の 4, 7, 8
+    pub level: Option<String>,
+}
+
+impl AreasQuery {
+    fn parse_level(&self) -> Result<AdminLevel, AppError> {
+        match self.level.as_deref() {
+            None => Ok(AdminLevel::Prefecture),
+            Some(level) => AdminLevel::parse(level).ok_or(AppError::BadRequest("Invalid level")),
         }
+    }
+}
+
+// ランキングの件数のデフォルトと上限
+const DEFAULT_RANKING_LIMIT: i64 = 10;
+const MAX_RANKING_LIMIT: i64 = 100;
+
+// 返すクラスタ数の上限
+const MAX_CLUSTERS: i64 = 10_000;
+
+// 1タイルに含めるY字路の上限（超えた場合は鋭いものを優先）
+const MAX_TILE_FEATURES: i64 = 5000;
+
+// タイルのキャッシュ期間（データはインポート時にしか変わらない）
+const TILE_CACHE_CONTROL: &str = "public, max-age=3600";
 
-        let coords: Result<Vec<f64>, _> = parts.iter().map(|s| s.parse::<f64>()).collect();
-        let coords = coords.map_err(|_| AppError::BadRequest("Invalid bbox coordinates"))?;
+// 検索半径の上限（メートル）
+const MAX_RADIUS_M: f64 = 50_000.0;
 
-        let (min_lon, min_lat, max_lon, max_lat) = (coords[0], coords[1], coords[2], coords[3]);
+// 道路名検索の文字数の上限
+const MAX_QUERY_CHARS: usize = 100;
 
-        // バリデーション
-        if min_lon >= max_lon || min_lat >= max_lat {
-            return Err(AppError::BadRequest("Invalid bbox range"));
+// 最近傍検索の件数のデフォルトと上限
+const DEFAULT_NEAREST_K: i64 = 10;
+const MAX_NEAREST_K: i64 = 100;
+
+// 一覧（GET /api/junctions）の検索範囲・件数・並び順・ページング・間引きのパラメータ
+const LIST_PARAMS: [&str; 7] = [
+    "bbox", "center", "radius_m", "limit", "sort", "cursor", "sampling",
+];
+
+// 検索範囲のパラメータ
+const AREA_PARAMS: [&str; 3] = ["bbox", "center", "radius_m"];
+
+impl JunctionsQuery {
+    // 一覧用のパラメータのうち、`allowed` 以外が指定されていれば 400 にする
+    // （黙って無視すると、クライアントが効いていると誤解したり、CDN が別の URL でキャッシュしたりする）
+    fn reject_list_params(&self, endpoint: &'static str, allowed: &[&str]) -> Result<(), AppError> {
+        let given = [
+            self.bbox.is_some(),
+            self.center.is_some(),
+            self.radius_m.is_some(),
+            self.limit.is_some(),
+            self.sort.is_some(),
+            self.cursor.is_some(),
+            self.sampling.is_some(),
+        ];
+        let params: Vec<&'static str> = LIST_PARAMS
+            .into_iter()
+            .zip(given)
+            .filter(|(name, given)| *given && !allowed.contains(name))
+            .map(|(name, _)| name)
+            .collect();
+
+        if params.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::UnsupportedParams { params, endpoint })
         }
+    }
 
-        if min_lon < -180.0 || max_lon > 180.0 || min_lat < -90.0 || max_lat > 90.0 {
-            return Err(AppError::BadRequest("bbox out of valid range"));
+    fn parse_area(&self) -> Result<SearchArea, AppError> {
+        match (&self.bbox, self.radius_m) {
+            (Some(bbox), None) => {
+                let [min_lon, min_lat, max_lon, max_lat] =
+                    parse_bbox(bbox).map_err(|err| AppError::BadRequest(err.as_str()))?;
+                Ok(SearchArea::Bbox(min_lon, min_lat, max_lon, max_lat))
+            }
+            (None, Some(radius_m)) => {
+                if !(radius_m > 0.0 && radius_m <= MAX_RADIUS_M) {
+                    return Err(AppError::BadRequest(
+                        "radius_m must be greater than 0 and at most 50000",
+                    ));
+                }
+                let (lat, lon) = self
+                    .parse_center()?
+                    .ok_or(AppError::BadRequest("radius_m requires center"))?;
+                Ok(SearchArea::Radius { lat, lon, radius_m })
+            }
+            (Some(_), Some(_)) => Err(AppError::BadRequest(
+                "bbox and radius_m cannot be used together",
+            )),
+            (None, None) if self.area.is_some() || self.q.is_some() => Ok(SearchArea::Everywhere),
+            (None, None) => Err(AppError::BadRequest(
+                "bbox or center and radius_m is required",
+            )),
         }
+    }
 
-        Ok((min_lon, min_lat, max_lon, max_lat))
+    // 集計用の範囲（bbox も center+radius_m もなければ全件）
+    fn parse_optional_area(&self) -> Result<Option<SearchArea>, AppError> {
+        match (&self.bbox, self.radius_m) {
+            (None, None) => Ok(None),
+            _ => self.parse_area().map(Some),
+        }
     }
 
     fn parse_angle_types(&self) -> Result<Option<Vec<AngleType>>, AppError> {
         if let Some(ref types_str) = self.angle_type {
             let types: Result<Vec<AngleType>, _> = types_str
                 .split(',')
-                .map(|s| match s.trim() {
-                    "verysharp" => Ok(AngleType::VerySharp),
-                    "sharp" => Ok(AngleType::Sharp),
-                    "normal" => Ok(AngleType::Normal),
-                    _ => Err(AppError::BadRequest("Invalid angle_type")),
+                .map(|s| {
+                    AngleType::parse(s.trim()).ok_or(AppError::BadRequest("Invalid angle_type"))
                 })
                 .collect();
             Ok(Some(types?))
@@ -103,6 +266,54 @@ impl JunctionsQuery {
         }
     }
 
+    fn parse_center(&self) -> Result<Option<(f64, f64)>, AppError> {
+        let Some(ref center) = self.center else {
+            return Ok(None);
+        };
+
+        let parts: Vec<&str> = center.split(',').collect();
+        if parts.len() != 2 {
+            return Err(AppError::BadRequest("center must be in format: lat,lon"));
+        }
+
+        let lat = parts[0].trim().parse::<f64>();
+        let lon = parts[1].trim().parse::<f64>();
+        let (Ok(lat), Ok(lon)) = (lat, lon) else {
+            return Err(AppError::BadRequest("Invalid center coordinates"));
+        };
+
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(AppError::BadRequest("center out of valid range"));
+        }
+
+        Ok(Some((lat, lon)))
+    }
+
+    fn parse_sort(&self) -> Result<JunctionSort, AppError> {
+        match self.sort.as_deref() {
+            None | Some("id") => Ok(JunctionSort::Id),
+            Some("min_angle") => Ok(JunctionSort::MinAngle),
+            Some("elevation_diff") => Ok(JunctionSort::ElevationDiff),
+            Some("distance") => {
+                let (lat, lon) = self
+                    .parse_center()?
+                    .ok_or(AppError::BadRequest("sort=distance requires center"))?;
+                Ok(JunctionSort::Distance { lat, lon })
+            }
+            Some(_) => Err(AppError::BadRequest("Invalid sort")),
+        }
+    }
+
+    fn parse_sampling(&self) -> Result<Option<Sampling>, AppError> {
+        match self.sampling.as_deref() {
+            None => Ok(None),
+            Some("grid") => Ok(Some(Sampling::Grid)),
+            Some("sharpest") => Ok(Some(Sampling::Sharpest)),
+            Some("random") => Ok(Some(Sampling::Random)),
+            Some(_) => Err(AppError::BadRequest("Invalid sampling")),
+        }
+    }
+
     fn to_filter_params(&self) -> Result<FilterParams, AppError> {
         // limit のバリデーション
         if let Some(v) = self.limit {
@@ -131,6 +342,74 @@ impl JunctionsQuery {
             }
         }
 
+        // 範囲フィルタのバリデーション: 高低差・勾配は0以上、min <= max
+        let ranges = [
+            (
+                self.elevation_gte,
+                self.elevation_lte,
+                "elevation_gte must be <= elevation_lte",
+            ),
+            (
+                self.min_elevation_diff_gte,
+                self.min_elevation_diff_lte,
+                "min_elevation_diff_gte must be <= min_elevation_diff_lte",
+            ),
+            (
+                self.max_elevation_diff_gte,
+                self.max_elevation_diff_lte,
+                "max_elevation_diff_gte must be <= max_elevation_diff_lte",
+            ),
+            (
+                self.local_slope_gte,
+                self.local_slope_lte,
+                "local_slope_gte must be <= local_slope_lte",
+            ),
+        ];
+        for (min, max, message) in ranges {
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    return Err(AppError::BadRequest(message));
+                }
+            }
+        }
+
+        let non_negative = [
+            (
+                self.min_elevation_diff_lte,
+                "min_elevation_diff_lte must be >= 0",
+            ),
+            (
+                self.max_elevation_diff_lte,
+                "max_elevation_diff_lte must be >= 0",
+            ),
+            (self.local_slope_lte, "local_slope_lte must be >= 0"),
+        ];
+        for (value, message) in non_negative {
+            if value.is_some_and(|v| v < 0.0) {
+                return Err(AppError::BadRequest(message));
+            }
+        }
+
+        let sort = self.parse_sort()?;
+        let sampling = self.parse_sampling()?;
+        if sampling.is_some() && self.cursor.is_some() {
+            return Err(AppError::BadRequest("sampling cannot be used with cursor"));
+        }
+
+        let q = match self.q.as_deref().map(str::trim) {
+            None => None,
+            Some(q) if q.is_empty() || q.chars().count() > MAX_QUERY_CHARS => {
+                return Err(AppError::BadRequest("q must be 1 to 100 characters"));
+            }
+            Some(q) => Some(q.to_string()),
+        };
+
+        let after = self
+            .cursor
+            .as_deref()
+            .map(|token| cursor::decode(token, &sort))
+            .transpose()?;
+
         Ok(FilterParams {
             angle_type: self.parse_angle_types()?,
             min_angle_lt: self.min_angle_lt,
@@ -138,6 +417,23 @@ impl JunctionsQuery {
             limit: self.limit,
             min_angle_elevation_diff: self.min_angle_elevation_diff,
             max_angle_elevation_diff: self.max_angle_elevation_diff,
+            min_arm_grade: self.min_arm_grade,
+            elevation_gte: self.elevation_gte,
+            elevation_lte: self.elevation_lte,
+            min_elevation_diff_gte: self.min_elevation_diff_gte,
+            min_elevation_diff_lte: self.min_elevation_diff_lte,
+            max_elevation_diff_gte: self.max_elevation_diff_gte,
+            max_elevation_diff_lte: self.max_elevation_diff_lte,
+            local_slope_gte: self.local_slope_gte,
+            local_slope_lte: self.local_slope_lte,
+            include_bridges: self.include_bridges.unwrap_or(true),
+            include_tunnels: self.include_tunnels.unwrap_or(true),
+            sort,
+            center: self.parse_center()?,
+            after,
+            sampling,
+            area_code: self.area.clone(),
+            q,
         })
     }
 }
@@ -147,42 +443,300 @@ impl JunctionsQuery {
 pub struct StatsResponse {
     pub total_count: i64,
     pub by_type: HashMap<String, i64>,
+    pub histograms: StatsHistograms,
+}
+
+// GET /api/stats のヒストグラム
+#[derive(Serialize)]
+pub struct StatsHistograms {
+    pub min_angle: Vec<HistogramBin>,
+    pub elevation_diff: Vec<HistogramBin>,
+    pub highway_class: HashMap<String, i64>,
+}
+
+// GET /api/stats/elevation のレスポンス
+#[derive(Serialize)]
+pub struct ElevationStatsResponse {
+    pub total_count: i64,
+    pub with_elevation: i64,
+    pub unprocessed: i64,
+    pub by_source: HashMap<String, i64>,
+    pub by_quality: HashMap<String, i64>,
+    pub skipped: HashMap<String, i64>,
+    pub with_nodata_samples: i64,
+}
+
+// 検索範囲内のY字路を1ページ分取得して FeatureCollection にする
+async fn find_page(
+    repository: &dyn JunctionRepository,
+    area: &SearchArea,
+    query: &JunctionsQuery,
+) -> Result<serde_json::Value, AppError> {
+    let filters = query.to_filter_params()?;
+
+    let sort = filters.sort;
+
+    let page = repository.find_junctions(area, filters).await?;
+    let next_cursor = page.next_cursor.map(|next| cursor::encode(&sort, &next));
+
+    let mut feature_collection =
+        Junction::to_feature_collection(page.junctions, page.total_count, next_cursor);
+    feature_collection["sampled"] = serde_json::json!(page.sampled.is_some());
+    feature_collection["sampling"] = serde_json::json!(page.sampled.map(|s| s.as_str()));
+
+    Ok(feature_collection)
 }
 
 // ハンドラー: GET /api/junctions
 pub async fn get_junctions(
-    State(pool): State<PgPool>,
+    State(repository): State<SharedRepository>,
     Query(query): Query<JunctionsQuery>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let bbox = query.parse_bbox()?;
+    let area = query.parse_area()?;
+
+    let feature_collection = find_page(repository.as_ref(), &area, &query).await?;
+
+    Ok(Json(feature_collection))
+}
+
+// ハンドラー: GET /api/junctions/export
+// 条件に一致するすべてのY字路を、リポジトリから読みながら指定の形式で返す（limit なし）
+pub async fn export_junctions(
+    State(repository): State<SharedRepository>,
+    Query(export): Query<ExportQuery>,
+    Query(query): Query<JunctionsQuery>,
+) -> Result<Response, AppError> {
+    let format = match export.format.as_deref() {
+        None => ExportFormat::GeoJson,
+        Some(format) => {
+            ExportFormat::parse(format).ok_or(AppError::BadRequest("Invalid format"))?
+        }
+    };
+    // 条件に一致するすべての行を返すため、件数やページを指定するパラメータは受け付けない
+    query.reject_list_params("export", &["bbox", "center", "radius_m", "sort"])?;
+
+    let area = query.parse_area()?;
     let filters = query.to_filter_params()?;
 
-    let (junctions, total_count) = repository::find_by_bbox(&pool, bbox, filters).await?;
+    // 途中でデータベースエラーが起きた場合はレスポンスを中断する（export::body_stream）
+    let body = export::body_stream(format, repository.stream_junctions(area, filters));
 
-    let feature_collection = Junction::to_feature_collection(junctions, total_count);
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"y-junctions.{}\"",
+                    format.extension()
+                ),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+// ハンドラー: POST /api/junctions/search
+pub async fn search_junctions(
+    State(repository): State<SharedRepository>,
+    Json(request): Json<SearchRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if request.filters.bbox.is_some() || request.filters.radius_m.is_some() {
+        return Err(AppError::BadRequest(
+            "bbox and radius_m cannot be used with geometry",
+        ));
+    }
+
+    let geojson = geometry::parse_search_area(&request.geometry)?;
+    if !repository.is_valid_polygon(&geojson).await? {
+        return Err(AppError::BadRequest(
+            "geometry is not a valid polygon (e.g. self-intersecting)",
+        ));
+    }
+
+    let area = SearchArea::Polygon(geojson);
+    let feature_collection = find_page(repository.as_ref(), &area, &request.filters).await?;
 
     Ok(Json(feature_collection))
 }
 
+// ハンドラー: GET /api/junctions/nearest
+pub async fn get_nearest_junctions(
+    State(repository): State<SharedRepository>,
+    Query(nearest): Query<NearestQuery>,
+    Query(query): Query<JunctionsQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if !(-90.0..=90.0).contains(&nearest.lat) || !(-180.0..=180.0).contains(&nearest.lon) {
+        return Err(AppError::BadRequest("lat/lon out of valid range"));
+    }
+
+    let k = nearest.k.unwrap_or(DEFAULT_NEAREST_K);
+    if !(1..=MAX_NEAREST_K).contains(&k) {
+        return Err(AppError::BadRequest("k must be between 1 and 100"));
+    }
+    // 件数は k、並び順は距離、基準点は lat/lon で決まるため、一覧用のパラメータは受け付けない
+    query.reject_list_params("nearest", &[])?;
+
+    let filters = query.to_filter_params()?;
+
+    let junctions = repository
+        .find_nearest(nearest.lat, nearest.lon, k, filters)
+        .await?;
+    let count = junctions.len() as i64;
+
+    Ok(Json(Junction::to_feature_collection(
+        junctions,
+        Some(count),
+        None,
+    )))
+}
+
+// ハンドラー: GET /api/tiles/:z/:x/:y.mvt
+pub async fn get_tile(
+    State(repository): State<SharedRepository>,
+    Path((z, x, y)): Path<(u32, u32, String)>,
+    Query(query): Query<JunctionsQuery>,
+) -> Result<Response, AppError> {
+    let tile = TileCoord::from_path(z, x, &y)?;
+    // 範囲はタイル、件数は MAX_TILE_FEATURES で決まるため、一覧用のパラメータは受け付けない
+    // （無視するとパラメータ付きの URL で全件のタイルがキャッシュされる）
+    query.reject_list_params("tiles", &[])?;
+    let filters = query.to_filter_params()?;
+
+    let (min_lon, min_lat, max_lon, max_lat) = tile.bounds();
+    let area = SearchArea::Bbox(min_lon, min_lat, max_lon, max_lat);
+
+    let body = repository
+        .find_tile((tile.z, tile.x, tile.y), &area, &filters, MAX_TILE_FEATURES)
+        .await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/vnd.mapbox-vector-tile"),
+            (header::CACHE_CONTROL, TILE_CACHE_CONTROL),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+// ハンドラー: GET /api/junctions/clusters
+pub async fn get_junction_clusters(
+    State(repository): State<SharedRepository>,
+    Query(clusters): Query<ClustersQuery>,
+    Query(query): Query<JunctionsQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if clusters.zoom > tile::MAX_ZOOM {
+        return Err(AppError::BadRequest("zoom must be between 0 and 22"));
+    }
+    // セルは件数の多い順に MAX_CLUSTERS 件まで返し、ページングや間引きはしない
+    query.reject_list_params("clusters", &AREA_PARAMS)?;
+
+    let area = query.parse_area()?;
+    let filters = query.to_filter_params()?;
+    let cell_size_m = tile::cluster_cell_size_m(clusters.zoom);
+
+    let clusters = repository
+        .find_clusters(&area, &filters, cell_size_m, MAX_CLUSTERS)
+        .await?;
+
+    Ok(Json(JunctionCluster::to_feature_collection(
+        &clusters,
+        cell_size_m,
+    )))
+}
+
 // ハンドラー: GET /api/junctions/:id
 pub async fn get_junction_by_id(
-    State(pool): State<PgPool>,
+    State(repository): State<SharedRepository>,
     Path(id): Path<i64>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let junction = repository::find_by_id(&pool, id)
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let junction = repository.find_by_id(id).await?.ok_or(AppError::NotFound)?;
 
-    Ok(Json(junction.to_feature()))
+    Ok(Json(junction.to_detail_feature()))
 }
 
 // ハンドラー: GET /api/stats
-pub async fn get_stats(State(pool): State<PgPool>) -> Result<Json<StatsResponse>, AppError> {
-    let total_count = repository::count_total(&pool).await?;
-    let by_type = repository::count_by_type(&pool).await?;
+// GET /api/junctions と同じパラメータで絞り込む（bbox も center+radius_m もなければ全件）
+pub async fn get_stats(
+    State(repository): State<SharedRepository>,
+    Query(query): Query<JunctionsQuery>,
+) -> Result<Json<StatsResponse>, AppError> {
+    // 統計は条件に一致するすべてのY字路が対象のため、一覧用のパラメータは受け付けない
+    query.reject_list_params("stats", &AREA_PARAMS)?;
+
+    let area = query.parse_optional_area()?;
+    let filters = query.to_filter_params()?;
+
+    let stats = repository.junction_stats(area.as_ref(), &filters).await?;
 
     Ok(Json(StatsResponse {
-        total_count,
-        by_type,
+        total_count: stats.total_count,
+        by_type: stats.by_type,
+        histograms: StatsHistograms {
+            min_angle: stats.min_angle,
+            elevation_diff: stats.elevation_diff,
+            highway_class: stats.by_highway_class,
+        },
+    }))
+}
+
+// GET /api/areas, GET /api/areas/ranking のレスポンス
+#[derive(Serialize)]
+pub struct AreasResponse {
+    pub level: AdminLevel,
+    pub areas: Vec<AreaSummary>,
+}
+
+// ハンドラー: GET /api/areas
+pub async fn get_areas(
+    State(repository): State<SharedRepository>,
+    Query(query): Query<AreasQuery>,
+) -> Result<Json<AreasResponse>, AppError> {
+    let level = query.parse_level()?;
+    let areas = repository.list_areas(level).await?;
+
+    Ok(Json(AreasResponse { level, areas }))
+}
+
+// ハンドラー: GET /api/areas/ranking
+// 条件に一致するY字路が多い順に行政区域を返す（例: angle_type=verysharp で鋭いY字路が多い市区町村）
+pub async fn get_area_ranking(
+    State(repository): State<SharedRepository>,
+    Query(query): Query<AreasQuery>,
+    Query(filters): Query<JunctionsQuery>,
+) -> Result<Json<AreasResponse>, AppError> {
+    let level = query.parse_level()?;
+    // limit はランキングの件数として使い、並び順は件数順で固定
+    filters.reject_list_params("ranking", &["bbox", "center", "radius_m", "limit"])?;
+    let limit = filters.limit.unwrap_or(DEFAULT_RANKING_LIMIT);
+    if !(1..=MAX_RANKING_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest("limit must be between 1 and 100"));
+    }
+    let area = filters.parse_optional_area()?;
+    let filter_params = filters.to_filter_params()?;
+
+    let areas = repository
+        .rank_areas(level, area.as_ref(), &filter_params, limit)
+        .await?;
+
+    Ok(Json(AreasResponse { level, areas }))
+}
+
+// ハンドラー: GET /api/stats/elevation
+pub async fn get_elevation_stats(
+    State(repository): State<SharedRepository>,
+) -> Result<Json<ElevationStatsResponse>, AppError> {
+    let coverage = repository.elevation_coverage().await?;
+
+    Ok(Json(ElevationStatsResponse {
+        total_count: coverage.total_count,
+        with_elevation: coverage.with_elevation,
+        unprocessed: coverage.unprocessed,
+        by_source: coverage.by_source,
+        by_quality: coverage.by_quality,
+        skipped: coverage.by_skip_reason,
+        with_nodata_samples: coverage.with_nodata_samples,
     }))
 }
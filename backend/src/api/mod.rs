@@ -1,2 +1,6 @@
+pub mod cursor;
+pub mod export;
+pub mod geometry;
 pub mod handlers;
 pub mod routes;
+pub mod tile;
@@ -1,12 +1,71 @@
-use axum::{routing::get, Router};
+use anyhow::Context;
+use axum::{
+    extract::DefaultBodyLimit,
+    http::HeaderValue,
+    routing::{get, post},
+    Router,
+};
 use sqlx::PgPool;
+use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
 use super::handlers;
+use crate::db::{PgRepository, SharedRepository};
+
+// POST /api/junctions/search のリクエストボディの上限（頂点数の上限に見合う大きさ）
+const SEARCH_BODY_LIMIT_BYTES: usize = 1024 * 1024;
 
 pub fn create_router(pool: PgPool) -> Router {
+    create_router_with(Arc::new(PgRepository::new(pool)))
+}
+
+/// Router serving the API from any repository (PostgreSQL, a dataset file loaded in memory, ...)
+pub fn create_router_with(repository: SharedRepository) -> Router {
     Router::new()
         .route("/api/junctions", get(handlers::get_junctions))
+        .route(
+            "/api/junctions/nearest",
+            get(handlers::get_nearest_junctions),
+        )
+        .route("/api/junctions/export", get(handlers::export_junctions))
+        .route(
+            "/api/junctions/clusters",
+            get(handlers::get_junction_clusters),
+        )
+        .route(
+            "/api/junctions/search",
+            post(handlers::search_junctions).layer(DefaultBodyLimit::max(SEARCH_BODY_LIMIT_BYTES)),
+        )
         .route("/api/junctions/:id", get(handlers::get_junction_by_id))
+        // axum 0.7 ではパラメータの後ろに拡張子を書けないため、y は ".mvt" 付きで受け取る
+        .route("/api/tiles/:z/:x/:y", get(handlers::get_tile))
+        .route("/api/areas", get(handlers::get_areas))
+        .route("/api/areas/ranking", get(handlers::get_area_ranking))
         .route("/api/stats", get(handlers::get_stats))
-        .with_state(pool)
+        .route("/api/stats/elevation", get(handlers::get_elevation_stats))
+        .with_state(repository)
+}
+
+/// CORS layer allowing `origins`; `*` allows any origin
+pub fn cors_layer(origins: &[String]) -> anyhow::Result<CorsLayer> {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        if origins.len() > 1 {
+            anyhow::bail!("\"*\" cannot be combined with other origins");
+        }
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .with_context(|| format!("Invalid CORS origin: {:?}", origin))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(Any)
+        .allow_headers(Any))
 }
@@ -0,0 +1,119 @@
+//! Tile coordinates for `GET /api/tiles/{z}/{x}/{y}.mvt`
+
+use super::handlers::AppError;
+
+/// Highest zoom level served
+pub const MAX_ZOOM: u32 = 22;
+
+/// Circumference of the Earth in Web Mercator meters (the width of the zoom 0 tile)
+const WEB_MERCATOR_WORLD_M: f64 = 40_075_016.685_578_49;
+
+/// Clusters per tile side, i.e. 64px cells on 256px tiles
+const CLUSTER_CELLS_PER_TILE: f64 = 4.0;
+
+/// Side of the grid cells used to cluster junctions at a zoom level, in Web Mercator meters
+pub fn cluster_cell_size_m(zoom: u32) -> f64 {
+    WEB_MERCATOR_WORLD_M / (1u64 << zoom) as f64 / CLUSTER_CELLS_PER_TILE
+}
+
+/// An XYZ tile in the Web Mercator tiling scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileCoord {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileCoord {
+    /// Parses the path segments of a tile request, where `y` carries the `.mvt` extension
+    ///
+    /// Returns NotFound for other extensions and BadRequest for coordinates outside the
+    /// tiling scheme.
+    pub fn from_path(z: u32, x: u32, y: &str) -> Result<Self, AppError> {
+        let y: u32 = y
+            .strip_suffix(".mvt")
+            .ok_or(AppError::NotFound)?
+            .parse()
+            .map_err(|_| AppError::BadRequest("Invalid tile coordinates"))?;
+
+        if z > MAX_ZOOM {
+            return Err(AppError::BadRequest("Invalid tile coordinates"));
+        }
+        let tiles = 1u64 << z;
+        if x as u64 >= tiles || y as u64 >= tiles {
+            return Err(AppError::BadRequest("Invalid tile coordinates"));
+        }
+
+        Ok(TileCoord { z, x, y })
+    }
+
+    /// Returns the bounds of the tile as (min_lon, min_lat, max_lon, max_lat)
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        let tiles = (1u64 << self.z) as f64;
+        let lon = |x: f64| x / tiles * 360.0 - 180.0;
+        let lat = |y: f64| {
+            let n = std::f64::consts::PI * (1.0 - 2.0 * y / tiles);
+            n.sinh().atan().to_degrees()
+        };
+
+        (
+            lon(self.x as f64),
+            lat(self.y as f64 + 1.0),
+            lon(self.x as f64 + 1.0),
+            lat(self.y as f64),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            TileCoord::from_path(15, 29105, "12903.mvt").unwrap(),
+            TileCoord {
+                z: 15,
+                x: 29105,
+                y: 12903
+            }
+        );
+        assert!(matches!(
+            TileCoord::from_path(15, 29105, "12903.png"),
+            Err(AppError::NotFound)
+        ));
+        assert!(matches!(
+            TileCoord::from_path(1, 2, "0.mvt"),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            TileCoord::from_path(23, 0, "0.mvt"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_cluster_cell_size() {
+        assert!((cluster_cell_size_m(0) - 10_018_754.17).abs() < 0.01);
+        assert!((cluster_cell_size_m(14) - 611.50).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let (min_lon, min_lat, max_lon, max_lat) = TileCoord { z: 0, x: 0, y: 0 }.bounds();
+        assert_eq!((min_lon, max_lon), (-180.0, 180.0));
+        assert!((max_lat - 85.0511287798).abs() < 1e-9);
+        assert!((min_lat + 85.0511287798).abs() < 1e-9);
+
+        // 東京駅を含むタイル
+        let (min_lon, min_lat, max_lon, max_lat) = TileCoord {
+            z: 15,
+            x: 29105,
+            y: 12903,
+        }
+        .bounds();
+        assert!(min_lon <= 139.767125 && 139.767125 < max_lon);
+        assert!(min_lat <= 35.681236 && 35.681236 < max_lat);
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use clap::Subcommand;
+use y_junction_backend::config::Config;
+use y_junction_backend::db::migrations;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Apply the pending migrations
+    Migrate,
+    /// Show which migrations are applied, and fail if the database is behind the code
+    Status,
+    /// Drop every table (all junctions and areas are lost) and migrate from scratch
+    Reset {
+        /// Confirm that the data may be deleted
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+pub async fn run(config: Config, args: Args) -> Result<()> {
+    if let Command::Reset { yes: false } = args.command {
+        anyhow::bail!("db reset deletes all data; pass --yes to confirm");
+    }
+
+    let pool = config.database.connect().await?;
+
+    match args.command {
+        Command::Migrate => {
+            let applied = migrations::migrate(&pool).await?;
+            println!("Applied {} migrations", applied);
+        }
+        Command::Status => {
+            let status = migrations::schema_status(&pool).await?;
+            print_status(&status);
+            // 最新でなければ終了コードで知らせる
+            status.check()?;
+            println!("Schema is up to date");
+        }
+        Command::Reset { .. } => {
+            migrations::reset(&pool).await?;
+            println!("Database reset and migrated");
+        }
+    }
+
+    Ok(())
+}
+
+pub fn print_status(status: &migrations::SchemaStatus) {
+    for migration in &status.migrations {
+        println!(
+            "{:>4}  {:<8}  {}",
+            migration.version,
+            migration.state.as_str(),
+            migration.description
+        );
+    }
+    for version in &status.unknown {
+        println!("{:>4}  {:<8}  (not in this build)", version, "unknown");
+    }
+    println!(
+        "Database version: {}, expected: {}",
+        status
+            .current_version()
+            .map_or_else(|| "none".to_string(), |v| v.to_string()),
+        status
+            .expected_version()
+            .map_or_else(|| "none".to_string(), |v| v.to_string())
+    );
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use clap::{Subcommand, ValueEnum};
+use std::path::PathBuf;
+use y_junction_backend::config::Config;
+use y_junction_backend::db::migrations;
+use y_junction_backend::importer::elevation_source::ElevationFormat;
+use y_junction_backend::importer::ElevationImportOptions;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// GSI JPGIS XML files or zip archives
+    Jpgis,
+    /// GSI elevation PNG tiles laid out as {z}/{x}/{y}.png
+    Png,
+}
+
+impl From<Format> for ElevationFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Jpgis => ElevationFormat::Jpgis,
+            Format::Png => ElevationFormat::PngTiles,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    import: ImportArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert GSI DEM XML into a compact binary file for fast elevation import
+    Compile(CompileArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct CompileArgs {
+    /// Directory containing GSI XML files or zip archives, overriding elevation.dir
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    /// Output file (e.g., data/gsi.ydem), overriding elevation.compile_output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportArgs {
+    /// Directory containing elevation data (e.g., GSI XML files), overriding elevation.dir
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    /// Format of the elevation data, overriding elevation.format
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Memory budget for parsed DEM tiles in MiB, overriding elevation.cache_mb
+    #[arg(long)]
+    cache_mb: Option<usize>,
+
+    /// Number of worker threads, overriding elevation.threads (defaults to the number of CPUs)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Number of junctions fetched and committed per batch, overriding elevation.batch_size
+    #[arg(long)]
+    batch_size: Option<i64>,
+
+    /// Only enrich junctions that do not have elevation yet
+    #[arg(long)]
+    only_missing: bool,
+
+    /// Resume after the given junction id (the last committed id from a previous run)
+    #[arg(long)]
+    resume_after_id: Option<i64>,
+
+    /// Apply pending database migrations before importing
+    #[arg(long)]
+    migrate: bool,
+}
+
+pub async fn run(config: Config, args: Args) -> Result<()> {
+    match args.command {
+        Some(Command::Compile(compile_args)) => compile(config, compile_args).await,
+        None => import(config, args.import).await,
+    }
+}
+
+async fn import(config: Config, args: ImportArgs) -> Result<()> {
+    let elevation = config.elevation;
+    let dir = args
+        .dir
+        .or(elevation.dir)
+        .context("--dir (or elevation.dir) must be set")?;
+    let dir = dir
+        .to_str()
+        .context("Elevation directory must be valid UTF-8")?;
+
+    tracing::info!("Starting elevation import process");
+    tracing::info!("Elevation directory: {}", dir);
+
+    let pool = config.database.connect().await?;
+    migrations::prepare_schema(&pool, args.migrate).await?;
+
+    let options = ElevationImportOptions {
+        format: args.format.map_or(elevation.format, Into::into),
+        cache_capacity_bytes: args.cache_mb.unwrap_or(elevation.cache_mb) * 1024 * 1024,
+        threads: args.threads.or(elevation.threads),
+        batch_size: args.batch_size.unwrap_or(elevation.batch_size),
+        only_missing: args.only_missing,
+        resume_after_id: args.resume_after_id,
+    };
+
+    // Import elevation data
+    let count = y_junction_backend::importer::import_elevation_data(&pool, dir, &options).await?;
+
+    tracing::info!(
+        "Elevation import process completed: {} junctions updated",
+        count
+    );
+
+    Ok(())
+}
+
+async fn compile(config: Config, args: CompileArgs) -> Result<()> {
+    let elevation = config.elevation;
+    let dir = match args.dir {
+        Some(dir) => dir,
+        None => {
+            let dir = elevation
+                .dir
+                .context("--dir (or elevation.dir) must be set")?;
+            // .ydem に変換できるのは JPGIS XML だけなので、インポート用の elevation.dir は
+            // JPGIS のときだけ使う（--dir で指定した場合は形式の設定によらない）
+            if elevation.format != ElevationFormat::Jpgis {
+                anyhow::bail!(
+                    "elevation.dir is not a JPGIS XML directory (elevation.format is not \"jpgis\"); pass one with --dir"
+                );
+            }
+            dir
+        }
+    };
+    let dir = dir
+        .to_str()
+        .context("Elevation directory must be valid UTF-8")?
+        .to_string();
+    let output = args
+        .output
+        .or(elevation.compile_output)
+        .context("--output (or elevation.compile_output) must be set")?;
+
+    tracing::info!("Compiling DEM tiles from {}", dir);
+
+    // XMLのパースは重いので非同期ランタイムのスレッドを塞がないようにする
+    let summary = tokio::task::spawn_blocking({
+        let output = output.clone();
+        move || y_junction_backend::importer::compiled_dem::compile(&dir, &output)
+    })
+    .await??;
+
+    tracing::info!(
+        "Wrote {} tiles ({:.1} MiB) to {:?}, skipped {} unreadable tiles",
+        summary.tiles,
+        summary.bytes as f64 / (1024.0 * 1024.0),
+        output,
+        summary.skipped
+    );
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use futures::StreamExt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use y_junction_backend::config::{parse_bbox, Config};
+use y_junction_backend::dataset::flatgeobuf::FlatGeobufWriter;
+use y_junction_backend::dataset::geopackage::GeoPackageWriter;
+use y_junction_backend::dataset::pmtiles::PmTilesWriter;
+use y_junction_backend::dataset::DatasetFormat;
+use y_junction_backend::db::migrations;
+use y_junction_backend::db::repository::{self, FilterParams, SearchArea};
+use y_junction_backend::domain::AngleType;
+
+// 進捗を出す間隔（件）
+const PROGRESS_INTERVAL: usize = 100_000;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// FlatGeobuf with a spatial index (.fgb)
+    Fgb,
+    /// GeoPackage (.gpkg)
+    Gpkg,
+    /// PMTiles archive of vector tiles (.pmtiles)
+    Pmtiles,
+}
+
+impl From<Format> for DatasetFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Fgb => DatasetFormat::FlatGeobuf,
+            Format::Gpkg => DatasetFormat::GeoPackage,
+            Format::Pmtiles => DatasetFormat::PmTiles,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Output file, replaced if it exists
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Output format (defaults to the one matching the output file extension)
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Only export junctions in the bounding box: min_lon,min_lat,max_lon,max_lat
+    #[arg(short, long)]
+    bbox: Option<String>,
+
+    /// Only export these angle types (comma separated: verysharp,sharp,normal)
+    #[arg(long)]
+    angle_type: Option<String>,
+
+    /// Only export junctions whose minimum angle is less than this (degrees)
+    #[arg(long)]
+    min_angle_lt: Option<i16>,
+
+    /// Only export junctions in the prefecture, city or ward with this code
+    #[arg(long)]
+    area: Option<String>,
+
+    /// Only export junctions on roads whose name contains this text
+    #[arg(long)]
+    q: Option<String>,
+
+    /// Lowest zoom level of the vector tiles (PMTiles only)
+    #[arg(long, default_value_t = 4)]
+    min_zoom: u8,
+
+    /// Highest zoom level of the vector tiles; every junction is included at this zoom (PMTiles only)
+    #[arg(long, default_value_t = 14)]
+    max_zoom: u8,
+}
+
+fn parse_angle_types(types: &str) -> Result<Vec<AngleType>> {
+    types
+        .split(',')
+        .map(|s| {
+            let s = s.trim();
+            AngleType::parse(s).with_context(|| format!("Invalid angle type: {}", s))
+        })
+        .collect()
+}
+
+pub async fn run(config: Config, args: Args) -> Result<()> {
+    let format = match args.format {
+        Some(format) => format.into(),
+        None => DatasetFormat::from_path(&args.output)
+            .context("Cannot tell the format from the output file extension; use --format")?,
+    };
+    if args.min_zoom > args.max_zoom || args.max_zoom > 16 {
+        anyhow::bail!("Zoom levels must satisfy min_zoom <= max_zoom <= 16");
+    }
+
+    let area = match &args.bbox {
+        Some(bbox) => {
+            let [min_lon, min_lat, max_lon, max_lat] = parse_bbox(bbox)?;
+            SearchArea::Bbox(min_lon, min_lat, max_lon, max_lat)
+        }
+        None => SearchArea::Everywhere,
+    };
+    let filters = FilterParams {
+        angle_type: args
+            .angle_type
+            .as_deref()
+            .map(parse_angle_types)
+            .transpose()?,
+        min_angle_lt: args.min_angle_lt,
+        area_code: args.area.clone(),
+        q: args.q.clone(),
+        include_bridges: true,
+        include_tunnels: true,
+        ..Default::default()
+    };
+
+    let pool = config.database.connect().await?;
+    migrations::prepare_schema(&pool, false).await?;
+
+    tracing::info!(
+        "Exporting Y-junctions as {:?} to {}",
+        format,
+        args.output.display()
+    );
+    let mut junctions = repository::stream_junctions(pool, area, filters);
+    let mut count = 0;
+
+    match format {
+        DatasetFormat::FlatGeobuf => {
+            let mut writer = FlatGeobufWriter::new("y_junctions");
+            while let Some(junction) = junctions.next().await {
+                writer.add(&junction?);
+                count += 1;
+                log_progress(count);
+            }
+            tracing::info!("Writing {} features with a spatial index...", writer.len());
+            writer.finish(BufWriter::new(File::create(&args.output)?))?;
+        }
+        DatasetFormat::GeoPackage => {
+            let mut writer = GeoPackageWriter::create(&args.output).await?;
+            while let Some(junction) = junctions.next().await {
+                writer.add(&junction?).await?;
+                count += 1;
+                log_progress(count);
+            }
+            writer.finish().await?;
+        }
+        DatasetFormat::PmTiles => {
+            let mut writer = PmTilesWriter::new(args.min_zoom, args.max_zoom);
+            while let Some(junction) = junctions.next().await {
+                writer.add(&junction?);
+                count += 1;
+                log_progress(count);
+            }
+            tracing::info!(
+                "Building vector tiles for zoom {}-{}...",
+                args.min_zoom,
+                args.max_zoom
+            );
+            writer.finish(BufWriter::new(File::create(&args.output)?))?;
+        }
+    }
+
+    tracing::info!(
+        "Export completed: {} junctions written to {}",
+        count,
+        args.output.display()
+    );
+
+    Ok(())
+}
+
+fn log_progress(count: usize) {
+    if count.is_multiple_of(PROGRESS_INTERVAL) {
+        tracing::info!("{} junctions read", count);
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use y_junction_backend::config::{parse_bbox, Config};
+use y_junction_backend::db::migrations;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Path to OSM PBF file, overriding import.input
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+
+    /// Bounding box: min_lon,min_lat,max_lon,max_lat, overriding import.bbox
+    #[arg(short, long)]
+    bbox: Option<String>,
+
+    /// Skip junctions whose minimum angle is at least this (degrees), overriding import.max_min_angle
+    #[arg(long)]
+    max_min_angle: Option<i16>,
+
+    /// Skip extracting prefecture/city/ward boundaries and assigning them to junctions
+    #[arg(long)]
+    skip_admin_areas: bool,
+
+    /// Apply pending database migrations before importing
+    #[arg(long)]
+    migrate: bool,
+}
+
+pub async fn run(config: Config, args: Args) -> Result<()> {
+    let import = config.import;
+    let input = args
+        .input
+        .or(import.input)
+        .context("--input (or import.input) must be set")?;
+    let input = input.to_str().context("Input path must be valid UTF-8")?;
+    let bbox = args
+        .bbox
+        .or(import.bbox)
+        .context("--bbox (or import.bbox) must be set")?;
+    let max_min_angle = args.max_min_angle.unwrap_or(import.max_min_angle);
+    let skip_admin_areas = args.skip_admin_areas || import.skip_admin_areas;
+
+    tracing::info!("Starting import process");
+    tracing::info!("Input file: {}", input);
+    tracing::info!("Bounding box: {}", bbox);
+
+    let [min_lon, min_lat, max_lon, max_lat] = parse_bbox(&bbox)?;
+
+    let pool = config.database.connect().await?;
+    migrations::prepare_schema(&pool, args.migrate).await?;
+
+    // Import OSM data from PBF
+    let count = y_junction_backend::importer::import_osm_data(
+        &pool,
+        input,
+        min_lon,
+        min_lat,
+        max_lon,
+        max_lat,
+        max_min_angle,
+    )
+    .await?;
+
+    if !skip_admin_areas {
+        y_junction_backend::importer::import_admin_areas(&pool, input).await?;
+    }
+
+    tracing::info!("Import process completed: {} junctions imported", count);
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use y_junction_backend::config::Config;
+
+mod db;
+mod elevation;
+mod export;
+mod import;
+mod serve;
+mod validate;
+
+#[derive(Parser, Debug)]
+#[command(name = "yjunction")]
+#[command(about = "Y-junction API server and data tools")]
+struct Cli {
+    /// TOML config file (defaults to ./yjunction.toml if it exists)
+    #[arg(long, global = true, env = "YJUNCTION_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// PostgreSQL URL, overriding database.url and DATABASE_URL
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+
+    /// Size of the database connection pool, overriding database.max_connections
+    #[arg(long, global = true)]
+    max_connections: Option<u32>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve the API from the database or a dataset file
+    Serve(serve::Args),
+    /// Import Y-junctions from an OSM PBF file
+    Import(import::Args),
+    /// Import elevation data for existing Y-junctions (`compile` converts GSI XML first)
+    Elevation(elevation::Args),
+    /// Export Y-junctions to an offline dataset (FlatGeobuf, GeoPackage or PMTiles)
+    Export(export::Args),
+    /// Check the config (and the database schema unless --offline) without running anything
+    Validate(validate::Args),
+    /// Manage the database schema with the migrations embedded in this build
+    Db(db::Args),
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // .envファイル読み込み（設定ファイルより環境変数が優先されるので先に読む）
+    dotenvy::dotenv().ok();
+
+    // ログ初期化
+    tracing_subscriber::fmt::init();
+
+    let cli = Cli::parse();
+
+    // 既定値 < 設定ファイル < 環境変数 < コマンドライン引数 の順に上書きする
+    let mut config = Config::load(cli.config.as_deref())?;
+    if let Some(url) = cli.database_url {
+        config.database.url = Some(url);
+    }
+    if let Some(max_connections) = cli.max_connections {
+        config.database.max_connections = max_connections;
+    }
+
+    match cli.command {
+        Command::Serve(args) => serve::run(config, args).await,
+        Command::Import(args) => import::run(config, args).await,
+        Command::Elevation(args) => elevation::run(config, args).await,
+        Command::Export(args) => export::run(config, args).await,
+        Command::Validate(args) => validate::run(config, args).await,
+        Command::Db(args) => db::run(config, args).await,
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use axum::{routing::get, Router};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use y_junction_backend::api;
+use y_junction_backend::config::Config;
+use y_junction_backend::dataset;
+use y_junction_backend::db::{migrations, MemoryRepository};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Address to listen on, overriding server.bind
+    #[arg(long)]
+    bind: Option<SocketAddr>,
+
+    /// Allowed CORS origin (repeatable), overriding server.cors_origins; `*` allows any
+    #[arg(long = "cors-origin")]
+    cors_origins: Vec<String>,
+
+    /// Serve this dataset file read-only instead of the database, overriding server.dataset_path
+    #[arg(long)]
+    dataset: Option<PathBuf>,
+
+    /// Apply pending database migrations before serving
+    #[arg(long)]
+    migrate: bool,
+}
+
+pub async fn run(mut config: Config, args: Args) -> Result<()> {
+    if let Some(bind) = args.bind {
+        config.server.bind = bind;
+    }
+    if !args.cors_origins.is_empty() {
+        config.server.cors_origins = args.cors_origins;
+    }
+    if args.dataset.is_some() {
+        config.server.dataset_path = args.dataset;
+    }
+    let cors = api::routes::cors_layer(&config.server.cors_origins)?;
+
+    // dataset_path があればデータベースを使わず、ファイルを読み込んでメモリ上から配信する
+    let api_router = match &config.server.dataset_path {
+        Some(path) => {
+            tracing::info!("Loading dataset from {}", path.display());
+            let junctions = dataset::read_junctions(path).await?;
+            let repository = MemoryRepository::new(junctions);
+            tracing::info!(
+                "Serving {} junctions read-only (no database)",
+                repository.len()
+            );
+            api::routes::create_router_with(Arc::new(repository))
+        }
+        None => {
+            let pool = config.database.connect().await?;
+
+            // migrate_on_startup なら未適用のマイグレーションを適用してから起動する
+            let migrate = args.migrate || config.database.migrate_on_startup;
+            migrations::prepare_schema(&pool, migrate).await?;
+
+            api::routes::create_router(pool)
+        }
+    };
+
+    let app = Router::new()
+        .route("/", get(|| async { "Y-Junction API" }))
+        .route("/health", get(|| async { "OK" }))
+        .merge(api_router)
+        .layer(cors);
+
+    // サーバー起動
+    let listener = tokio::net::TcpListener::bind(config.server.bind).await?;
+
+    tracing::info!("Server listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
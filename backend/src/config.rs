@@ -0,0 +1,416 @@
+//! Settings shared by the `yjunction` subcommands
+//!
+//! Values are layered: built-in defaults, then the TOML config file, then environment
+//! variables, then command line flags (applied by each subcommand).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use crate::api::routes::cors_layer;
+use crate::importer::elevation_source::ElevationFormat;
+use crate::importer::parser::DEFAULT_MAX_MIN_ANGLE;
+
+/// Config file read from the working directory when no path is given
+pub const DEFAULT_CONFIG_FILE: &str = "yjunction.toml";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    pub import: ImportConfig,
+    pub elevation: ElevationConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// PostgreSQL URL (`DATABASE_URL`)
+    pub url: Option<String>,
+    /// Size of the connection pool (`DATABASE_MAX_CONNECTIONS`)
+    pub max_connections: u32,
+    /// Apply pending migrations before serving (`MIGRATE_ON_STARTUP`)
+    pub migrate_on_startup: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 5,
+            migrate_on_startup: false,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub async fn connect(&self) -> Result<PgPool> {
+        let url = self
+            .url
+            .as_deref()
+            .context("database.url (or DATABASE_URL) must be set")?;
+
+        tracing::info!("Connecting to database...");
+        let pool = PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .connect(url)
+            .await
+            .context("Failed to connect to database")?;
+        tracing::info!("Database connection established");
+
+        Ok(pool)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address the API listens on (`BIND_ADDRESS`)
+    pub bind: SocketAddr,
+    /// Origins allowed by CORS, `*` for any (`CORS_ORIGINS`, comma separated)
+    pub cors_origins: Vec<String>,
+    /// Serve this dataset file from memory instead of the database (`DATASET_PATH`)
+    pub dataset_path: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            cors_origins: vec!["*".to_string()],
+            dataset_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportConfig {
+    /// OSM PBF file
+    pub input: Option<PathBuf>,
+    /// min_lon,min_lat,max_lon,max_lat
+    pub bbox: Option<String>,
+    /// Junctions whose minimum angle is at least this (degrees) are skipped as T-junctions
+    pub max_min_angle: i16,
+    pub skip_admin_areas: bool,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            input: None,
+            bbox: None,
+            max_min_angle: DEFAULT_MAX_MIN_ANGLE,
+            skip_admin_areas: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ElevationConfig {
+    /// Directory of elevation data (or a compiled `.ydem` file)
+    pub dir: Option<PathBuf>,
+    pub format: ElevationFormat,
+    /// Memory budget for parsed DEM tiles in MiB
+    pub cache_mb: usize,
+    /// Number of junctions fetched and committed per batch
+    pub batch_size: i64,
+    /// Number of worker threads (defaults to the number of CPUs)
+    pub threads: Option<usize>,
+    /// File written by `elevation compile` (`.ydem`)
+    pub compile_output: Option<PathBuf>,
+}
+
+impl Default for ElevationConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            format: ElevationFormat::default(),
+            cache_mb: 256,
+            batch_size: 10_000,
+            threads: None,
+            compile_output: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file (`path`, or [`DEFAULT_CONFIG_FILE`] if it exists) and applies
+    /// the environment variables on top of it
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Overrides the settings that have an environment variable set
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(url) = var("DATABASE_URL") {
+            self.database.url = Some(url);
+        }
+        if let Some(value) = var("DATABASE_MAX_CONNECTIONS") {
+            self.database.max_connections = value
+                .parse()
+                .context("DATABASE_MAX_CONNECTIONS must be a number")?;
+        }
+        if let Some(value) = var("MIGRATE_ON_STARTUP") {
+            self.database.migrate_on_startup = match value.as_str() {
+                "1" | "true" => true,
+                "" | "0" | "false" => false,
+                _ => anyhow::bail!("MIGRATE_ON_STARTUP must be true or false"),
+            };
+        }
+        if let Some(value) = var("BIND_ADDRESS") {
+            self.server.bind = value
+                .parse()
+                .context("BIND_ADDRESS must be an address such as 0.0.0.0:8080")?;
+        }
+        if let Some(value) = var("CORS_ORIGINS") {
+            self.server.cors_origins = value
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+        if let Some(path) = var("DATASET_PATH") {
+            self.server.dataset_path = Some(PathBuf::from(path));
+        }
+
+        Ok(())
+    }
+
+    /// Problems that would make a subcommand fail, empty when the config is usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check_path = |key: &str, path: &Option<PathBuf>| {
+            if let Some(path) = path {
+                if !path.exists() {
+                    problems.push(format!("{}: {} does not exist", key, path.display()));
+                }
+            }
+        };
+        check_path("server.dataset_path", &self.server.dataset_path);
+        check_path("import.input", &self.import.input);
+        check_path("elevation.dir", &self.elevation.dir);
+
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if let Err(err) = cors_layer(&self.server.cors_origins) {
+            problems.push(format!("server.cors_origins: {}", err));
+        }
+        if let Some(Err(err)) = self.import.bbox.as_deref().map(parse_bbox) {
+            problems.push(format!("import.bbox: {}", err));
+        }
+        if !(1..=180).contains(&self.import.max_min_angle) {
+            problems.push("import.max_min_angle must be between 1 and 180".to_string());
+        }
+        if self.elevation.cache_mb == 0 {
+            problems.push("elevation.cache_mb must be at least 1".to_string());
+        }
+        if self.elevation.batch_size < 1 {
+            problems.push("elevation.batch_size must be at least 1".to_string());
+        }
+        if self.elevation.threads == Some(0) {
+            problems.push("elevation.threads must be at least 1".to_string());
+        }
+
+        problems
+    }
+}
+
+/// Why a bbox was rejected by `parse_bbox`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BboxError {
+    Format,
+    Coordinates,
+    Range,
+    OutOfRange,
+}
+
+impl BboxError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Format => "bbox must be in format: min_lon,min_lat,max_lon,max_lat",
+            Self::Coordinates => "Invalid bbox coordinates",
+            Self::Range => "Invalid bbox range",
+            Self::OutOfRange => "bbox out of valid range",
+        }
+    }
+}
+
+impl std::fmt::Display for BboxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for BboxError {}
+
+/// Parses `min_lon,min_lat,max_lon,max_lat`, rejecting inverted boxes and coordinates
+/// outside the valid longitude/latitude ranges
+pub fn parse_bbox(bbox: &str) -> Result<[f64; 4], BboxError> {
+    let parts: Vec<&str> = bbox.split(',').collect();
+    let [min_lon, min_lat, max_lon, max_lat] = parts[..] else {
+        return Err(BboxError::Format);
+    };
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<f64>()
+            .map_err(|_| BboxError::Coordinates)
+    };
+    let (min_lon, min_lat, max_lon, max_lat) = (
+        parse(min_lon)?,
+        parse(min_lat)?,
+        parse(max_lon)?,
+        parse(max_lat)?,
+    );
+
+    if min_lon >= max_lon || min_lat >= max_lat {
+        return Err(BboxError::Range);
+    }
+    if min_lon < -180.0 || max_lon > 180.0 || min_lat < -90.0 || max_lat > 90.0 {
+        return Err(BboxError::OutOfRange);
+    }
+
+    Ok([min_lon, min_lat, max_lon, max_lat])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::default();
+        assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.server.bind.to_string(), "0.0.0.0:8080");
+        assert_eq!(config.server.cors_origins, vec!["*"]);
+        assert_eq!(config.import.max_min_angle, 60);
+        assert_eq!(config.elevation.format, ElevationFormat::Jpgis);
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let config: Config = toml::from_str(
+            r#"
+            [database]
+            url = "postgres://localhost/y_junction"
+            max_connections = 10
+
+            [server]
+            bind = "127.0.0.1:3000"
+            cors_origins = ["https://example.com"]
+
+            [import]
+            bbox = "132.0,32.7,134.8,34.5"
+            max_min_angle = 50
+
+            [elevation]
+            format = "png"
+            compile_output = "/data/gsi.ydem"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.database.url.as_deref(),
+            Some("postgres://localhost/y_junction")
+        );
+        assert_eq!(config.database.max_connections, 10);
+        assert!(!config.database.migrate_on_startup);
+        assert_eq!(config.server.bind.port(), 3000);
+        assert_eq!(config.import.max_min_angle, 50);
+        assert!(!config.import.skip_admin_areas);
+        assert_eq!(config.elevation.format, ElevationFormat::PngTiles);
+        assert_eq!(config.elevation.cache_mb, 256);
+        assert_eq!(
+            config.elevation.compile_output,
+            Some(PathBuf::from("/data/gsi.ydem"))
+        );
+
+        // 同梱の設定例も読み込めること
+        let example: Config = toml::from_str(include_str!("../yjunction.example.toml")).unwrap();
+        assert!(example.database.url.is_some());
+
+        // 綴り間違いは無視せずエラーにする
+        assert!(toml::from_str::<Config>("[server]\nport = 8080").is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config: Config =
+            toml::from_str("[database]\nurl = \"postgres://file\"\nmax_connections = 2").unwrap();
+        let env: HashMap<&str, &str> = [
+            ("DATABASE_URL", "postgres://env"),
+            ("MIGRATE_ON_STARTUP", "true"),
+            ("CORS_ORIGINS", "https://a.example, https://b.example"),
+        ]
+        .into();
+        config
+            .apply_env(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.database.url.as_deref(), Some("postgres://env"));
+        assert_eq!(config.database.max_connections, 2);
+        assert!(config.database.migrate_on_startup);
+        assert_eq!(
+            config.server.cors_origins,
+            vec!["https://a.example", "https://b.example"]
+        );
+
+        let err = config
+            .apply_env(|name| (name == "BIND_ADDRESS").then(|| "localhost".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("BIND_ADDRESS"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = Config::default();
+        config.database.max_connections = 0;
+        config.server.cors_origins = vec!["bad\norigin".to_string()];
+        config.import.bbox = Some("1,2,3".to_string());
+        config.import.max_min_angle = 0;
+        config.elevation.dir = Some(PathBuf::from("/nonexistent/elevation"));
+
+        let problems = config.validate();
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+        assert!(problems[0].starts_with("elevation.dir"));
+    }
+
+    #[test]
+    fn test_parse_bbox() {
+        assert_eq!(
+            parse_bbox("132.0, 32.7,134.8,34.5").unwrap(),
+            [132.0, 32.7, 134.8, 34.5]
+        );
+        assert_eq!(parse_bbox("132.0,32.7,134.8"), Err(BboxError::Format));
+        assert_eq!(parse_bbox("a,b,c,d"), Err(BboxError::Coordinates));
+        assert_eq!(parse_bbox("134.8,32.7,132.0,34.5"), Err(BboxError::Range));
+        assert_eq!(
+            parse_bbox("132.0,32.7,134.8,95.0"),
+            Err(BboxError::OutOfRange)
+        );
+    }
+}
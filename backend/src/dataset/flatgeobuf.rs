@@ -0,0 +1,690 @@
+use anyhow::{Context, Result};
+use flatbuffers::FlatBufferBuilder;
+use std::io::Write;
+use std::ops::Range;
+
+use super::{
+    attributes, hilbert_index, junction_from_attributes, Attributes, Bounds, ColumnType, Value,
+    COLUMNS,
+};
+use crate::domain::Junction;
+
+// https://flatgeobuf.org/ のバージョン 3
+const MAGIC_BYTES: [u8; 8] = [b'f', b'g', b'b', 3, b'f', b'g', b'b', 0];
+
+/// Number of children per node of the packed Hilbert R-tree index
+pub const INDEX_NODE_SIZE: u16 = 16;
+
+// header.fbs の GeometryType と ColumnType
+const GEOMETRY_TYPE_POINT: u8 = 1;
+const COLUMN_TYPE_INT: u8 = 5;
+const COLUMN_TYPE_LONG: u8 = 7;
+const COLUMN_TYPE_FLOAT: u8 = 9;
+const COLUMN_TYPE_DOUBLE: u8 = 10;
+const COLUMN_TYPE_STRING: u8 = 11;
+
+// フィールド番号 n のテーブルスロット
+const fn slot(field: u16) -> u16 {
+    4 + 2 * field
+}
+
+/// Writes junctions as a FlatGeobuf file with a spatial index
+///
+/// Features are kept in memory until [`FlatGeobufWriter::finish`], because the file stores them
+/// sorted along a Hilbert curve so that a bbox can be read with a few HTTP range requests.
+pub struct FlatGeobufWriter {
+    name: String,
+    features: Vec<EncodedFeature>,
+}
+
+struct EncodedFeature {
+    lon: f64,
+    lat: f64,
+    /// Size-prefixed Feature flatbuffer
+    bytes: Vec<u8>,
+}
+
+/// Entry of the packed R-tree: a bbox and the offset of a feature or of the first child node
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NodeItem {
+    bounds: Bounds,
+    offset: u64,
+}
+
+impl FlatGeobufWriter {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            features: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, junction: &Junction) {
+        self.features.push(EncodedFeature {
+            lon: junction.lon,
+            lat: junction.lat,
+            bytes: encode_feature(junction.lon, junction.lat, &attributes(junction)),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    pub fn finish(mut self, mut out: impl Write) -> Result<()> {
+        let bounds = Bounds::of_points(self.features.iter().map(|f| (f.lon, f.lat)));
+
+        if let Some(bounds) = bounds {
+            // 範囲を 2^16 x 2^16 に分けたヒルベルト曲線の順に並べる
+            let scale = |v: f64, min: f64, max: f64| {
+                let span = max - min;
+                if span > 0.0 {
+                    ((v - min) / span * 65535.0) as u64
+                } else {
+                    0
+                }
+            };
+            self.features.sort_by_cached_key(|f| {
+                hilbert_index(
+                    16,
+                    scale(f.lon, bounds.min_lon, bounds.max_lon),
+                    scale(f.lat, bounds.min_lat, bounds.max_lat),
+                )
+            });
+        }
+
+        let index_node_size = if self.features.is_empty() {
+            0
+        } else {
+            INDEX_NODE_SIZE
+        };
+
+        out.write_all(&MAGIC_BYTES)?;
+        out.write_all(&encode_header(
+            &self.name,
+            bounds,
+            self.features.len() as u64,
+            index_node_size,
+        ))?;
+
+        if index_node_size > 0 {
+            let mut offset = 0;
+            let leaves: Vec<NodeItem> = self
+                .features
+                .iter()
+                .map(|f| {
+                    let leaf = NodeItem {
+                        bounds: Bounds::point(f.lon, f.lat),
+                        offset,
+                    };
+                    offset += f.bytes.len() as u64;
+                    leaf
+                })
+                .collect();
+
+            for node in build_index(&leaves, index_node_size as usize) {
+                for v in [
+                    node.bounds.min_lon,
+                    node.bounds.min_lat,
+                    node.bounds.max_lon,
+                    node.bounds.max_lat,
+                ] {
+                    out.write_all(&v.to_le_bytes())?;
+                }
+                out.write_all(&node.offset.to_le_bytes())?;
+            }
+        }
+
+        for feature in &self.features {
+            out.write_all(&feature.bytes)?;
+        }
+        out.flush()?;
+
+        Ok(())
+    }
+}
+
+fn encode_header(
+    name: &str,
+    bounds: Option<Bounds>,
+    features_count: u64,
+    index_node_size: u16,
+) -> Vec<u8> {
+    let mut builder = FlatBufferBuilder::new();
+
+    let name = builder.create_string(name);
+    let envelope =
+        bounds.map(|b| builder.create_vector(&[b.min_lon, b.min_lat, b.max_lon, b.max_lat]));
+
+    let columns: Vec<_> = COLUMNS
+        .iter()
+        .map(|column| {
+            let column_name = builder.create_string(column.name);
+            let column_type = match column.column_type {
+                ColumnType::Integer => COLUMN_TYPE_LONG,
+                ColumnType::Real => COLUMN_TYPE_DOUBLE,
+                ColumnType::Text => COLUMN_TYPE_STRING,
+            };
+            let start = builder.start_table();
+            builder.push_slot_always(slot(0), column_name);
+            builder.push_slot(slot(1), column_type, 0);
+            // id は必ず値があり一意
+            if column.name == "id" {
+                builder.push_slot(slot(7), false, true);
+                builder.push_slot(slot(8), true, false);
+                builder.push_slot(slot(9), true, false);
+            }
+            builder.end_table(start)
+        })
+        .collect();
+    let columns = builder.create_vector(&columns);
+
+    let org = builder.create_string("EPSG");
+    let start = builder.start_table();
+    builder.push_slot_always(slot(0), org);
+    builder.push_slot(slot(1), 4326i32, 0);
+    let crs = builder.end_table(start);
+
+    let start = builder.start_table();
+    builder.push_slot_always(slot(0), name);
+    if let Some(envelope) = envelope {
+        builder.push_slot_always(slot(1), envelope);
+    }
+    builder.push_slot(slot(2), GEOMETRY_TYPE_POINT, 0);
+    builder.push_slot_always(slot(7), columns);
+    builder.push_slot(slot(8), features_count, 0);
+    builder.push_slot(slot(9), index_node_size, INDEX_NODE_SIZE);
+    builder.push_slot_always(slot(10), crs);
+    let header = builder.end_table(start);
+
+    builder.finish_size_prefixed(header, None);
+    builder.finished_data().to_vec()
+}
+
+fn encode_feature(lon: f64, lat: f64, values: &[Value]) -> Vec<u8> {
+    let mut builder = FlatBufferBuilder::new();
+
+    let xy = builder.create_vector(&[lon, lat]);
+    let start = builder.start_table();
+    builder.push_slot_always(slot(1), xy);
+    let geometry = builder.end_table(start);
+
+    let properties = builder.create_vector(&encode_properties(values));
+
+    let start = builder.start_table();
+    builder.push_slot_always(slot(0), geometry);
+    builder.push_slot_always(slot(1), properties);
+    let feature = builder.end_table(start);
+
+    builder.finish_size_prefixed(feature, None);
+    builder.finished_data().to_vec()
+}
+
+/// Encodes attribute values as FlatGeobuf properties: the column index (u16) followed by the value
+///
+/// Null values are left out.
+fn encode_properties(values: &[Value]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (index, value) in values.iter().enumerate() {
+        let index = (index as u16).to_le_bytes();
+        match value {
+            Value::Integer(v) => {
+                bytes.extend_from_slice(&index);
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Real(v) => {
+                bytes.extend_from_slice(&index);
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Text(v) => {
+                bytes.extend_from_slice(&index);
+                bytes.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(v.as_bytes());
+            }
+            Value::Null => {}
+        }
+    }
+    bytes
+}
+
+/// Reads back every junction of a FlatGeobuf file written by [`FlatGeobufWriter`]
+///
+/// The spatial index is skipped; features are returned in file order.
+pub fn read_junctions(bytes: &[u8]) -> Result<Vec<Junction>> {
+    if bytes.len() < MAGIC_BYTES.len() || bytes[..3] != MAGIC_BYTES[..3] {
+        anyhow::bail!("Not a FlatGeobuf file");
+    }
+    let mut position = MAGIC_BYTES.len();
+
+    let (header, header_size) = Table::size_prefixed(bytes, position)?;
+    position += 4 + header_size;
+
+    let mut columns = Vec::new();
+    if let Some(vector) = header.vector(7)? {
+        for i in 0..vector.len {
+            let column = vector.table(i)?;
+            let name = column.string(0)?.context("Column without a name")?;
+            columns.push((name, column.u8(1)?.unwrap_or(0)));
+        }
+    }
+    let features_count = header.u64(8)?.unwrap_or(0) as usize;
+    let index_node_size = header.u16(9)?.unwrap_or(INDEX_NODE_SIZE) as usize;
+
+    // 索引（ノードあたり 40 バイト）は読み飛ばす
+    if index_node_size > 0 && features_count > 0 {
+        position += level_bounds(features_count, index_node_size)[0].end * 40;
+    }
+
+    let mut junctions = Vec::with_capacity(features_count);
+    while position < bytes.len() {
+        let (feature, feature_size) = Table::size_prefixed(bytes, position)?;
+        position += 4 + feature_size;
+
+        let geometry = feature.table(0)?.context("Feature without geometry")?;
+        let xy = geometry
+            .vector(1)?
+            .context("Geometry without coordinates")?;
+        if xy.len < 2 {
+            anyhow::bail!("Point geometry needs two coordinates");
+        }
+        let (lon, lat) = (xy.f64(0)?, xy.f64(1)?);
+
+        let properties = match feature.vector(1)? {
+            Some(vector) => vector.bytes(1)?,
+            None => &[],
+        };
+        let attributes = decode_properties(properties, &columns)?;
+        junctions.push(junction_from_attributes(lon, lat, &attributes)?);
+    }
+
+    Ok(junctions)
+}
+
+/// Decodes FlatGeobuf properties into values keyed by column name
+fn decode_properties(mut bytes: &[u8], columns: &[(String, u8)]) -> Result<Attributes> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if bytes.len() < len {
+            anyhow::bail!("Truncated feature properties");
+        }
+        let (taken, rest) = bytes.split_at(len);
+        *bytes = rest;
+        Ok(taken)
+    }
+
+    let mut attributes = Attributes::new();
+    while !bytes.is_empty() {
+        let index = u16::from_le_bytes(take(&mut bytes, 2)?.try_into()?) as usize;
+        let (name, column_type) = columns.get(index).context("Unknown column in properties")?;
+        let value = match *column_type {
+            COLUMN_TYPE_INT => {
+                Value::Integer(i32::from_le_bytes(take(&mut bytes, 4)?.try_into()?).into())
+            }
+            COLUMN_TYPE_LONG => {
+                Value::Integer(i64::from_le_bytes(take(&mut bytes, 8)?.try_into()?))
+            }
+            COLUMN_TYPE_FLOAT => {
+                Value::Real(f32::from_le_bytes(take(&mut bytes, 4)?.try_into()?).into())
+            }
+            COLUMN_TYPE_DOUBLE => Value::Real(f64::from_le_bytes(take(&mut bytes, 8)?.try_into()?)),
+            COLUMN_TYPE_STRING => {
+                let len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into()?) as usize;
+                Value::Text(String::from_utf8(take(&mut bytes, len)?.to_vec())?)
+            }
+            other => anyhow::bail!("Unsupported column type {} for {}", other, name),
+        };
+        attributes.insert(name.clone(), value);
+    }
+    Ok(attributes)
+}
+
+/// Read-only view of a flatbuffer table, with bounds checks on every access
+#[derive(Clone, Copy)]
+struct Table<'a> {
+    buf: &'a [u8],
+    position: usize,
+}
+
+/// Vector field of a flatbuffer table
+struct Vector<'a> {
+    buf: &'a [u8],
+    /// Position of the first element
+    position: usize,
+    len: usize,
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Result<u32> {
+    let bytes = buf.get(at..at + 4).context("Truncated flatbuffer")?;
+    Ok(u32::from_le_bytes(bytes.try_into()?))
+}
+
+impl<'a> Table<'a> {
+    /// Root table of the size-prefixed flatbuffer at `at`, and the size of the buffer
+    fn size_prefixed(bytes: &'a [u8], at: usize) -> Result<(Self, usize)> {
+        let size = read_u32(bytes, at)? as usize;
+        let buf = bytes
+            .get(at + 4..at + 4 + size)
+            .context("Truncated flatbuffer")?;
+        let position = read_u32(buf, 0)? as usize;
+        Ok((Table { buf, position }, size))
+    }
+
+    /// Position of the field in the buffer, None when the field is absent
+    fn field(&self, field: u16) -> Result<Option<usize>> {
+        let soffset = read_u32(self.buf, self.position)? as i32;
+        let vtable = (self.position as i64 - soffset as i64)
+            .try_into()
+            .ok()
+            .context("Invalid flatbuffer vtable")?;
+        let read_u16 = |at: usize| -> Result<usize> {
+            let bytes = self.buf.get(at..at + 2).context("Truncated flatbuffer")?;
+            Ok(u16::from_le_bytes(bytes.try_into()?) as usize)
+        };
+        let vtable_size = read_u16(vtable)?;
+        let entry = slot(field) as usize;
+        if entry + 2 > vtable_size {
+            return Ok(None);
+        }
+        match read_u16(vtable + entry)? {
+            0 => Ok(None),
+            offset => Ok(Some(self.position + offset)),
+        }
+    }
+
+    fn scalar<const N: usize>(&self, field: u16) -> Result<Option<[u8; N]>> {
+        let Some(at) = self.field(field)? else {
+            return Ok(None);
+        };
+        let bytes = self.buf.get(at..at + N).context("Truncated flatbuffer")?;
+        Ok(Some(bytes.try_into()?))
+    }
+
+    fn u8(&self, field: u16) -> Result<Option<u8>> {
+        Ok(self.scalar::<1>(field)?.map(|b| b[0]))
+    }
+
+    fn u16(&self, field: u16) -> Result<Option<u16>> {
+        Ok(self.scalar(field)?.map(u16::from_le_bytes))
+    }
+
+    fn u64(&self, field: u16) -> Result<Option<u64>> {
+        Ok(self.scalar(field)?.map(u64::from_le_bytes))
+    }
+
+    // 参照型のフィールドは、フィールドの位置からの相対オフセット
+    fn reference(&self, field: u16) -> Result<Option<usize>> {
+        let Some(at) = self.field(field)? else {
+            return Ok(None);
+        };
+        Ok(Some(at + read_u32(self.buf, at)? as usize))
+    }
+
+    fn table(&self, field: u16) -> Result<Option<Table<'a>>> {
+        Ok(self.reference(field)?.map(|position| Table {
+            buf: self.buf,
+            position,
+        }))
+    }
+
+    fn vector(&self, field: u16) -> Result<Option<Vector<'a>>> {
+        let Some(at) = self.reference(field)? else {
+            return Ok(None);
+        };
+        Ok(Some(Vector {
+            buf: self.buf,
+            position: at + 4,
+            len: read_u32(self.buf, at)? as usize,
+        }))
+    }
+
+    fn string(&self, field: u16) -> Result<Option<String>> {
+        let Some(vector) = self.vector(field)? else {
+            return Ok(None);
+        };
+        Ok(Some(String::from_utf8(vector.bytes(1)?.to_vec())?))
+    }
+}
+
+impl<'a> Vector<'a> {
+    /// Raw bytes of the elements, each `element_size` bytes long
+    fn bytes(&self, element_size: usize) -> Result<&'a [u8]> {
+        self.buf
+            .get(self.position..self.position + self.len * element_size)
+            .context("Truncated flatbuffer")
+    }
+
+    fn f64(&self, index: usize) -> Result<f64> {
+        let bytes = &self.bytes(8)?[index * 8..index * 8 + 8];
+        Ok(f64::from_le_bytes(bytes.try_into()?))
+    }
+
+    fn table(&self, index: usize) -> Result<Table<'a>> {
+        let at = self.position + index * 4;
+        Ok(Table {
+            buf: self.buf,
+            position: at + read_u32(self.buf, at)? as usize,
+        })
+    }
+}
+
+/// Node ranges of each level of the R-tree, from the leaves up to the root
+///
+/// The root is stored first and the leaves last.
+fn level_bounds(num_items: usize, node_size: usize) -> Vec<Range<usize>> {
+    let mut level_num_nodes = vec![num_items];
+    let mut n = num_items;
+    loop {
+        n = n.div_ceil(node_size);
+        level_num_nodes.push(n);
+        if n == 1 {
+            break;
+        }
+    }
+
+    let mut end: usize = level_num_nodes.iter().sum();
+    level_num_nodes
+        .into_iter()
+        .map(|count| {
+            let start = end - count;
+            let range = start..end;
+            end = start;
+            range
+        })
+        .collect()
+}
+
+/// Builds the packed R-tree over leaves already sorted in file order
+fn build_index(leaves: &[NodeItem], node_size: usize) -> Vec<NodeItem> {
+    let levels = level_bounds(leaves.len(), node_size);
+    let mut nodes = vec![
+        NodeItem {
+            bounds: leaves[0].bounds,
+            offset: 0,
+        };
+        levels[0].end
+    ];
+    nodes[levels[0].clone()].copy_from_slice(leaves);
+
+    // 親ノードは子の範囲を合わせたもので、offset は最初の子ノードの位置
+    for (children, parents) in levels.iter().zip(levels.iter().skip(1)) {
+        for (parent, first) in parents.clone().zip(children.clone().step_by(node_size)) {
+            let last = (first + node_size).min(children.end);
+            let mut bounds = nodes[first].bounds;
+            for child in &nodes[first + 1..last] {
+                bounds.expand(&child.bounds);
+            }
+            nodes[parent] = NodeItem {
+                bounds,
+                offset: first as u64,
+            };
+        }
+    }
+
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::{assert_same_junction, junction};
+
+    #[test]
+    fn test_level_bounds() {
+        assert_eq!(level_bounds(1, 16), vec![1..2, 0..1]);
+        assert_eq!(level_bounds(16, 16), vec![1..17, 0..1]);
+        // 葉 40 個 → 3 ノード → ルート
+        assert_eq!(level_bounds(40, 16), vec![4..44, 1..4, 0..1]);
+    }
+
+    #[test]
+    fn test_build_index() {
+        let leaves: Vec<NodeItem> = (0..20)
+            .map(|i| NodeItem {
+                bounds: Bounds::point(i as f64, i as f64 * 2.0),
+                offset: i * 100,
+            })
+            .collect();
+        let nodes = build_index(&leaves, 16);
+
+        assert_eq!(nodes.len(), 23);
+        assert_eq!(nodes[3..], leaves[..]);
+        // ルートは全体を覆い、2 つの子ノードを指す
+        assert_eq!(nodes[0].offset, 1);
+        assert_eq!(nodes[0].bounds.max_lat, 38.0);
+        assert_eq!(nodes[1].offset, 3);
+        assert_eq!(nodes[1].bounds.max_lon, 15.0);
+        assert_eq!(nodes[2].offset, 19);
+        assert_eq!(nodes[2].bounds.min_lon, 16.0);
+    }
+
+    #[test]
+    fn test_encode_properties() {
+        let bytes = encode_properties(&[
+            Value::Integer(5),
+            Value::Null,
+            Value::Text("分岐".to_string()),
+        ]);
+
+        assert_eq!(&bytes[0..2], &[0, 0]);
+        assert_eq!(&bytes[2..10], &5i64.to_le_bytes());
+        assert_eq!(&bytes[10..12], &[2, 0]);
+        assert_eq!(&bytes[12..16], &6u32.to_le_bytes());
+        assert_eq!(&bytes[16..], "分岐".as_bytes());
+    }
+
+    #[test]
+    fn test_file_layout() {
+        let mut writer = FlatGeobufWriter::new("y_junctions");
+        for i in 0..3 {
+            writer.add(&junction(i, 139.0 + i as f64 * 0.5, 35.0));
+        }
+        let feature_bytes: usize = writer.features.iter().map(|f| f.bytes.len()).sum();
+
+        let mut output = Vec::new();
+        writer.finish(&mut output).unwrap();
+
+        assert_eq!(&output[..8], &MAGIC_BYTES);
+        let header_size = u32::from_le_bytes(output[8..12].try_into().unwrap()) as usize;
+        // マジックバイト + ヘッダー + 索引（ルート + 葉 3 つ）+ フィーチャー
+        assert_eq!(output.len(), 8 + 4 + header_size + 4 * 40 + feature_bytes);
+
+        // ルートノードの範囲はすべての点を覆う
+        let index = &output[12 + header_size..];
+        let read_f64 = |at: usize| f64::from_le_bytes(index[at..at + 8].try_into().unwrap());
+        assert_eq!(read_f64(0), 139.0);
+        assert_eq!(read_f64(16), 140.0);
+    }
+
+    #[test]
+    fn test_read_junctions() {
+        let mut written = vec![junction(1, 139.5, 35.0), junction(2, 135.0, 34.5)];
+        written[1].highway_class = Some("primary".to_string());
+        written[1].road_names = [None, None, None];
+        let mut writer = FlatGeobufWriter::new("y_junctions");
+        for junction in &written {
+            writer.add(junction);
+        }
+        let mut output = Vec::new();
+        writer.finish(&mut output).unwrap();
+
+        let mut read = read_junctions(&output).unwrap();
+        read.sort_by_key(|junction| junction.id);
+        assert_eq!(read.len(), 2);
+        for (read, written) in read.iter().zip(&written) {
+            assert_same_junction(read, written);
+        }
+
+        assert!(read_junctions(b"not a file").is_err());
+        assert!(read_junctions(&output[..output.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_read_with_flatgeobuf_crate() {
+        use ::flatgeobuf::{FallibleStreamingIterator, FeatureProperties, FgbReader};
+        use std::io::Cursor;
+
+        // 葉 48 個で索引が 3 段になる
+        let mut writer = FlatGeobufWriter::new("y_junctions");
+        for i in 0..48 {
+            writer.add(&junction(
+                i,
+                139.0 + (i % 8) as f64 * 0.1,
+                35.0 + (i / 8) as f64 * 0.1,
+            ));
+        }
+        let mut output = Vec::new();
+        writer.finish(&mut output).unwrap();
+
+        let reader = FgbReader::open(Cursor::new(&output)).unwrap();
+        let header = reader.header();
+        assert_eq!(header.name(), Some("y_junctions"));
+        assert_eq!(header.features_count(), 48);
+        assert_eq!(header.index_node_size(), INDEX_NODE_SIZE);
+        assert_eq!(header.columns().unwrap().len(), COLUMNS.len());
+
+        let mut features = reader.select_all().unwrap();
+        let mut ids = Vec::new();
+        while let Some(feature) = features.next().unwrap() {
+            let id: i64 = feature.property("id").unwrap();
+            let xy = feature.geometry().unwrap().xy().unwrap();
+            assert_eq!(xy.get(0), 139.0 + (id % 8) as f64 * 0.1);
+            assert_eq!(xy.get(1), 35.0 + (id / 8) as f64 * 0.1);
+            assert_eq!(feature.property::<i64>("angle_1").unwrap(), 20);
+            assert_eq!(feature.property::<f64>("elevation").unwrap(), 12.5);
+            assert_eq!(
+                feature.property::<String>("prefecture_code").unwrap(),
+                "JP-13"
+            );
+            ids.push(id);
+        }
+        ids.sort();
+        assert_eq!(ids, (0..48).collect::<Vec<_>>());
+
+        // 索引を使った bbox 検索でも同じ点が返る
+        let mut features = FgbReader::open(Cursor::new(&output))
+            .unwrap()
+            .select_bbox(139.15, 35.05, 139.45, 35.25)
+            .unwrap();
+        let mut ids = Vec::new();
+        while let Some(feature) = features.next().unwrap() {
+            ids.push(feature.property::<i64>("id").unwrap());
+        }
+        ids.sort();
+        assert_eq!(ids, vec![10, 11, 12, 18, 19, 20]);
+    }
+
+    #[test]
+    fn test_empty_file_has_no_index() {
+        let mut output = Vec::new();
+        FlatGeobufWriter::new("y_junctions")
+            .finish(&mut output)
+            .unwrap();
+
+        let header_size = u32::from_le_bytes(output[8..12].try_into().unwrap()) as usize;
+        assert_eq!(output.len(), 12 + header_size);
+        assert!(read_junctions(&output).unwrap().is_empty());
+    }
+}
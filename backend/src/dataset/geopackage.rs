@@ -0,0 +1,459 @@
+use anyhow::{Context, Result};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column as _, Row, Sqlite, Transaction};
+use std::path::Path;
+
+use super::{attributes, junction_from_attributes, Attributes, Bounds, ColumnType, Value, COLUMNS};
+use crate::domain::Junction;
+
+/// Name of the feature table
+pub const TABLE_NAME: &str = "y_junctions";
+
+// GeoPackage 1.4 の必須テーブルと WGS 84 の定義
+const SCHEMA: &str = r#"
+PRAGMA application_id = 1196444487;
+PRAGMA user_version = 10400;
+
+CREATE TABLE gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+
+CREATE TABLE gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+
+CREATE TABLE gpkg_extensions (
+    table_name TEXT,
+    column_name TEXT,
+    extension_name TEXT NOT NULL,
+    definition TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name)
+);
+
+INSERT INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined',
+     'undefined cartesian coordinate reference system'),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined',
+     'undefined geographic coordinate reference system'),
+    ('WGS 84 geodetic', 4326, 'EPSG', 4326,
+     'GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AXIS["Latitude",NORTH],AXIS["Longitude",EAST],AUTHORITY["EPSG","4326"]]',
+     'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');
+
+INSERT INTO gpkg_contents (table_name, data_type, identifier, description, srs_id)
+    VALUES ('y_junctions', 'features', 'Y-junctions', 'Y-junctions extracted from OpenStreetMap', 4326);
+INSERT INTO gpkg_geometry_columns VALUES ('y_junctions', 'geom', 'POINT', 4326, 0, 0);
+
+CREATE VIRTUAL TABLE rtree_y_junctions_geom USING rtree(id, minx, maxx, miny, maxy);
+INSERT INTO gpkg_extensions VALUES
+    ('y_junctions', 'geom', 'gpkg_rtree_index',
+     'http://www.geopackage.org/spec120/#extension_rtree', 'write-only');
+"#;
+
+// 空間索引を更新するトリガー（GeoPackage 仕様の gpkg_rtree_index）
+//
+// ST_* 関数は GDAL などの GeoPackage 対応ソフトが提供する。書き出し後に作るので、
+// 書き出し中の INSERT では発火しない。
+const RTREE_TRIGGERS: &str = r#"
+CREATE TRIGGER rtree_y_junctions_geom_insert AFTER INSERT ON y_junctions
+WHEN (new.geom NOT NULL AND NOT ST_IsEmpty(NEW.geom))
+BEGIN
+    INSERT OR REPLACE INTO rtree_y_junctions_geom VALUES (
+        NEW.fid, ST_MinX(NEW.geom), ST_MaxX(NEW.geom), ST_MinY(NEW.geom), ST_MaxY(NEW.geom));
+END;
+
+CREATE TRIGGER rtree_y_junctions_geom_update1 AFTER UPDATE OF geom ON y_junctions
+WHEN OLD.fid = NEW.fid AND (NEW.geom NOTNULL AND NOT ST_IsEmpty(NEW.geom))
+BEGIN
+    INSERT OR REPLACE INTO rtree_y_junctions_geom VALUES (
+        NEW.fid, ST_MinX(NEW.geom), ST_MaxX(NEW.geom), ST_MinY(NEW.geom), ST_MaxY(NEW.geom));
+END;
+
+CREATE TRIGGER rtree_y_junctions_geom_update2 AFTER UPDATE OF geom ON y_junctions
+WHEN OLD.fid = NEW.fid AND (NEW.geom ISNULL OR ST_IsEmpty(NEW.geom))
+BEGIN
+    DELETE FROM rtree_y_junctions_geom WHERE id = OLD.fid;
+END;
+
+CREATE TRIGGER rtree_y_junctions_geom_update3 AFTER UPDATE ON y_junctions
+WHEN OLD.fid != NEW.fid AND (NEW.geom NOTNULL AND NOT ST_IsEmpty(NEW.geom))
+BEGIN
+    DELETE FROM rtree_y_junctions_geom WHERE id = OLD.fid;
+    INSERT OR REPLACE INTO rtree_y_junctions_geom VALUES (
+        NEW.fid, ST_MinX(NEW.geom), ST_MaxX(NEW.geom), ST_MinY(NEW.geom), ST_MaxY(NEW.geom));
+END;
+
+CREATE TRIGGER rtree_y_junctions_geom_update4 AFTER UPDATE ON y_junctions
+WHEN OLD.fid != NEW.fid AND (NEW.geom ISNULL OR ST_IsEmpty(NEW.geom))
+BEGIN
+    DELETE FROM rtree_y_junctions_geom WHERE id IN (OLD.fid, NEW.fid);
+END;
+
+CREATE TRIGGER rtree_y_junctions_geom_delete AFTER DELETE ON y_junctions
+WHEN old.geom NOT NULL
+BEGIN
+    DELETE FROM rtree_y_junctions_geom WHERE id = OLD.fid;
+END;
+"#;
+
+/// Writes junctions to a GeoPackage (SQLite) file with an R-tree spatial index
+///
+/// The feature id (`fid`) is the junction id. Rows are written in one transaction as they are
+/// added, so the file only becomes visible as a complete dataset after [`GeoPackageWriter::finish`].
+pub struct GeoPackageWriter {
+    pool: SqlitePool,
+    transaction: Transaction<'static, Sqlite>,
+    insert_sql: String,
+    bounds: Option<Bounds>,
+    count: usize,
+}
+
+impl GeoPackageWriter {
+    /// Creates the file, replacing any existing one
+    pub async fn create(path: &Path) -> Result<Self> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+        sqlx::raw_sql(&create_table_sql()).execute(&pool).await?;
+
+        let transaction = pool.begin().await?;
+        Ok(Self {
+            pool,
+            transaction,
+            insert_sql: insert_sql(),
+            bounds: None,
+            count: 0,
+        })
+    }
+
+    pub async fn add(&mut self, junction: &Junction) -> Result<()> {
+        let mut query = sqlx::query(&self.insert_sql)
+            .bind(junction.id)
+            .bind(point_geometry(junction.lon, junction.lat));
+        for value in attributes(junction) {
+            query = match value {
+                Value::Integer(v) => query.bind(v),
+                Value::Real(v) => query.bind(v),
+                Value::Text(v) => query.bind(v),
+                Value::Null => query.bind(None::<String>),
+            };
+        }
+        query.execute(&mut *self.transaction).await?;
+
+        sqlx::query("INSERT INTO rtree_y_junctions_geom VALUES (?, ?, ?, ?, ?)")
+            .bind(junction.id)
+            .bind(junction.lon)
+            .bind(junction.lon)
+            .bind(junction.lat)
+            .bind(junction.lat)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let point = Bounds::point(junction.lon, junction.lat);
+        match &mut self.bounds {
+            Some(bounds) => bounds.expand(&point),
+            None => self.bounds = Some(point),
+        }
+        self.count += 1;
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub async fn finish(mut self) -> Result<()> {
+        if let Some(bounds) = self.bounds {
+            sqlx::query(
+                "UPDATE gpkg_contents SET min_x = ?, min_y = ?, max_x = ?, max_y = ?, \
+                 last_change = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE table_name = ?",
+            )
+            .bind(bounds.min_lon)
+            .bind(bounds.min_lat)
+            .bind(bounds.max_lon)
+            .bind(bounds.max_lat)
+            .bind(TABLE_NAME)
+            .execute(&mut *self.transaction)
+            .await?;
+        }
+        sqlx::raw_sql(RTREE_TRIGGERS)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.transaction.commit().await?;
+        self.pool.close().await;
+
+        Ok(())
+    }
+}
+
+fn create_table_sql() -> String {
+    let mut columns = vec![
+        "fid INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL".to_string(),
+        "geom POINT".to_string(),
+    ];
+    for column in COLUMNS {
+        let column_type = match column.column_type {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "DOUBLE",
+            ColumnType::Text => "TEXT",
+        };
+        columns.push(format!("{} {}", column.name, column_type));
+    }
+    format!(
+        "CREATE TABLE {} (\n    {}\n);",
+        TABLE_NAME,
+        columns.join(",\n    ")
+    )
+}
+
+fn insert_sql() -> String {
+    let names: Vec<&str> = COLUMNS.iter().map(|column| column.name).collect();
+    format!(
+        "INSERT INTO {} (fid, geom, {}) VALUES (?, ?{})",
+        TABLE_NAME,
+        names.join(", "),
+        ", ?".repeat(names.len())
+    )
+}
+
+/// GeoPackage geometry blob of a point: the "GP" header (no envelope, SRS 4326) and WKB
+pub fn point_geometry(lon: f64, lat: f64) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(29);
+    // magic, version 0, flags（リトルエンディアン、エンベロープなし）
+    blob.extend_from_slice(&[b'G', b'P', 0, 0b0000_0001]);
+    blob.extend_from_slice(&4326i32.to_le_bytes());
+    // WKB Point（リトルエンディアン）
+    blob.push(1);
+    blob.extend_from_slice(&1u32.to_le_bytes());
+    blob.extend_from_slice(&lon.to_le_bytes());
+    blob.extend_from_slice(&lat.to_le_bytes());
+    blob
+}
+
+/// Reads back every junction of a GeoPackage written by [`GeoPackageWriter`], in fid order
+pub async fn read_junctions(path: &Path) -> Result<Vec<Junction>> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(SqliteConnectOptions::new().filename(path).read_only(true))
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    let rows = sqlx::query(&format!("SELECT * FROM {} ORDER BY fid", TABLE_NAME))
+        .fetch_all(&pool)
+        .await?;
+    pool.close().await;
+
+    rows.iter()
+        .map(|row| {
+            let geom: Vec<u8> = row.try_get("geom")?;
+            let (lon, lat) = parse_point_geometry(&geom)?;
+            junction_from_attributes(lon, lat, &row_attributes(row)?)
+        })
+        .collect()
+}
+
+// 既知のカラムだけを宣言どおりの型で読む
+fn row_attributes(row: &SqliteRow) -> Result<Attributes> {
+    let mut attributes = Attributes::new();
+    for row_column in row.columns() {
+        let Some(column) = COLUMNS.iter().find(|c| c.name == row_column.name()) else {
+            continue;
+        };
+        let index = row_column.ordinal();
+        let value = match column.column_type {
+            ColumnType::Integer => row.try_get::<Option<i64>, _>(index)?.map(Value::Integer),
+            ColumnType::Real => row.try_get::<Option<f64>, _>(index)?.map(Value::Real),
+            ColumnType::Text => row.try_get::<Option<String>, _>(index)?.map(Value::Text),
+        };
+        attributes.insert(column.name.to_string(), value.unwrap_or(Value::Null));
+    }
+    Ok(attributes)
+}
+
+/// (lon, lat) of a GeoPackage point geometry blob
+pub fn parse_point_geometry(blob: &[u8]) -> Result<(f64, f64)> {
+    if blob.len() < 8 || &blob[..2] != b"GP" {
+        anyhow::bail!("Not a GeoPackage geometry");
+    }
+    // flags のビット 1-3 がエンベロープの種類（なし、XY、XYZ、XYM、XYZM）
+    let envelope_size = match (blob[3] >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        other => anyhow::bail!("Invalid geometry envelope type {}", other),
+    };
+    let wkb = blob
+        .get(8 + envelope_size..)
+        .filter(|wkb| wkb.len() >= 21)
+        .context("Truncated geometry")?;
+
+    let little_endian = wkb[0] == 1;
+    let read_u32 = |bytes: [u8; 4]| {
+        if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+    let read_f64 = |bytes: [u8; 8]| {
+        if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        }
+    };
+    if read_u32(wkb[1..5].try_into()?) != 1 {
+        anyhow::bail!("Geometry is not a point");
+    }
+    Ok((
+        read_f64(wkb[5..13].try_into()?),
+        read_f64(wkb[13..21].try_into()?),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::{assert_same_junction, junction};
+
+    #[test]
+    fn test_point_geometry() {
+        let blob = point_geometry(139.5, 35.25);
+        assert_eq!(blob.len(), 29);
+        assert_eq!(&blob[..4], b"GP\x00\x01");
+        assert_eq!(&blob[4..8], &4326i32.to_le_bytes());
+        assert_eq!(&blob[8..13], &[1, 1, 0, 0, 0]);
+        assert_eq!(&blob[13..21], &139.5f64.to_le_bytes());
+        assert_eq!(&blob[21..29], &35.25f64.to_le_bytes());
+        assert_eq!(parse_point_geometry(&blob).unwrap(), (139.5, 35.25));
+
+        // XY エンベロープ付き、ビッグエンディアンの WKB
+        let mut blob = vec![b'G', b'P', 0, 0b0000_0011];
+        blob.extend_from_slice(&4326i32.to_le_bytes());
+        for v in [139.5f64, 139.5, 35.25, 35.25] {
+            blob.extend_from_slice(&v.to_le_bytes());
+        }
+        blob.push(0);
+        blob.extend_from_slice(&1u32.to_be_bytes());
+        blob.extend_from_slice(&139.5f64.to_be_bytes());
+        blob.extend_from_slice(&35.25f64.to_be_bytes());
+        assert_eq!(parse_point_geometry(&blob).unwrap(), (139.5, 35.25));
+
+        assert!(parse_point_geometry(b"GP\x00\x01").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_geopackage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("y-junctions.gpkg");
+
+        let mut writer = GeoPackageWriter::create(&path).await.unwrap();
+        writer.add(&junction(10, 139.5, 35.25)).await.unwrap();
+        writer.add(&junction(11, 140.0, 36.0)).await.unwrap();
+        writer.finish().await.unwrap();
+
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&path))
+            .await
+            .unwrap();
+
+        let application_id: i64 = sqlx::query_scalar("PRAGMA application_id")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(application_id, 0x4750_4B47);
+
+        let row = sqlx::query(
+            "SELECT fid, geom, angle_type, city_code, label FROM y_junctions WHERE fid = 11",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.get::<i64, _>("fid"), 11);
+        assert_eq!(row.get::<Vec<u8>, _>("geom"), point_geometry(140.0, 36.0));
+        assert_eq!(row.get::<String, _>("angle_type"), "verysharp");
+        assert_eq!(row.get::<Option<String>, _>("city_code"), None);
+        assert_eq!(row.get::<String, _>("label"), "旧中山道の分岐（東京都）");
+
+        let (min_x, max_y): (f64, f64) = sqlx::query_as(
+            "SELECT min_x, max_y FROM gpkg_contents WHERE table_name = 'y_junctions'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((min_x, max_y), (139.5, 36.0));
+
+        // 空間索引で範囲内の行が引ける
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT id FROM rtree_y_junctions_geom WHERE minx <= 139.8 AND maxx >= 139.0",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(ids, vec![10]);
+    }
+
+    #[tokio::test]
+    async fn test_read_geopackage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("y-junctions.gpkg");
+
+        let mut written = vec![junction(10, 139.5, 35.25), junction(11, 140.0, 36.0)];
+        written[0].has_bridge = true;
+        let mut writer = GeoPackageWriter::create(&path).await.unwrap();
+        for junction in &written {
+            writer.add(junction).await.unwrap();
+        }
+        writer.finish().await.unwrap();
+
+        let read = read_junctions(&path).await.unwrap();
+        assert_eq!(read.len(), 2);
+        for (read, written) in read.iter().zip(&written) {
+            assert_same_junction(read, written);
+        }
+    }
+}
@@ -0,0 +1,486 @@
+pub mod flatgeobuf;
+pub mod geopackage;
+pub mod mvt;
+pub mod pmtiles;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::domain::{AreaRef, Junction, JunctionAreas};
+
+/// File format of an offline dataset written by the `export` binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    FlatGeobuf,
+    GeoPackage,
+    PmTiles,
+}
+
+impl DatasetFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "fgb" | "flatgeobuf" => Some(Self::FlatGeobuf),
+            "gpkg" | "geopackage" => Some(Self::GeoPackage),
+            "pmtiles" => Some(Self::PmTiles),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| Self::parse(&ext.to_ascii_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+/// Attribute column of a junction feature
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+}
+
+const fn column(name: &'static str, column_type: ColumnType) -> Column {
+    Column { name, column_type }
+}
+
+/// Attribute columns written to FlatGeobuf and GeoPackage, in the order of [`attributes`]
+pub const COLUMNS: &[Column] = &[
+    column("id", ColumnType::Integer),
+    column("osm_node_id", ColumnType::Integer),
+    column("angle_1", ColumnType::Integer),
+    column("angle_2", ColumnType::Integer),
+    column("angle_3", ColumnType::Integer),
+    column("angle_type", ColumnType::Text),
+    column("bearing_1", ColumnType::Real),
+    column("bearing_2", ColumnType::Real),
+    column("bearing_3", ColumnType::Real),
+    column("elevation", ColumnType::Real),
+    column("min_elevation_diff", ColumnType::Real),
+    column("max_elevation_diff", ColumnType::Real),
+    column("min_angle_elevation_diff", ColumnType::Real),
+    column("local_slope", ColumnType::Real),
+    column("elevation_source", ColumnType::Text),
+    column("elevation_quality", ColumnType::Text),
+    column("elevation_nodata_samples", ColumnType::Integer),
+    column("elevation_skip_reason", ColumnType::Text),
+    column("arm_grade_1", ColumnType::Real),
+    column("arm_grade_2", ColumnType::Real),
+    column("arm_grade_3", ColumnType::Real),
+    column("prefecture_code", ColumnType::Text),
+    column("prefecture_name", ColumnType::Text),
+    column("city_code", ColumnType::Text),
+    column("city_name", ColumnType::Text),
+    column("ward_code", ColumnType::Text),
+    column("ward_name", ColumnType::Text),
+    column("road_name_1", ColumnType::Text),
+    column("road_name_2", ColumnType::Text),
+    column("road_name_3", ColumnType::Text),
+    column("highway_class", ColumnType::Text),
+    column("has_bridge", ColumnType::Integer),
+    column("has_tunnel", ColumnType::Integer),
+    column("created_at", ColumnType::Text),
+    column("label", ColumnType::Text),
+    column("streetview_url", ColumnType::Text),
+];
+
+/// Value of one attribute column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Null,
+}
+
+impl Value {
+    fn real(value: Option<f64>) -> Self {
+        value.map(Value::Real).unwrap_or(Value::Null)
+    }
+
+    fn text(value: Option<&str>) -> Self {
+        value
+            .map(|v| Value::Text(v.to_string()))
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Attribute values of a junction, in the order of [`COLUMNS`]
+pub fn attributes(junction: &Junction) -> Vec<Value> {
+    let bearing = |i: usize| Value::real(junction.bearings.get(i).map(|&b| b as f64));
+    let areas = &junction.areas;
+    let area = |area: &Option<AreaRef>| {
+        (
+            Value::text(area.as_ref().map(|a| a.code.as_str())),
+            Value::text(area.as_ref().map(|a| a.name.as_str())),
+        )
+    };
+    let (prefecture_code, prefecture_name) = area(&areas.prefecture);
+    let (city_code, city_name) = area(&areas.city);
+    let (ward_code, ward_name) = area(&areas.ward);
+
+    vec![
+        Value::Integer(junction.id),
+        Value::Integer(junction.osm_node_id),
+        Value::Integer(junction.angle_1.into()),
+        Value::Integer(junction.angle_2.into()),
+        Value::Integer(junction.angle_3.into()),
+        Value::Text(junction.angle_type().as_str().to_string()),
+        bearing(0),
+        bearing(1),
+        bearing(2),
+        Value::real(junction.elevation),
+        Value::real(junction.min_elevation_diff),
+        Value::real(junction.max_elevation_diff),
+        Value::real(junction.min_angle_elevation_diff),
+        Value::real(junction.local_slope),
+        Value::text(junction.elevation_source.as_deref()),
+        Value::text(junction.elevation_quality.as_deref()),
+        junction
+            .elevation_nodata_samples
+            .map(|v| Value::Integer(v.into()))
+            .unwrap_or(Value::Null),
+        Value::text(junction.elevation_skip_reason.as_deref()),
+        Value::real(junction.arm_grades[0]),
+        Value::real(junction.arm_grades[1]),
+        Value::real(junction.arm_grades[2]),
+        prefecture_code,
+        prefecture_name,
+        city_code,
+        city_name,
+        ward_code,
+        ward_name,
+        Value::text(junction.road_names[0].as_deref()),
+        Value::text(junction.road_names[1].as_deref()),
+        Value::text(junction.road_names[2].as_deref()),
+        Value::text(junction.highway_class.as_deref()),
+        Value::Integer(junction.has_bridge.into()),
+        Value::Integer(junction.has_tunnel.into()),
+        Value::Text(junction.created_at.to_rfc3339()),
+        Value::Text(junction.label()),
+        Value::Text(junction.streetview_url()),
+    ]
+}
+
+/// Attribute values of one feature keyed by column name, as read back from a dataset file
+pub type Attributes = HashMap<String, Value>;
+
+/// Rebuilds a junction from the attributes written by [`attributes`]
+///
+/// Derived columns (angle_type, label, streetview_url) are ignored, and columns missing from
+/// older files are left empty.
+pub fn junction_from_attributes(lon: f64, lat: f64, attributes: &Attributes) -> Result<Junction> {
+    let integer = |name: &str| match attributes.get(name) {
+        Some(Value::Integer(v)) => Some(*v),
+        _ => None,
+    };
+    let real = |name: &str| match attributes.get(name) {
+        Some(Value::Real(v)) => Some(*v),
+        Some(Value::Integer(v)) => Some(*v as f64),
+        _ => None,
+    };
+    let text = |name: &str| match attributes.get(name) {
+        Some(Value::Text(v)) => Some(v.clone()),
+        _ => None,
+    };
+    let angle = |name: &str| -> Result<i16> {
+        let angle = integer(name).with_context(|| format!("Feature without {}", name))?;
+        Ok(angle.try_into()?)
+    };
+    let area = |level: &str| {
+        let code = text(&format!("{}_code", level))?;
+        let name = text(&format!("{}_name", level)).unwrap_or_default();
+        Some(AreaRef { code, name })
+    };
+
+    let created_at = match text("created_at") {
+        Some(created_at) => DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+        None => DateTime::default(),
+    };
+
+    Ok(Junction {
+        id: integer("id").context("Feature without id")?,
+        osm_node_id: integer("osm_node_id").unwrap_or_default(),
+        lat,
+        lon,
+        angle_1: angle("angle_1")?,
+        angle_2: angle("angle_2")?,
+        angle_3: angle("angle_3")?,
+        bearings: ["bearing_1", "bearing_2", "bearing_3"]
+            .iter()
+            .filter_map(|name| real(name).map(|b| b as f32))
+            .collect(),
+        created_at,
+        elevation: real("elevation"),
+        min_elevation_diff: real("min_elevation_diff"),
+        max_elevation_diff: real("max_elevation_diff"),
+        min_angle_elevation_diff: real("min_angle_elevation_diff"),
+        local_slope: real("local_slope"),
+        elevation_source: text("elevation_source"),
+        elevation_quality: text("elevation_quality"),
+        elevation_nodata_samples: integer("elevation_nodata_samples")
+            .and_then(|v| v.try_into().ok()),
+        elevation_skip_reason: text("elevation_skip_reason"),
+        arm_grades: [
+            real("arm_grade_1"),
+            real("arm_grade_2"),
+            real("arm_grade_3"),
+        ],
+        elevation_profile: None,
+        distance_m: None,
+        areas: JunctionAreas {
+            prefecture: area("prefecture"),
+            city: area("city"),
+            ward: area("ward"),
+        },
+        road_names: [
+            text("road_name_1"),
+            text("road_name_2"),
+            text("road_name_3"),
+        ],
+        highway_class: text("highway_class"),
+        has_bridge: integer("has_bridge").unwrap_or_default() != 0,
+        has_tunnel: integer("has_tunnel").unwrap_or_default() != 0,
+    })
+}
+
+/// Loads every junction of a FlatGeobuf or GeoPackage file written by the `export` binary
+pub async fn read_junctions(path: &Path) -> Result<Vec<Junction>> {
+    match DatasetFormat::from_path(path) {
+        Some(DatasetFormat::FlatGeobuf) => {
+            let bytes = tokio::fs::read(path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            flatgeobuf::read_junctions(&bytes)
+        }
+        Some(DatasetFormat::GeoPackage) => geopackage::read_junctions(path).await,
+        Some(DatasetFormat::PmTiles) => {
+            anyhow::bail!("PMTiles only holds tile properties; use a FlatGeobuf or GeoPackage file")
+        }
+        None => anyhow::bail!("Unknown dataset format: {}", path.display()),
+    }
+}
+
+/// Position of (x, y) along the Hilbert curve filling a 2^order x 2^order grid
+pub fn hilbert_index(order: u32, mut x: u64, mut y: u64) -> u64 {
+    let n = 1u64 << order;
+    let mut index = 0;
+    let mut s = n >> 1;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        index += s * s * ((3 * rx) ^ ry);
+        // 部分正方形の向きに合わせて回転する
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s >>= 1;
+    }
+    index
+}
+
+/// Bounding box (min_lon, min_lat, max_lon, max_lat) of the exported junctions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl Bounds {
+    pub fn point(lon: f64, lat: f64) -> Self {
+        Self {
+            min_lon: lon,
+            min_lat: lat,
+            max_lon: lon,
+            max_lat: lat,
+        }
+    }
+
+    pub fn expand(&mut self, other: &Bounds) {
+        self.min_lon = self.min_lon.min(other.min_lon);
+        self.min_lat = self.min_lat.min(other.min_lat);
+        self.max_lon = self.max_lon.max(other.max_lon);
+        self.max_lat = self.max_lat.max(other.max_lat);
+    }
+
+    /// Bounds of all points, None if there are none
+    pub fn of_points(points: impl IntoIterator<Item = (f64, f64)>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, (lon, lat)| {
+            let point = Bounds::point(lon, lat);
+            Some(match bounds {
+                Some(mut bounds) => {
+                    bounds.expand(&point);
+                    bounds
+                }
+                None => point,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::domain::JunctionAreas;
+    use chrono::Utc;
+
+    pub(crate) fn junction(id: i64, lon: f64, lat: f64) -> Junction {
+        Junction {
+            id,
+            osm_node_id: 1000 + id,
+            lat,
+            lon,
+            angle_1: 20,
+            angle_2: 150,
+            angle_3: 190,
+            bearings: vec![10.0, 30.0, 180.0],
+            created_at: Utc::now(),
+            elevation: Some(12.5),
+            min_elevation_diff: None,
+            max_elevation_diff: None,
+            min_angle_elevation_diff: Some(1.5),
+            local_slope: None,
+            elevation_source: Some("DEM5A".to_string()),
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [Some(2.5), None, Some(-1.0)],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas {
+                prefecture: Some(AreaRef {
+                    code: "JP-13".to_string(),
+                    name: "東京都".to_string(),
+                }),
+                ..Default::default()
+            },
+            road_names: [Some("旧中山道".to_string()), None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
+        }
+    }
+
+    /// Asserts that a junction read back from a dataset equals the one written
+    pub(crate) fn assert_same_junction(read: &Junction, written: &Junction) {
+        assert_eq!(
+            serde_json::to_value(read).unwrap(),
+            serde_json::to_value(written).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_junction_from_attributes() {
+        let mut original = junction(7, 139.5, 35.25);
+        original.has_tunnel = true;
+        original.elevation_nodata_samples = Some(2);
+        let attributes: Attributes = COLUMNS
+            .iter()
+            .map(|c| c.name.to_string())
+            .zip(super::attributes(&original))
+            .collect();
+
+        let junction = junction_from_attributes(139.5, 35.25, &attributes).unwrap();
+        assert_same_junction(&junction, &original);
+
+        // 角度のない行は読めない
+        let mut attributes = attributes;
+        attributes.remove("angle_1");
+        assert!(junction_from_attributes(139.5, 35.25, &attributes).is_err());
+    }
+
+    #[test]
+    fn test_dataset_format_from_path() {
+        assert_eq!(
+            DatasetFormat::from_path(Path::new("out/y.fgb")),
+            Some(DatasetFormat::FlatGeobuf)
+        );
+        assert_eq!(
+            DatasetFormat::from_path(Path::new("y.GPKG")),
+            Some(DatasetFormat::GeoPackage)
+        );
+        assert_eq!(
+            DatasetFormat::from_path(Path::new("y.pmtiles")),
+            Some(DatasetFormat::PmTiles)
+        );
+        assert_eq!(DatasetFormat::from_path(Path::new("y.geojson")), None);
+        assert_eq!(DatasetFormat::from_path(Path::new("y")), None);
+    }
+
+    #[test]
+    fn test_attributes_match_columns() {
+        let values = attributes(&junction(7, 139.7, 35.6));
+        assert_eq!(values.len(), COLUMNS.len());
+
+        for (column, value) in COLUMNS.iter().zip(&values) {
+            match value {
+                Value::Integer(_) => assert_eq!(column.column_type, ColumnType::Integer),
+                Value::Real(_) => assert_eq!(column.column_type, ColumnType::Real),
+                Value::Text(_) => assert_eq!(column.column_type, ColumnType::Text),
+                Value::Null => {}
+            }
+        }
+        let value = |name: &str| {
+            let index = COLUMNS.iter().position(|c| c.name == name).unwrap();
+            values[index].clone()
+        };
+        assert_eq!(value("id"), Value::Integer(7));
+        assert_eq!(value("angle_type"), Value::Text("verysharp".to_string()));
+        assert_eq!(value("prefecture_code"), Value::Text("JP-13".to_string()));
+        assert_eq!(value("city_code"), Value::Null);
+        assert_eq!(value("has_bridge"), Value::Integer(0));
+    }
+
+    #[test]
+    fn test_hilbert_index() {
+        // 2x2 のグリッドは (0,0) → (0,1) → (1,1) → (1,0) の順に通る
+        assert_eq!(hilbert_index(1, 0, 0), 0);
+        assert_eq!(hilbert_index(1, 0, 1), 1);
+        assert_eq!(hilbert_index(1, 1, 1), 2);
+        assert_eq!(hilbert_index(1, 1, 0), 3);
+
+        // 隣り合う位置はすべて隣のセル
+        let order = 3;
+        let mut cells = vec![(0, 0); 64];
+        for x in 0..8 {
+            for y in 0..8 {
+                cells[hilbert_index(order, x, y) as usize] = (x, y);
+            }
+        }
+        for pair in cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+        }
+    }
+
+    #[test]
+    fn test_bounds_of_points() {
+        let bounds = Bounds::of_points([(139.0, 35.0), (140.0, 34.5)]).unwrap();
+        assert_eq!(
+            bounds,
+            Bounds {
+                min_lon: 139.0,
+                min_lat: 34.5,
+                max_lon: 140.0,
+                max_lat: 35.0
+            }
+        );
+        assert_eq!(Bounds::of_points([]), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Minimal Mapbox Vector Tile encoder for point layers
+//!
+//! See https://github.com/mapbox/vector-tile-spec/tree/master/2.1 for the protobuf schema.
+
+use std::collections::HashMap;
+
+use super::Value;
+
+/// Tile coordinates range over 0..EXTENT on each axis
+pub const EXTENT: u32 = 4096;
+
+// protobuf のワイヤータイプ
+const VARINT: u32 = 0;
+const FIXED64: u32 = 1;
+const LEN: u32 = 2;
+
+// ジオメトリのコマンド MoveTo（1 点）
+const MOVE_TO_ONE: u32 = 1 | (1 << 3);
+const GEOM_TYPE_POINT: u64 = 1;
+
+/// One layer of point features
+pub struct PointLayer {
+    name: String,
+    keys: Vec<String>,
+    key_index: HashMap<String, u32>,
+    values: Vec<Vec<u8>>,
+    value_index: HashMap<Vec<u8>, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl PointLayer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            keys: Vec::new(),
+            key_index: HashMap::new(),
+            values: Vec::new(),
+            value_index: HashMap::new(),
+            features: Vec::new(),
+        }
+    }
+
+    /// Adds a point at tile coordinates (x, y); null properties are left out
+    pub fn add_point(&mut self, id: u64, x: i32, y: i32, properties: &[(&str, Value)]) {
+        let mut tags = Vec::new();
+        for (key, value) in properties {
+            let Some(value) = encode_value(value) else {
+                continue;
+            };
+            tags.push(self.key(key));
+            tags.push(self.value(value));
+        }
+
+        let mut feature = Vec::new();
+        write_key(&mut feature, 1, VARINT);
+        write_varint(&mut feature, id);
+        write_packed(&mut feature, 2, &tags);
+        write_key(&mut feature, 3, VARINT);
+        write_varint(&mut feature, GEOM_TYPE_POINT);
+        write_packed(&mut feature, 4, &[MOVE_TO_ONE, zigzag(x), zigzag(y)]);
+        self.features.push(feature);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Encodes a tile containing only this layer
+    pub fn encode_tile(&self) -> Vec<u8> {
+        let mut layer = Vec::new();
+        write_key(&mut layer, 15, VARINT);
+        write_varint(&mut layer, 2);
+        write_bytes(&mut layer, 1, self.name.as_bytes());
+        for feature in &self.features {
+            write_bytes(&mut layer, 2, feature);
+        }
+        for key in &self.keys {
+            write_bytes(&mut layer, 3, key.as_bytes());
+        }
+        for value in &self.values {
+            write_bytes(&mut layer, 4, value);
+        }
+        write_key(&mut layer, 5, VARINT);
+        write_varint(&mut layer, EXTENT.into());
+
+        let mut tile = Vec::new();
+        write_bytes(&mut tile, 3, &layer);
+        tile
+    }
+
+    fn key(&mut self, key: &str) -> u32 {
+        if let Some(&index) = self.key_index.get(key) {
+            return index;
+        }
+        let index = self.keys.len() as u32;
+        self.keys.push(key.to_string());
+        self.key_index.insert(key.to_string(), index);
+        index
+    }
+
+    fn value(&mut self, value: Vec<u8>) -> u32 {
+        if let Some(&index) = self.value_index.get(&value) {
+            return index;
+        }
+        let index = self.values.len() as u32;
+        self.values.push(value.clone());
+        self.value_index.insert(value, index);
+        index
+    }
+}
+
+// Value メッセージ（string_value = 1, double_value = 3, sint_value = 6）
+fn encode_value(value: &Value) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match value {
+        Value::Text(v) => write_bytes(&mut bytes, 1, v.as_bytes()),
+        Value::Real(v) => {
+            write_key(&mut bytes, 3, FIXED64);
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Integer(v) => {
+            write_key(&mut bytes, 6, VARINT);
+            write_varint(&mut bytes, ((v << 1) ^ (v >> 63)) as u64);
+        }
+        Value::Null => return None,
+    }
+    Some(bytes)
+}
+
+fn zigzag(v: i32) -> u32 {
+    ((v << 1) ^ (v >> 31)) as u32
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn write_key(out: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(out, ((field << 3) | wire_type).into());
+}
+
+fn write_bytes(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(out, field, LEN);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_packed(out: &mut Vec<u8>, field: u32, values: &[u32]) {
+    let mut packed = Vec::new();
+    for &v in values {
+        write_varint(&mut packed, v.into());
+    }
+    write_bytes(out, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_and_zigzag() {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, 300);
+        assert_eq!(bytes, vec![0xac, 0x02]);
+
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(zigzag(-2), 3);
+    }
+
+    #[test]
+    fn test_encode_point_tile() {
+        let mut layer = PointLayer::new("junctions");
+        layer.add_point(
+            7,
+            25,
+            17,
+            &[
+                ("angle_type", Value::Text("sharp".to_string())),
+                ("elevation", Value::Null),
+            ],
+        );
+        layer.add_point(8, 1, 2, &[("angle_type", Value::Text("sharp".to_string()))]);
+        let tile = layer.encode_tile();
+
+        // キーと値は共有される
+        assert_eq!(layer.keys, vec!["angle_type"]);
+        assert_eq!(layer.values.len(), 1);
+
+        // id=7, tags=[0,0], type=POINT, geometry=[MoveTo(1), 50, 34]
+        let feature = [0x08, 7, 0x12, 2, 0, 0, 0x18, 1, 0x22, 3, 9, 50, 34];
+        assert!(tile.windows(feature.len()).any(|w| w == feature));
+        assert!(tile.windows(9).any(|w| w == b"junctions"));
+        // 先頭は layers フィールド
+        assert_eq!(tile[0], 0x1a);
+    }
+
+    #[test]
+    fn test_decode_with_geozero() {
+        use geozero::mvt::{Message, Tile};
+
+        let mut layer = PointLayer::new("junctions");
+        layer.add_point(
+            7,
+            -3,
+            4100,
+            &[
+                ("angle_type", Value::Text("sharp".to_string())),
+                ("angle_1", Value::Integer(-20)),
+                ("elevation", Value::Real(12.5)),
+                ("label", Value::Null),
+            ],
+        );
+        layer.add_point(300, 0, 0, &[("angle_1", Value::Integer(20))]);
+
+        let tile = Tile::decode(layer.encode_tile().as_slice()).unwrap();
+        assert_eq!(tile.layers.len(), 1);
+        let decoded = &tile.layers[0];
+        assert_eq!(decoded.version, 2);
+        assert_eq!(decoded.name, "junctions");
+        assert_eq!(decoded.extent, Some(EXTENT));
+        assert_eq!(decoded.keys, vec!["angle_type", "angle_1", "elevation"]);
+
+        let first = &decoded.features[0];
+        assert_eq!(first.id, Some(7));
+        assert_eq!(first.r#type, Some(GEOM_TYPE_POINT as i32));
+        assert_eq!(first.geometry, vec![MOVE_TO_ONE, zigzag(-3), zigzag(4100)]);
+        let properties: Vec<_> = first
+            .tags
+            .chunks(2)
+            .map(|tag| {
+                (
+                    decoded.keys[tag[0] as usize].as_str(),
+                    &decoded.values[tag[1] as usize],
+                )
+            })
+            .collect();
+        assert_eq!(properties.len(), 3);
+        assert_eq!(properties[0].1.string_value.as_deref(), Some("sharp"));
+        assert_eq!(properties[1].1.sint_value, Some(-20));
+        assert_eq!(properties[2].1.double_value, Some(12.5));
+
+        let second = &decoded.features[1];
+        assert_eq!(second.id, Some(300));
+        assert_eq!(second.tags, vec![1, 3]);
+        assert_eq!(decoded.values[3].sint_value, Some(20));
+    }
+}
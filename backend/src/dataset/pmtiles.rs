@@ -0,0 +1,575 @@
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::{BTreeMap, HashSet};
+use std::f64::consts::PI;
+use std::io::Write;
+
+use super::mvt::{self, PointLayer};
+use super::{attributes, hilbert_index, Bounds, Value, COLUMNS};
+use crate::domain::Junction;
+
+/// Name of the vector tile layer holding the junctions
+pub const LAYER_NAME: &str = "junctions";
+
+const HEADER_SIZE: usize = 127;
+// ヘッダーとルートディレクトリは先頭 16 KiB に収める（仕様）
+const MAX_ROOT_DIRECTORY_SIZE: usize = 16_384 - HEADER_SIZE;
+
+// ヘッダーの圧縮方式とタイル形式
+const COMPRESSION_GZIP: u8 = 2;
+const TILE_TYPE_MVT: u8 = 1;
+
+// 最大ズーム未満では、タイルを GRID_CELLS x GRID_CELLS に分けて各セル 1 点に間引く
+const GRID_CELLS: u32 = 64;
+
+/// Properties carried by each point in the vector tiles
+pub const TILE_PROPERTIES: &[&str] = &[
+    "id",
+    "angle_1",
+    "angle_2",
+    "angle_3",
+    "angle_type",
+    "elevation",
+    "min_angle_elevation_diff",
+    "label",
+    "streetview_url",
+];
+
+/// Writes junctions as a PMTiles v3 archive of Mapbox Vector Tiles
+///
+/// Below `max_zoom` the points are thinned out to one per 1/64 of a tile, keeping the sharpest
+/// junction, so that tiles stay small at country-wide zooms. All junctions appear at `max_zoom`,
+/// and map viewers overzoom beyond it.
+pub struct PmTilesWriter {
+    min_zoom: u8,
+    max_zoom: u8,
+    points: Vec<TilePoint>,
+}
+
+struct TilePoint {
+    id: u64,
+    lon: f64,
+    lat: f64,
+    min_angle: i16,
+    properties: Vec<(&'static str, Value)>,
+}
+
+/// Directory entry: a run of tiles with the same content, or a leaf directory when run_length is 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    tile_id: u64,
+    offset: u64,
+    length: u32,
+    run_length: u32,
+}
+
+impl PmTilesWriter {
+    pub fn new(min_zoom: u8, max_zoom: u8) -> Self {
+        Self {
+            min_zoom,
+            max_zoom,
+            points: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, junction: &Junction) {
+        let properties = COLUMNS
+            .iter()
+            .zip(attributes(junction))
+            .filter(|(column, _)| TILE_PROPERTIES.contains(&column.name))
+            .map(|(column, value)| (column.name, value))
+            .collect();
+
+        self.points.push(TilePoint {
+            id: junction.id as u64,
+            lon: junction.lon,
+            lat: junction.lat,
+            min_angle: junction.angle_1.min(junction.angle_2).min(junction.angle_3),
+            properties,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn finish(mut self, mut out: impl Write) -> Result<()> {
+        // 鋭いものから順に置いていき、セルが空いていれば採用する
+        self.points.sort_by_key(|p| (p.min_angle, p.id));
+
+        let mut tiles: BTreeMap<u64, Vec<(usize, i32, i32)>> = BTreeMap::new();
+        for zoom in self.min_zoom..=self.max_zoom {
+            let mut occupied = HashSet::new();
+            for (index, point) in self.points.iter().enumerate() {
+                let (x, y) = world_coords(point.lon, point.lat, zoom);
+                let (tile_x, tile_y) = (x / mvt::EXTENT, y / mvt::EXTENT);
+                if zoom < self.max_zoom {
+                    let cell_size = mvt::EXTENT / GRID_CELLS;
+                    if !occupied.insert((x / cell_size, y / cell_size)) {
+                        continue;
+                    }
+                }
+                tiles
+                    .entry(tile_id(zoom, tile_x.into(), tile_y.into()))
+                    .or_default()
+                    .push((
+                        index,
+                        (x - tile_x * mvt::EXTENT) as i32,
+                        (y - tile_y * mvt::EXTENT) as i32,
+                    ));
+            }
+        }
+
+        // タイルIDの順に並べる（clustered）
+        let mut tile_data = Vec::new();
+        let mut entries = Vec::with_capacity(tiles.len());
+        for (tile_id, points) in &tiles {
+            let mut layer = PointLayer::new(LAYER_NAME);
+            for &(index, x, y) in points {
+                let point = &self.points[index];
+                layer.add_point(point.id, x, y, &point.properties);
+            }
+            let tile = gzip(&layer.encode_tile())?;
+            entries.push(Entry {
+                tile_id: *tile_id,
+                offset: tile_data.len() as u64,
+                length: tile.len() as u32,
+                run_length: 1,
+            });
+            tile_data.extend_from_slice(&tile);
+        }
+
+        let bounds = Bounds::of_points(self.points.iter().map(|p| (p.lon, p.lat)))
+            .unwrap_or(Bounds::point(0.0, 0.0));
+        let metadata = gzip(self.metadata().to_string().as_bytes())?;
+        let (root, leaves) = build_directories(&entries)?;
+
+        let root_offset = HEADER_SIZE as u64;
+        let metadata_offset = root_offset + root.len() as u64;
+        let leaves_offset = metadata_offset + metadata.len() as u64;
+        let tile_data_offset = leaves_offset + leaves.len() as u64;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(b"PMTiles");
+        header.push(3);
+        for v in [
+            root_offset,
+            root.len() as u64,
+            metadata_offset,
+            metadata.len() as u64,
+            leaves_offset,
+            leaves.len() as u64,
+            tile_data_offset,
+            tile_data.len() as u64,
+            // 重複タイルはまとめないので、タイル数・エントリ数・内容の数はすべて同じ
+            entries.len() as u64,
+            entries.len() as u64,
+            entries.len() as u64,
+        ] {
+            header.extend_from_slice(&v.to_le_bytes());
+        }
+        header.extend_from_slice(&[
+            1,
+            COMPRESSION_GZIP,
+            COMPRESSION_GZIP,
+            TILE_TYPE_MVT,
+            self.min_zoom,
+            self.max_zoom,
+        ]);
+        for v in [
+            bounds.min_lon,
+            bounds.min_lat,
+            bounds.max_lon,
+            bounds.max_lat,
+        ] {
+            header.extend_from_slice(&e7(v).to_le_bytes());
+        }
+        header.push(self.min_zoom);
+        header.extend_from_slice(&e7((bounds.min_lon + bounds.max_lon) / 2.0).to_le_bytes());
+        header.extend_from_slice(&e7((bounds.min_lat + bounds.max_lat) / 2.0).to_le_bytes());
+        debug_assert_eq!(header.len(), HEADER_SIZE);
+
+        for part in [&header, &root, &metadata, &leaves, &tile_data] {
+            out.write_all(part)?;
+        }
+        out.flush()?;
+
+        Ok(())
+    }
+
+    fn metadata(&self) -> serde_json::Value {
+        let fields: serde_json::Map<String, serde_json::Value> = COLUMNS
+            .iter()
+            .filter(|column| TILE_PROPERTIES.contains(&column.name))
+            .map(|column| {
+                let field_type = match column.column_type {
+                    super::ColumnType::Text => "String",
+                    _ => "Number",
+                };
+                (column.name.to_string(), field_type.into())
+            })
+            .collect();
+
+        serde_json::json!({
+            "name": "Y-junctions",
+            "format": "pbf",
+            "attribution": "© OpenStreetMap contributors",
+            "vector_layers": [{
+                "id": LAYER_NAME,
+                "fields": fields,
+                "minzoom": self.min_zoom,
+                "maxzoom": self.max_zoom,
+            }],
+        })
+    }
+}
+
+/// Position in pixels of the whole Web Mercator world at `zoom`, each tile being EXTENT pixels
+fn world_coords(lon: f64, lat: f64, zoom: u8) -> (u32, u32) {
+    let size = f64::from(mvt::EXTENT) * f64::from(1u32 << zoom);
+    let lat = lat.to_radians();
+    let x = (lon + 180.0) / 360.0 * size;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * size;
+    let clamp = |v: f64| v.clamp(0.0, size - 1.0) as u32;
+    (clamp(x), clamp(y))
+}
+
+/// PMTiles tile id: tiles of lower zooms first, then along the Hilbert curve
+pub fn tile_id(zoom: u8, x: u64, y: u64) -> u64 {
+    // 4^0 + 4^1 + ... + 4^(zoom-1)
+    let base = ((1u64 << (2 * u32::from(zoom))) - 1) / 3;
+    base + hilbert_index(zoom.into(), x, y)
+}
+
+fn e7(v: f64) -> i32 {
+    (v * 10_000_000.0).round() as i32
+}
+
+fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+fn serialize_directory(entries: &[Entry]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    mvt::write_varint(&mut bytes, entries.len() as u64);
+
+    let mut last_id = 0;
+    for entry in entries {
+        mvt::write_varint(&mut bytes, entry.tile_id - last_id);
+        last_id = entry.tile_id;
+    }
+    for entry in entries {
+        mvt::write_varint(&mut bytes, entry.run_length.into());
+    }
+    for entry in entries {
+        mvt::write_varint(&mut bytes, entry.length.into());
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        // 直前のエントリの続きなら 0、そうでなければ offset + 1
+        if i > 0 && entry.offset == entries[i - 1].offset + u64::from(entries[i - 1].length) {
+            mvt::write_varint(&mut bytes, 0);
+        } else {
+            mvt::write_varint(&mut bytes, entry.offset + 1);
+        }
+    }
+
+    bytes
+}
+
+/// Compressed root directory and leaf directories
+///
+/// When all entries do not fit in the root directory, they are split into leaf directories
+/// that are made larger until the root directory pointing to them fits.
+fn build_directories(entries: &[Entry]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let root = gzip(&serialize_directory(entries))?;
+    if root.len() <= MAX_ROOT_DIRECTORY_SIZE {
+        return Ok((root, Vec::new()));
+    }
+
+    let mut leaf_size = 4096;
+    loop {
+        let mut leaves = Vec::new();
+        let mut root_entries = Vec::new();
+        for chunk in entries.chunks(leaf_size) {
+            let leaf = gzip(&serialize_directory(chunk))?;
+            root_entries.push(Entry {
+                tile_id: chunk[0].tile_id,
+                offset: leaves.len() as u64,
+                length: leaf.len() as u32,
+                run_length: 0,
+            });
+            leaves.extend_from_slice(&leaf);
+        }
+
+        let root = gzip(&serialize_directory(&root_entries))?;
+        if root.len() <= MAX_ROOT_DIRECTORY_SIZE {
+            return Ok((root, leaves));
+        }
+        leaf_size += leaf_size / 5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::junction;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn gunzip(bytes: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut output).unwrap();
+        output
+    }
+
+    fn read_varints(bytes: &[u8]) -> Vec<u64> {
+        let mut values = Vec::new();
+        let (mut value, mut shift) = (0, 0);
+        for &b in bytes {
+            value |= u64::from(b & 0x7f) << shift;
+            shift += 7;
+            if b < 0x80 {
+                values.push(value);
+                (value, shift) = (0, 0);
+            }
+        }
+        values
+    }
+
+    fn u64_at(bytes: &[u8], at: usize) -> u64 {
+        u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_tile_id() {
+        // 仕様の例
+        assert_eq!(tile_id(0, 0, 0), 0);
+        assert_eq!(tile_id(1, 0, 0), 1);
+        assert_eq!(tile_id(1, 0, 1), 2);
+        assert_eq!(tile_id(1, 1, 1), 3);
+        assert_eq!(tile_id(1, 1, 0), 4);
+        assert_eq!(tile_id(2, 0, 0), 5);
+    }
+
+    #[test]
+    fn test_world_coords() {
+        assert_eq!(world_coords(0.0, 0.0, 0), (2048, 2048));
+        assert_eq!(world_coords(-180.0, 89.0, 1), (0, 0));
+        // 東京駅はズーム 12 のタイル (3638, 1612)
+        let (x, y) = world_coords(139.7671, 35.6812, 12);
+        assert_eq!((x / mvt::EXTENT, y / mvt::EXTENT), (3638, 1612));
+    }
+
+    #[test]
+    fn test_serialize_directory() {
+        let entries = [
+            Entry {
+                tile_id: 5,
+                offset: 0,
+                length: 10,
+                run_length: 1,
+            },
+            Entry {
+                tile_id: 7,
+                offset: 10,
+                length: 20,
+                run_length: 1,
+            },
+            Entry {
+                tile_id: 8,
+                offset: 100,
+                length: 5,
+                run_length: 2,
+            },
+        ];
+        assert_eq!(
+            read_varints(&serialize_directory(&entries)),
+            vec![3, 5, 2, 1, 1, 1, 2, 10, 20, 5, 1, 0, 101]
+        );
+    }
+
+    #[test]
+    fn test_archive_layout() {
+        let mut writer = PmTilesWriter::new(0, 2);
+        writer.add(&junction(1, 139.0, 35.0));
+        writer.add(&junction(2, 139.0001, 35.0001));
+        writer.add(&junction(3, -70.0, -30.0));
+
+        let mut output = Vec::new();
+        writer.finish(&mut output).unwrap();
+
+        assert_eq!(&output[..8], b"PMTiles\x03");
+        let root_offset = u64_at(&output, 8) as usize;
+        let root_length = u64_at(&output, 16) as usize;
+        let metadata_offset = u64_at(&output, 24) as usize;
+        let metadata_length = u64_at(&output, 32) as usize;
+        let tile_data_offset = u64_at(&output, 56) as usize;
+        let tile_count = u64_at(&output, 72);
+        assert_eq!(root_offset, HEADER_SIZE);
+        assert_eq!(output[96..100], [1, 2, 2, 1]);
+        assert_eq!((output[100], output[101]), (0, 2));
+
+        // ズーム 0 に 1 枚、ズーム 1・2 に 2 枚ずつ
+        let root = read_varints(&gunzip(&output[root_offset..root_offset + root_length]));
+        assert_eq!(root[0], 5);
+        assert_eq!(tile_count, 5);
+        assert_eq!(root[1], 0);
+
+        let metadata: serde_json::Value = serde_json::from_slice(&gunzip(
+            &output[metadata_offset..metadata_offset + metadata_length],
+        ))
+        .unwrap();
+        assert_eq!(metadata["vector_layers"][0]["id"], LAYER_NAME);
+
+        // ズーム 0 では近い 2 点が同じセルに入るので、南米の点と合わせて 2 点
+        let z0_length = root[1 + 5 + 5] as usize;
+        let z0 = gunzip(&output[tile_data_offset..tile_data_offset + z0_length]);
+        assert_eq!(count_features(&z0), 2);
+    }
+
+    // pmtiles クレートのリーダーで開く（一時ディレクトリはリーダーより長く残す）
+    async fn open_with_pmtiles_crate(
+        writer: PmTilesWriter,
+    ) -> (
+        tempfile::TempDir,
+        pmtiles::AsyncPmTilesReader<pmtiles::MmapBackend>,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("y-junctions.pmtiles");
+        writer
+            .finish(std::fs::File::create(&path).unwrap())
+            .unwrap();
+        let reader = pmtiles::AsyncPmTilesReader::new_with_path(&path)
+            .await
+            .unwrap();
+        (dir, reader)
+    }
+
+    async fn read_tile(
+        reader: &pmtiles::AsyncPmTilesReader<pmtiles::MmapBackend>,
+        zoom: u8,
+        lon: f64,
+        lat: f64,
+    ) -> Option<geozero::mvt::Tile> {
+        use geozero::mvt::Message;
+
+        let (x, y) = world_coords(lon, lat, zoom);
+        let coord = pmtiles::TileCoord::new(zoom, x / mvt::EXTENT, y / mvt::EXTENT).unwrap();
+        let tile = reader.get_tile_decompressed(coord).await.unwrap()?;
+        Some(geozero::mvt::Tile::decode(tile).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_read_with_pmtiles_crate() {
+        let mut writer = PmTilesWriter::new(0, 2);
+        writer.add(&junction(1, 139.0, 35.0));
+        writer.add(&junction(2, 139.0001, 35.0001));
+        writer.add(&junction(3, -70.0, -30.0));
+        let (_dir, reader) = open_with_pmtiles_crate(writer).await;
+
+        let header = reader.get_header();
+        assert_eq!(header.tile_type, pmtiles::TileType::Mvt);
+        assert_eq!(header.tile_compression, pmtiles::Compression::Gzip);
+        assert_eq!((header.min_zoom, header.max_zoom), (0, 2));
+        assert_eq!(header.min_longitude, -70.0);
+        assert_eq!(header.max_latitude, 35.0001);
+
+        let metadata: serde_json::Value =
+            serde_json::from_str(&reader.get_metadata().await.unwrap()).unwrap();
+        assert_eq!(metadata["vector_layers"][0]["id"], LAYER_NAME);
+
+        // 最大ズームでは近い 2 点とも残る
+        let tile = read_tile(&reader, 2, 139.0, 35.0).await.unwrap();
+        assert_eq!(tile.layers.len(), 1);
+        let layer = &tile.layers[0];
+        assert_eq!(layer.name, LAYER_NAME);
+        assert_eq!(layer.extent, Some(mvt::EXTENT));
+        let mut ids: Vec<_> = layer.features.iter().map(|f| f.id.unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+        assert!(layer
+            .keys
+            .iter()
+            .all(|key| TILE_PROPERTIES.contains(&key.as_str())));
+        let angle_type = layer.keys.iter().position(|k| k == "angle_type").unwrap();
+        let value = layer.features[0]
+            .tags
+            .chunks(2)
+            .find(|tag| tag[0] as usize == angle_type)
+            .map(|tag| &layer.values[tag[1] as usize])
+            .unwrap();
+        assert_eq!(value.string_value.as_deref(), Some("verysharp"));
+
+        // ズーム 0 では間引かれる
+        let tile = read_tile(&reader, 0, 0.0, 0.0).await.unwrap();
+        assert_eq!(tile.layers[0].features.len(), 2);
+
+        // 点のないタイルはアーカイブにない
+        assert!(read_tile(&reader, 2, 0.0, 80.0).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_leaf_directories_with_pmtiles_crate() {
+        // 散らばった点で、ルートディレクトリに収まらない数のタイルを作る
+        let mut writer = PmTilesWriter::new(12, 12);
+        let mut seed: u64 = 1;
+        let mut random = || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let points: Vec<(f64, f64)> = (0..12_000)
+            .map(|_| (random() * 340.0 - 170.0, random() * 140.0 - 70.0))
+            .collect();
+        for (i, &(lon, lat)) in points.iter().enumerate() {
+            writer.add(&junction(i as i64, lon, lat));
+        }
+        let (dir, reader) = open_with_pmtiles_crate(writer).await;
+
+        let output = std::fs::read(dir.path().join("y-junctions.pmtiles")).unwrap();
+        assert!(
+            u64_at(&output, 48) > 0,
+            "leaf directories should be written"
+        );
+
+        for (i, &(lon, lat)) in points.iter().enumerate().step_by(97) {
+            let tile = read_tile(&reader, 12, lon, lat).await.unwrap();
+            assert!(tile.layers[0]
+                .features
+                .iter()
+                .any(|f| f.id == Some(i as u64)));
+        }
+    }
+
+    // 長さ付きフィールド (番号, 中身) を並べる（varint フィールドは読み飛ばす）
+    fn len_fields(mut bytes: &[u8]) -> Vec<(u8, &[u8])> {
+        let mut fields = Vec::new();
+        while let Some((&key, rest)) = bytes.split_first() {
+            let end = rest.iter().position(|b| b & 0x80 == 0).unwrap() + 1;
+            let varint = read_varints(&rest[..end])[0] as usize;
+            bytes = &rest[end..];
+            if key & 0x07 == 2 {
+                fields.push((key >> 3, &bytes[..varint]));
+                bytes = &bytes[varint..];
+            }
+        }
+        fields
+    }
+
+    fn count_features(tile: &[u8]) -> usize {
+        let layers = len_fields(tile);
+        assert_eq!(layers.len(), 1);
+        len_fields(layers[0].1)
+            .iter()
+            .filter(|(field, _)| *field == 2)
+            .count()
+    }
+}
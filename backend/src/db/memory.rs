@@ -0,0 +1,1216 @@
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use geo::line_intersection::{line_intersection, LineIntersection};
+use geo::{
+    BoundingRect, Coord, HaversineDistance, Intersects, LineString, MultiPolygon, Point, Polygon,
+};
+use md5::{Digest, Md5};
+use rstar::primitives::GeomWithData;
+use rstar::{RTree, AABB};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use super::repository::{
+    dense_histogram, ElevationCoverage, FilterParams, JunctionPage, JunctionSort, JunctionStats,
+    PageCursor, Sampling, SearchArea, ELEVATION_DIFF_BINS, ELEVATION_DIFF_BIN_M, MIN_ANGLE_BINS,
+    MIN_ANGLE_BIN_DEG,
+};
+use super::JunctionRepository;
+use crate::dataset::mvt::{PointLayer, EXTENT};
+use crate::dataset::Value;
+use crate::domain::{
+    AdminLevel, AngleType, AngleTypeCounts, AreaRef, AreaSummary, Junction, JunctionAreas,
+    JunctionCluster,
+};
+
+// 半径検索の外接矩形の計算に使う地球の半径（m）
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+// Web メルカトル（EPSG:3857）の地球の半径（m）
+const WEB_MERCATOR_RADIUS_M: f64 = 6_378_137.0;
+
+type IndexedPoint = GeomWithData<[f64; 2], usize>;
+
+/// Junctions held in memory with an R-tree over their locations
+///
+/// Used to serve a dataset file without a database and to test the API without PostgreSQL.
+/// Queries follow the same filter, sort, paging, sampling and aggregation rules as the
+/// PostgreSQL ones, except that:
+///
+/// - distances are computed on a sphere rather than the WGS 84 spheroid
+/// - `q` only matches the road names stored with the junction, not their readings
+/// - areas are only listed when at least one junction lies in them
+/// - search polygons are only checked for self-intersecting rings
+pub struct MemoryRepository {
+    /// Sorted by id
+    junctions: Vec<Junction>,
+    index: RTree<IndexedPoint>,
+}
+
+impl MemoryRepository {
+    pub fn new(mut junctions: Vec<Junction>) -> Self {
+        junctions.sort_by_key(|junction| junction.id);
+        let points = junctions
+            .iter()
+            .enumerate()
+            .map(|(i, junction)| IndexedPoint::new([junction.lon, junction.lat], i))
+            .collect();
+
+        Self {
+            junctions,
+            index: RTree::bulk_load(points),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.junctions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.junctions.is_empty()
+    }
+
+    /// Junctions in the area that pass the filters, in id order
+    fn matching(
+        &self,
+        area: &SearchArea,
+        filters: &FilterParams,
+    ) -> Result<Vec<&Junction>, sqlx::Error> {
+        let mut indices: Vec<usize> = match area {
+            &SearchArea::Bbox(min_lon, min_lat, max_lon, max_lat) => {
+                self.in_envelope([min_lon, min_lat], [max_lon, max_lat])
+            }
+            &SearchArea::Radius { lat, lon, radius_m } => {
+                let center = Point::new(lon, lat);
+                let (lower, upper) = radius_envelope(lat, lon, radius_m);
+                self.in_envelope(lower, upper)
+                    .into_iter()
+                    .filter(|&i| distance_m(&self.junctions[i], center) <= radius_m)
+                    .collect()
+            }
+            SearchArea::Polygon(geojson) => {
+                let polygons = parse_polygons(geojson)?;
+                match polygons.bounding_rect() {
+                    Some(rect) => self
+                        .in_envelope(rect.min().into(), rect.max().into())
+                        .into_iter()
+                        .filter(|&i| {
+                            let junction = &self.junctions[i];
+                            polygons.intersects(&Point::new(junction.lon, junction.lat))
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                }
+            }
+            SearchArea::Everywhere => (0..self.junctions.len()).collect(),
+        };
+        indices.sort_unstable();
+
+        Ok(indices
+            .into_iter()
+            .map(|i| &self.junctions[i])
+            .filter(|junction| matches_filters(junction, filters))
+            .collect())
+    }
+
+    /// Junctions in the area that pass the filters, in the requested sort order
+    fn sorted(
+        &self,
+        area: &SearchArea,
+        filters: &FilterParams,
+    ) -> Result<Vec<Junction>, sqlx::Error> {
+        let mut matched = self.matching(area, filters)?;
+        sort_junctions(&mut matched, &filters.sort);
+        Ok(with_distance(matched, filters.center.or(area.center())))
+    }
+
+    fn in_envelope(&self, lower: [f64; 2], upper: [f64; 2]) -> Vec<usize> {
+        self.index
+            .locate_in_envelope(&AABB::from_corners(lower, upper))
+            .map(|point| point.data)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl JunctionRepository for MemoryRepository {
+    async fn find_junctions(
+        &self,
+        area: &SearchArea,
+        filters: FilterParams,
+    ) -> Result<JunctionPage, sqlx::Error> {
+        let limit = filters.limit.unwrap_or(500).clamp(0, 1000) as usize;
+        let center = filters.center.or(area.center());
+
+        let matched = self.matching(area, &filters)?;
+        // PostgreSQL と同じく、カーソルで続きを取得するときは数えない
+        let total_count = filters.after.is_none().then_some(matched.len() as i64);
+        let sort_key = |junction: &Junction| sort_key(&filters.sort, junction);
+
+        if let Some(sampling) = filters.sampling.filter(|_| matched.len() > limit) {
+            let mut sample = sample(matched, sampling, limit);
+            sort_junctions(&mut sample, &filters.sort);
+            return Ok(JunctionPage {
+                junctions: with_distance(sample, center),
+                total_count,
+                next_cursor: None,
+                sampled: Some(sampling),
+            });
+        }
+
+        let mut rows: Vec<&Junction> = match filters.after {
+            Some(cursor) => matched
+                .into_iter()
+                .filter(|junction| {
+                    after_cursor(&filters.sort, sort_key(junction), junction.id, &cursor)
+                })
+                .collect(),
+            None => matched,
+        };
+        sort_junctions(&mut rows, &filters.sort);
+
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|junction| PageCursor {
+                key: sort_key(junction),
+                id: junction.id,
+            })
+        } else {
+            None
+        };
+
+        Ok(JunctionPage {
+            junctions: with_distance(rows, center),
+            total_count,
+            next_cursor,
+            sampled: None,
+        })
+    }
+
+    fn stream_junctions(
+        &self,
+        area: SearchArea,
+        filters: FilterParams,
+    ) -> BoxStream<'static, Result<Junction, sqlx::Error>> {
+        match self.sorted(&area, &filters) {
+            Ok(junctions) => stream::iter(junctions.into_iter().map(Ok)).boxed(),
+            Err(err) => stream::once(async { Err(err) }).boxed(),
+        }
+    }
+
+    async fn find_nearest(
+        &self,
+        lat: f64,
+        lon: f64,
+        k: i64,
+        filters: FilterParams,
+    ) -> Result<Vec<Junction>, sqlx::Error> {
+        let filters = FilterParams {
+            sort: JunctionSort::Distance { lat, lon },
+            center: Some((lat, lon)),
+            ..filters
+        };
+        let mut junctions = self.sorted(&SearchArea::Everywhere, &filters)?;
+        junctions.truncate(k.max(0) as usize);
+        Ok(junctions)
+    }
+
+    async fn find_tile(
+        &self,
+        (z, x, y): (u32, u32, u32),
+        area: &SearchArea,
+        filters: &FilterParams,
+        max_features: i64,
+    ) -> Result<Vec<u8>, sqlx::Error> {
+        let mut matched = self.matching(area, filters)?;
+        matched.sort_by_key(|junction| (min_angle(junction), junction.id));
+        matched.truncate(max_features.max(0) as usize);
+
+        let scale = f64::from(1u32 << z);
+        let mut layer = PointLayer::new("junctions");
+        for junction in matched {
+            // タイル内のピクセル座標（ST_AsMVTGeom と同じく四捨五入）
+            let (unit_x, unit_y) = web_mercator_unit(junction.lon, junction.lat);
+            let pixel_x = ((unit_x * scale - f64::from(x)) * f64::from(EXTENT)).round();
+            let pixel_y = ((unit_y * scale - f64::from(y)) * f64::from(EXTENT)).round();
+            let real = |value: Option<f64>| value.map_or(Value::Null, Value::Real);
+            layer.add_point(
+                junction.id as u64,
+                pixel_x as i32,
+                pixel_y as i32,
+                &[
+                    ("id", Value::Integer(junction.id)),
+                    ("angle_1", Value::Integer(junction.angle_1.into())),
+                    ("angle_2", Value::Integer(junction.angle_2.into())),
+                    ("angle_3", Value::Integer(junction.angle_3.into())),
+                    ("min_angle", Value::Integer(min_angle(junction).into())),
+                    (
+                        "angle_type",
+                        Value::Text(junction.angle_type().as_str().to_string()),
+                    ),
+                    ("elevation", real(junction.elevation)),
+                    (
+                        "min_angle_elevation_diff",
+                        real(junction.min_angle_elevation_diff),
+                    ),
+                ],
+            );
+        }
+
+        Ok(if layer.is_empty() {
+            Vec::new()
+        } else {
+            layer.encode_tile()
+        })
+    }
+
+    async fn find_clusters(
+        &self,
+        area: &SearchArea,
+        filters: &FilterParams,
+        cell_size_m: f64,
+        max_clusters: i64,
+    ) -> Result<Vec<JunctionCluster>, sqlx::Error> {
+        let matched = self.matching(area, filters)?;
+
+        // ST_SnapToGrid と同じく、最も近い格子点（偶数丸め）ごとにまとめる
+        let mut cells: HashMap<(i64, i64), Vec<&Junction>> = HashMap::new();
+        for junction in matched {
+            let (x, y) = web_mercator_m(junction.lon, junction.lat);
+            let cell = (
+                (x / cell_size_m).round_ties_even() as i64,
+                (y / cell_size_m).round_ties_even() as i64,
+            );
+            cells.entry(cell).or_default().push(junction);
+        }
+
+        let mut clusters: Vec<JunctionCluster> = cells
+            .into_values()
+            .map(|junctions| {
+                let count = junctions.len();
+                let sharpest = junctions
+                    .iter()
+                    .min_by_key(|junction| (min_angle(junction), junction.id))
+                    .expect("cells are never empty");
+                JunctionCluster {
+                    lat: junctions.iter().map(|j| j.lat).sum::<f64>() / count as f64,
+                    lon: junctions.iter().map(|j| j.lon).sum::<f64>() / count as f64,
+                    count: count as i64,
+                    min_angle: min_angle(sharpest),
+                    sharpest_id: sharpest.id,
+                    by_type: type_counts(&junctions),
+                }
+            })
+            .collect();
+        clusters.sort_by_key(|cluster| (-cluster.count, cluster.sharpest_id));
+        clusters.truncate(max_clusters.max(0) as usize);
+
+        Ok(clusters)
+    }
+
+    async fn is_valid_polygon(&self, geojson: &str) -> Result<bool, sqlx::Error> {
+        let polygons = parse_polygons(geojson)?;
+        let valid = polygons.iter().all(|polygon| {
+            std::iter::once(polygon.exterior())
+                .chain(polygon.interiors())
+                .all(is_simple_ring)
+        });
+        Ok(valid)
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<Junction>, sqlx::Error> {
+        Ok(self
+            .junctions
+            .binary_search_by_key(&id, |junction| junction.id)
+            .ok()
+            .map(|i| self.junctions[i].clone()))
+    }
+
+    async fn junction_stats(
+        &self,
+        area: Option<&SearchArea>,
+        filters: &FilterParams,
+    ) -> Result<JunctionStats, sqlx::Error> {
+        let matched = self.matching(area.unwrap_or(&SearchArea::Everywhere), filters)?;
+
+        let mut by_type = HashMap::new();
+        let mut by_highway_class = HashMap::new();
+        let mut min_angle_bins: HashMap<i32, i64> = HashMap::new();
+        let mut elevation_diff_bins: HashMap<i32, i64> = HashMap::new();
+        for junction in &matched {
+            *by_type
+                .entry(junction.angle_type().as_str().to_string())
+                .or_insert(0) += 1;
+            *by_highway_class
+                .entry(
+                    junction
+                        .highway_class
+                        .clone()
+                        .unwrap_or_else(|| "unknown".to_string()),
+                )
+                .or_insert(0) += 1;
+
+            // 範囲外の値は最後のビンにまとめる
+            let bin = (min_angle(junction) as f64 / MIN_ANGLE_BIN_DEG).floor() as i32;
+            *min_angle_bins
+                .entry(bin.min(MIN_ANGLE_BINS - 1))
+                .or_insert(0) += 1;
+            if let Some(diff) = junction.min_angle_elevation_diff {
+                let bin = (diff / ELEVATION_DIFF_BIN_M).floor() as i32;
+                *elevation_diff_bins
+                    .entry(bin.min(ELEVATION_DIFF_BINS - 1))
+                    .or_insert(0) += 1;
+            }
+        }
+
+        let min_angle: Vec<(i32, i64)> = min_angle_bins.into_iter().collect();
+        let elevation_diff: Vec<(i32, i64)> = elevation_diff_bins.into_iter().collect();
+
+        Ok(JunctionStats {
+            total_count: matched.len() as i64,
+            by_type,
+            min_angle: dense_histogram(&min_angle, MIN_ANGLE_BIN_DEG, MIN_ANGLE_BINS, false),
+            elevation_diff: dense_histogram(
+                &elevation_diff,
+                ELEVATION_DIFF_BIN_M,
+                ELEVATION_DIFF_BINS,
+                true,
+            ),
+            by_highway_class,
+        })
+    }
+
+    async fn list_areas(&self, level: AdminLevel) -> Result<Vec<AreaSummary>, sqlx::Error> {
+        // 一覧はすべてのY字路を数える（橋・トンネルも含む）
+        let filters = FilterParams {
+            include_bridges: true,
+            include_tunnels: true,
+            ..Default::default()
+        };
+        let mut areas = self.rank_areas(level, None, &filters, i64::MAX).await?;
+        areas.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(areas)
+    }
+
+    async fn rank_areas(
+        &self,
+        level: AdminLevel,
+        area: Option<&SearchArea>,
+        filters: &FilterParams,
+        limit: i64,
+    ) -> Result<Vec<AreaSummary>, sqlx::Error> {
+        let matched = self.matching(area.unwrap_or(&SearchArea::Everywhere), filters)?;
+
+        let mut by_code: HashMap<&str, (&str, Vec<&Junction>)> = HashMap::new();
+        for junction in matched {
+            let Some(area) = area_at(&junction.areas, level) else {
+                continue;
+            };
+            let (name, junctions) = by_code
+                .entry(&area.code)
+                .or_insert((&area.name, Vec::new()));
+            // SQL の MAX(name) と同じ
+            *name = (*name).max(area.name.as_str());
+            junctions.push(junction);
+        }
+
+        let mut areas: Vec<AreaSummary> = by_code
+            .into_iter()
+            .map(|(code, (name, junctions))| AreaSummary {
+                code: code.to_string(),
+                name: name.to_string(),
+                level,
+                total_count: junctions.len() as i64,
+                by_type: type_counts(&junctions),
+            })
+            .collect();
+        areas.sort_by(|a, b| b.total_count.cmp(&a.total_count).then(a.code.cmp(&b.code)));
+        areas.truncate(limit.max(0).try_into().unwrap_or(usize::MAX));
+
+        Ok(areas)
+    }
+
+    async fn elevation_coverage(&self) -> Result<ElevationCoverage, sqlx::Error> {
+        let mut coverage = ElevationCoverage {
+            total_count: self.junctions.len() as i64,
+            ..Default::default()
+        };
+        for junction in &self.junctions {
+            if junction.elevation.is_some() {
+                coverage.with_elevation += 1;
+                if junction.elevation_nodata_samples.is_some_and(|n| n > 0) {
+                    coverage.with_nodata_samples += 1;
+                }
+                if let Some(ref source) = junction.elevation_source {
+                    *coverage.by_source.entry(source.clone()).or_insert(0) += 1;
+                }
+                if let Some(ref quality) = junction.elevation_quality {
+                    *coverage.by_quality.entry(quality.clone()).or_insert(0) += 1;
+                }
+            } else if let Some(ref reason) = junction.elevation_skip_reason {
+                *coverage.by_skip_reason.entry(reason.clone()).or_insert(0) += 1;
+            } else {
+                coverage.unprocessed += 1;
+            }
+        }
+
+        Ok(coverage)
+    }
+}
+
+fn min_angle(junction: &Junction) -> i16 {
+    junction.angles().into_iter().min().unwrap_or_default()
+}
+
+fn type_counts(junctions: &[&Junction]) -> AngleTypeCounts {
+    let mut counts = AngleTypeCounts::default();
+    for junction in junctions {
+        match junction.angle_type() {
+            AngleType::VerySharp => counts.verysharp += 1,
+            AngleType::Sharp => counts.sharp += 1,
+            AngleType::Normal => counts.normal += 1,
+        }
+    }
+    counts
+}
+
+fn area_at(areas: &JunctionAreas, level: AdminLevel) -> Option<&AreaRef> {
+    match level {
+        AdminLevel::Prefecture => areas.prefecture.as_ref(),
+        AdminLevel::City => areas.city.as_ref(),
+        AdminLevel::Ward => areas.ward.as_ref(),
+    }
+}
+
+// EPSG:3857 の座標（m）
+fn web_mercator_m(lon: f64, lat: f64) -> (f64, f64) {
+    let x = lon.to_radians() * WEB_MERCATOR_RADIUS_M;
+    let y = (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0)
+        .tan()
+        .ln()
+        * WEB_MERCATOR_RADIUS_M;
+    (x, y)
+}
+
+// 世界全体を 0..1 とした Web メルカトル座標（y は北が 0）
+fn web_mercator_unit(lon: f64, lat: f64) -> (f64, f64) {
+    let (x, y) = web_mercator_m(lon, lat);
+    let circumference = 2.0 * std::f64::consts::PI * WEB_MERCATOR_RADIUS_M;
+    (0.5 + x / circumference, 0.5 - y / circumference)
+}
+
+// 隣り合う辺が端点でだけ接し、それ以外の辺と交わらないリング
+fn is_simple_ring(ring: &LineString) -> bool {
+    let lines: Vec<_> = ring.lines().collect();
+    if lines.len() < 3 {
+        return false;
+    }
+    for (i, a) in lines.iter().enumerate() {
+        for (j, b) in lines.iter().enumerate().skip(i + 1) {
+            let adjacent = j == i + 1 || (i == 0 && j == lines.len() - 1);
+            match line_intersection(*a, *b) {
+                None => {}
+                Some(LineIntersection::SinglePoint {
+                    is_proper: false, ..
+                }) if adjacent => {}
+                Some(_) => return false,
+            }
+        }
+    }
+    true
+}
+
+fn distance_m(junction: &Junction, center: Point) -> f64 {
+    Point::new(junction.lon, junction.lat).haversine_distance(&center)
+}
+
+// 半径検索の候補を絞る外接矩形（球面と回転楕円体の差を見込んで少し広げる）
+fn radius_envelope(lat: f64, lon: f64, radius_m: f64) -> ([f64; 2], [f64; 2]) {
+    let dlat = (radius_m / EARTH_RADIUS_M).to_degrees() * 1.01;
+    let max_abs_lat = lat.abs() + dlat;
+    if max_abs_lat >= 90.0 {
+        return ([-180.0, lat - dlat], [180.0, lat + dlat]);
+    }
+    let dlon = dlat / max_abs_lat.to_radians().cos();
+    ([lon - dlon, lat - dlat], [lon + dlon, lat + dlat])
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "coordinates")]
+enum GeoJsonPolygon {
+    Polygon(Vec<Vec<[f64; 2]>>),
+    MultiPolygon(Vec<Vec<Vec<[f64; 2]>>>),
+}
+
+// 検証済みの GeoJSON Polygon/MultiPolygon を geo の型に変換する
+fn parse_polygons(geojson: &str) -> Result<MultiPolygon, sqlx::Error> {
+    let geometry: GeoJsonPolygon = serde_json::from_str(geojson)
+        .map_err(|err| sqlx::Error::Protocol(format!("Invalid search polygon: {}", err)))?;
+    let polygons = match geometry {
+        GeoJsonPolygon::Polygon(rings) => vec![rings],
+        GeoJsonPolygon::MultiPolygon(polygons) => polygons,
+    };
+
+    let ring = |ring: Vec<[f64; 2]>| {
+        LineString::from(
+            ring.into_iter()
+                .map(|[x, y]| Coord { x, y })
+                .collect::<Vec<_>>(),
+        )
+    };
+    Ok(MultiPolygon::new(
+        polygons
+            .into_iter()
+            .filter(|rings| !rings.is_empty())
+            .map(|mut rings| {
+                let exterior = ring(rings.remove(0));
+                Polygon::new(exterior, rings.into_iter().map(ring).collect())
+            })
+            .collect(),
+    ))
+}
+
+// SQL の絞り込み条件と同じ判定（値のない列は範囲条件に一致しない）
+fn matches_filters(junction: &Junction, filters: &FilterParams) -> bool {
+    let min_angle = min_angle(junction);
+
+    if let Some(ref angle_types) = filters.angle_type {
+        if !angle_types.is_empty() && !angle_types.contains(&junction.angle_type()) {
+            return false;
+        }
+    }
+    if filters.min_angle_lt.is_some_and(|lt| min_angle >= lt)
+        || filters.min_angle_gt.is_some_and(|gt| min_angle <= gt)
+    {
+        return false;
+    }
+
+    let max_grade = junction
+        .arm_grades
+        .iter()
+        .flatten()
+        .copied()
+        .reduce(f64::max);
+    let ranges = [
+        (
+            junction.min_angle_elevation_diff,
+            filters.min_angle_elevation_diff,
+            filters.max_angle_elevation_diff,
+        ),
+        (max_grade, filters.min_arm_grade, None),
+        (
+            junction.elevation,
+            filters.elevation_gte,
+            filters.elevation_lte,
+        ),
+        (
+            junction.min_elevation_diff,
+            filters.min_elevation_diff_gte,
+            filters.min_elevation_diff_lte,
+        ),
+        (
+            junction.max_elevation_diff,
+            filters.max_elevation_diff_gte,
+            filters.max_elevation_diff_lte,
+        ),
+        (
+            junction.local_slope,
+            filters.local_slope_gte,
+            filters.local_slope_lte,
+        ),
+    ];
+    for (value, gte, lte) in ranges {
+        if gte.is_none() && lte.is_none() {
+            continue;
+        }
+        let Some(value) = value else {
+            return false;
+        };
+        if gte.is_some_and(|min| value < min) || lte.is_some_and(|max| value > max) {
+            return false;
+        }
+    }
+
+    if (!filters.include_bridges && junction.has_bridge)
+        || (!filters.include_tunnels && junction.has_tunnel)
+    {
+        return false;
+    }
+
+    if let Some(ref code) = filters.area_code {
+        let areas = &junction.areas;
+        let in_area = [&areas.prefecture, &areas.city, &areas.ward]
+            .into_iter()
+            .flatten()
+            .any(|area| &area.code == code);
+        if !in_area {
+            return false;
+        }
+    }
+
+    if let Some(ref q) = filters.q {
+        let q = q.to_lowercase();
+        let found = junction
+            .road_names
+            .iter()
+            .flatten()
+            .any(|name| name.to_lowercase().contains(&q));
+        if !found {
+            return false;
+        }
+    }
+
+    true
+}
+
+// JunctionSort::push_key と同じソートキー
+fn sort_key(sort: &JunctionSort, junction: &Junction) -> f64 {
+    match *sort {
+        JunctionSort::Id => junction.id as f64,
+        JunctionSort::MinAngle => min_angle(junction).into(),
+        JunctionSort::ElevationDiff => junction.min_angle_elevation_diff.unwrap_or(-1.0),
+        JunctionSort::Distance { lat, lon } => distance_m(junction, Point::new(lon, lat)),
+    }
+}
+
+// ソートキー順（同じキーの行は id 順）に並べる
+fn sort_junctions(junctions: &mut [&Junction], sort: &JunctionSort) {
+    junctions.sort_by(|a, b| {
+        let ordering = sort_key(sort, a).total_cmp(&sort_key(sort, b));
+        let ordering = if sort.descending() {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then(a.id.cmp(&b.id))
+    });
+}
+
+fn after_cursor(sort: &JunctionSort, key: f64, id: i64, cursor: &PageCursor) -> bool {
+    match key.partial_cmp(&cursor.key) {
+        Some(Ordering::Equal) => id > cursor.id,
+        Some(Ordering::Greater) => !sort.descending(),
+        Some(Ordering::Less) => sort.descending(),
+        None => false,
+    }
+}
+
+/// Picks `limit` junctions with the same rules as the SQL sampling
+fn sample(matched: Vec<&Junction>, sampling: Sampling, limit: usize) -> Vec<&Junction> {
+    let sharpest_first =
+        |a: &&Junction, b: &&Junction| min_angle(a).cmp(&min_angle(b)).then(a.id.cmp(&b.id));
+
+    let mut sample = match sampling {
+        Sampling::Grid => {
+            // マッチした範囲を約 limit 個のセルに分け、各セルで最も鋭いY字路を選ぶ
+            // （limit に届かない分は残りから鋭い順に補う）
+            let min_lon = matched.iter().map(|j| j.lon).fold(f64::INFINITY, f64::min);
+            let min_lat = matched.iter().map(|j| j.lat).fold(f64::INFINITY, f64::min);
+            let max_lon = matched
+                .iter()
+                .map(|j| j.lon)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let max_lat = matched
+                .iter()
+                .map(|j| j.lat)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let span = (max_lon - min_lon).max(max_lat - min_lat);
+            // 全件が同じ位置ならセルは 1 つ
+            let cell = (span > 0.0).then(|| span / (limit as f64).sqrt().ceil());
+
+            let mut cells: HashMap<(i64, i64), &Junction> = HashMap::new();
+            for &junction in &matched {
+                let key = match cell {
+                    Some(cell) => (
+                        ((junction.lon - min_lon) / cell).floor() as i64,
+                        ((junction.lat - min_lat) / cell).floor() as i64,
+                    ),
+                    None => (0, 0),
+                };
+                cells
+                    .entry(key)
+                    .and_modify(|best| {
+                        if sharpest_first(&junction, best) == Ordering::Less {
+                            *best = junction;
+                        }
+                    })
+                    .or_insert(junction);
+            }
+            let mut sample: Vec<&Junction> = cells.into_values().collect();
+            sample.sort_by(sharpest_first);
+
+            let picked: HashSet<i64> = sample.iter().map(|junction| junction.id).collect();
+            let mut rest: Vec<&Junction> = matched
+                .into_iter()
+                .filter(|junction| !picked.contains(&junction.id))
+                .collect();
+            rest.sort_by(sharpest_first);
+            sample.extend(rest);
+            sample
+        }
+        Sampling::Sharpest => {
+            let mut sample = matched;
+            sample.sort_by(sharpest_first);
+            sample
+        }
+        Sampling::Random => {
+            // md5(id::text), id の順（同じ条件なら同じ結果）
+            let mut sample = matched;
+            sample.sort_by_cached_key(|junction| {
+                (Md5::digest(junction.id.to_string().as_bytes()), junction.id)
+            });
+            sample
+        }
+    };
+    sample.truncate(limit);
+    sample
+}
+
+// distance_m を設定したコピーを返す
+fn with_distance(junctions: Vec<&Junction>, center: Option<(f64, f64)>) -> Vec<Junction> {
+    junctions
+        .into_iter()
+        .map(|junction| Junction {
+            distance_m: center.map(|(lat, lon)| distance_m(junction, Point::new(lon, lat))),
+            ..junction.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::junction;
+
+    fn repository() -> MemoryRepository {
+        // 0.01 度間隔の格子上に 25 件（id は 1 から）
+        let junctions = (0..25)
+            .map(|i| {
+                let mut junction = junction(
+                    i + 1,
+                    139.0 + (i % 5) as f64 * 0.01,
+                    35.0 + (i / 5) as f64 * 0.01,
+                );
+                junction.angle_1 = (10 + i * 2) as i16;
+                junction.min_angle_elevation_diff = (i % 3 != 0).then_some(i as f64 / 2.0);
+                junction
+            })
+            .collect();
+        MemoryRepository::new(junctions)
+    }
+
+    fn ids(page: &JunctionPage) -> Vec<i64> {
+        page.junctions.iter().map(|j| j.id).collect()
+    }
+
+    fn all_filters() -> FilterParams {
+        FilterParams {
+            include_bridges: true,
+            include_tunnels: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bbox_and_filters() {
+        let repository = repository();
+        let area = SearchArea::Bbox(138.999, 34.999, 139.015, 35.015);
+
+        let page = repository
+            .find_junctions(&area, all_filters())
+            .await
+            .unwrap();
+        assert_eq!(ids(&page), vec![1, 2, 6, 7]);
+        assert_eq!(page.total_count, Some(4));
+        assert!(page.next_cursor.is_none());
+
+        // 鋭い角度（最小角 < 30）かつ高低差 1m 以上
+        let filters = FilterParams {
+            angle_type: Some(vec![AngleType::VerySharp]),
+            min_angle_elevation_diff: Some(1.0),
+            ..all_filters()
+        };
+        let page = repository
+            .find_junctions(&SearchArea::Everywhere, filters)
+            .await
+            .unwrap();
+        assert_eq!(ids(&page), vec![3, 5, 6, 8, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_radius_and_distance() {
+        let repository = repository();
+        // 1 件目から約 1.2km（隣の格子点は約 0.9km と 1.1km）
+        let area = SearchArea::Radius {
+            lat: 35.0,
+            lon: 139.0,
+            radius_m: 1200.0,
+        };
+        let filters = FilterParams {
+            sort: JunctionSort::Distance {
+                lat: 35.0,
+                lon: 139.0,
+            },
+            ..all_filters()
+        };
+
+        let page = repository.find_junctions(&area, filters).await.unwrap();
+        assert_eq!(ids(&page), vec![1, 2, 6]);
+        assert_eq!(page.junctions[0].distance_m, Some(0.0));
+        assert!(page.junctions[1].distance_m.unwrap() < page.junctions[2].distance_m.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_polygon_area() {
+        let repository = repository();
+        // 南西の 3 点だけを含む三角形
+        let area = SearchArea::Polygon(
+            r#"{"type":"Polygon","coordinates":[[[138.99,34.99],[139.025,34.99],[138.99,35.025],[138.99,34.99]]]}"#
+                .to_string(),
+        );
+
+        let page = repository
+            .find_junctions(&area, all_filters())
+            .await
+            .unwrap();
+        assert_eq!(ids(&page), vec![1, 2, 6]);
+
+        let invalid = SearchArea::Polygon("{}".to_string());
+        assert!(repository
+            .find_junctions(&invalid, all_filters())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_paging_with_cursor() {
+        let repository = repository();
+        let filters = FilterParams {
+            limit: Some(10),
+            sort: JunctionSort::ElevationDiff,
+            ..all_filters()
+        };
+
+        let mut seen = Vec::new();
+        let mut after = None;
+        loop {
+            let page = repository
+                .find_junctions(
+                    &SearchArea::Everywhere,
+                    FilterParams {
+                        after,
+                        ..filters.clone()
+                    },
+                )
+                .await
+                .unwrap();
+            // 件数は最初のページにだけ付く
+            assert_eq!(page.total_count, after.is_none().then_some(25));
+            seen.extend(page.junctions);
+            match page.next_cursor {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+
+        // 高低差の大きい順で、高低差のない行は最後に id 順
+        assert_eq!(seen.len(), 25);
+        assert_eq!(seen[0].id, 24);
+        assert_eq!(
+            seen[17..].iter().map(|j| j.id).collect::<Vec<_>>(),
+            vec![1, 4, 7, 10, 13, 16, 19, 22, 25][1..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sampling() {
+        let repository = repository();
+        let filters = |sampling| FilterParams {
+            limit: Some(4),
+            sampling: Some(sampling),
+            ..all_filters()
+        };
+
+        let page = repository
+            .find_junctions(&SearchArea::Everywhere, filters(Sampling::Sharpest))
+            .await
+            .unwrap();
+        assert_eq!(ids(&page), vec![1, 2, 3, 4]);
+        assert_eq!(page.sampled, Some(Sampling::Sharpest));
+        assert_eq!(page.total_count, Some(25));
+
+        // 2x2 のセルそれぞれで最も鋭いY字路
+        let page = repository
+            .find_junctions(&SearchArea::Everywhere, filters(Sampling::Grid))
+            .await
+            .unwrap();
+        assert_eq!(ids(&page), vec![1, 3, 11, 13]);
+
+        let random = repository
+            .find_junctions(&SearchArea::Everywhere, filters(Sampling::Random))
+            .await
+            .unwrap();
+        assert_eq!(random.junctions.len(), 4);
+        let again = repository
+            .find_junctions(&SearchArea::Everywhere, filters(Sampling::Random))
+            .await
+            .unwrap();
+        assert_eq!(ids(&random), ids(&again));
+    }
+
+    #[tokio::test]
+    async fn test_attribute_filters() {
+        let mut junctions: Vec<Junction> = (1..=3).map(|i| junction(i, 139.0, 35.0)).collect();
+        junctions[0].has_bridge = true;
+        junctions[1].road_names = [Some("Chuo-dori".to_string()), None, None];
+        junctions[2].areas.city = Some(AreaRef {
+            code: "13101".to_string(),
+            name: "千代田区".to_string(),
+        });
+        let repository = MemoryRepository::new(junctions);
+
+        let find = |filters: FilterParams| {
+            let repository = &repository;
+            async move {
+                ids(&repository
+                    .find_junctions(&SearchArea::Everywhere, filters)
+                    .await
+                    .unwrap())
+            }
+        };
+
+        assert_eq!(
+            find(FilterParams {
+                include_tunnels: true,
+                ..Default::default()
+            })
+            .await,
+            vec![2, 3]
+        );
+        assert_eq!(
+            find(FilterParams {
+                q: Some("chuo".to_string()),
+                ..all_filters()
+            })
+            .await,
+            vec![2]
+        );
+        assert_eq!(
+            find(FilterParams {
+                area_code: Some("13101".to_string()),
+                ..all_filters()
+            })
+            .await,
+            vec![3]
+        );
+        // 局所勾配のない行は範囲条件に一致しない
+        assert!(find(FilterParams {
+            local_slope_gte: Some(0.0),
+            ..all_filters()
+        })
+        .await
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_by_id_and_stats() {
+        let repository = repository();
+        assert_eq!(repository.find_by_id(7).await.unwrap().unwrap().id, 7);
+        assert!(repository.find_by_id(100).await.unwrap().is_none());
+
+        let stats = repository
+            .junction_stats(None, &all_filters())
+            .await
+            .unwrap();
+        assert_eq!(stats.total_count, 25);
+        // 最小角 10..58（2 度刻み）
+        assert_eq!(stats.by_type["verysharp"], 10);
+        assert_eq!(stats.by_type["sharp"], 8);
+        assert_eq!(stats.by_type["normal"], 7);
+        assert_eq!(stats.min_angle.len(), 12);
+        assert_eq!(stats.min_angle[2].count, 3);
+        // 高低差のない 9 件は除く
+        let elevation_total: i64 = stats.elevation_diff.iter().map(|bin| bin.count).sum();
+        assert_eq!(elevation_total, 16);
+        assert_eq!(stats.elevation_diff[10].count, 3);
+        assert_eq!(stats.by_highway_class["unknown"], 25);
+
+        let area = SearchArea::Bbox(138.999, 34.999, 139.015, 35.015);
+        let stats = repository
+            .junction_stats(Some(&area), &all_filters())
+            .await
+            .unwrap();
+        assert_eq!(stats.total_count, 4);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_and_stream() {
+        let repository = repository();
+        let nearest = repository
+            .find_nearest(35.0, 139.0, 3, all_filters())
+            .await
+            .unwrap();
+        assert_eq!(
+            nearest.iter().map(|j| j.id).collect::<Vec<_>>(),
+            vec![1, 2, 6]
+        );
+        assert_eq!(nearest[0].distance_m, Some(0.0));
+
+        let filters = FilterParams {
+            sort: JunctionSort::MinAngle,
+            ..all_filters()
+        };
+        let streamed: Vec<i64> = repository
+            .stream_junctions(SearchArea::Everywhere, filters)
+            .map(|junction| junction.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(streamed, (1..=25).collect::<Vec<_>>());
+
+        let invalid = SearchArea::Polygon("{}".to_string());
+        let mut errors = repository.stream_junctions(invalid, all_filters());
+        assert!(errors.next().await.unwrap().is_err());
+        assert!(errors.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tile() {
+        let repository = repository();
+        // z=10 で 139.0, 35.0 を含むタイル
+        let (x, y) = (907, 405);
+        let tile = repository
+            .find_tile((10, x, y), &SearchArea::Everywhere, &all_filters(), 2)
+            .await
+            .unwrap();
+        assert!(!tile.is_empty());
+
+        let (unit_x, unit_y) = web_mercator_unit(139.0, 35.0);
+        assert_eq!((unit_x * 1024.0) as u32, x);
+        assert_eq!((unit_y * 1024.0) as u32, y);
+
+        let empty = repository
+            .find_tile(
+                (10, x, y),
+                &SearchArea::Bbox(0.0, 0.0, 1.0, 1.0),
+                &all_filters(),
+                2,
+            )
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_clusters() {
+        let repository = repository();
+        // 1 つのセルに全件が入る
+        let clusters = repository
+            .find_clusters(&SearchArea::Everywhere, &all_filters(), 100_000.0, 10)
+            .await
+            .unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].count, 25);
+        assert_eq!(clusters[0].sharpest_id, 1);
+        assert_eq!(clusters[0].min_angle, 10);
+        assert!((clusters[0].lat - 35.02).abs() < 1e-9);
+        assert!((clusters[0].lon - 139.02).abs() < 1e-9);
+        assert_eq!(clusters[0].by_type.verysharp, 10);
+
+        // 格子点ごとに 1 件ずつ
+        let clusters = repository
+            .find_clusters(&SearchArea::Everywhere, &all_filters(), 10.0, 5)
+            .await
+            .unwrap();
+        assert_eq!(clusters.len(), 5);
+        assert!(clusters.iter().all(|cluster| cluster.count == 1));
+        assert_eq!(
+            clusters.iter().map(|c| c.sharpest_id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_is_valid_polygon() {
+        let repository = repository();
+        let square =
+            r#"{"type":"Polygon","coordinates":[[[139,35],[140,35],[140,36],[139,36],[139,35]]]}"#;
+        assert!(repository.is_valid_polygon(square).await.unwrap());
+
+        // 蝶ネクタイ型（自己交差）
+        let bowtie =
+            r#"{"type":"Polygon","coordinates":[[[139,35],[140,36],[140,35],[139,36],[139,35]]]}"#;
+        assert!(!repository.is_valid_polygon(bowtie).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_areas() {
+        let area = |code: &str, name: &str| {
+            Some(AreaRef {
+                code: code.to_string(),
+                name: name.to_string(),
+            })
+        };
+        let mut junctions: Vec<Junction> = (1..=4).map(|i| junction(i, 139.0, 35.0)).collect();
+        junctions[0].areas.city = area("13102", "中央区");
+        junctions[1].areas.city = area("13101", "千代田区");
+        junctions[2].areas.city = area("13102", "中央区");
+        junctions[2].has_bridge = true;
+        let repository = MemoryRepository::new(junctions);
+
+        let areas = repository.list_areas(AdminLevel::City).await.unwrap();
+        assert_eq!(
+            areas
+                .iter()
+                .map(|a| (a.code.as_str(), a.total_count))
+                .collect::<Vec<_>>(),
+            vec![("13101", 1), ("13102", 2)]
+        );
+        assert_eq!(areas[1].name, "中央区");
+        assert!(repository
+            .list_areas(AdminLevel::Ward)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let ranking = repository
+            .rank_areas(AdminLevel::City, None, &all_filters(), 1)
+            .await
+            .unwrap();
+        assert_eq!(ranking.len(), 1);
+        assert_eq!(ranking[0].code, "13102");
+
+        // 橋を除くと同数になり、コード順
+        let ranking = repository
+            .rank_areas(AdminLevel::City, None, &FilterParams::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            ranking.iter().map(|a| a.code.as_str()).collect::<Vec<_>>(),
+            vec!["13101", "13102"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_elevation_coverage() {
+        let mut junctions: Vec<Junction> = (1..=4).map(|i| junction(i, 139.0, 35.0)).collect();
+        for junction in &mut junctions[2..] {
+            junction.elevation = None;
+        }
+        for junction in &mut junctions[..2] {
+            junction.elevation_source = Some("DEM5A".to_string());
+            junction.elevation_quality = Some("ok".to_string());
+        }
+        junctions[1].elevation_nodata_samples = Some(2);
+        junctions[2].elevation_skip_reason = Some("nodata".to_string());
+        let repository = MemoryRepository::new(junctions);
+
+        let coverage = repository.elevation_coverage().await.unwrap();
+        assert_eq!(coverage.total_count, 4);
+        assert_eq!(coverage.with_elevation, 2);
+        assert_eq!(coverage.unprocessed, 1);
+        assert_eq!(coverage.with_nodata_samples, 1);
+        assert_eq!(coverage.by_source["DEM5A"], 2);
+        assert_eq!(coverage.by_quality["ok"], 2);
+        assert_eq!(coverage.by_skip_reason["nodata"], 1);
+    }
+}
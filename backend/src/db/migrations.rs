@@ -0,0 +1,308 @@
+use anyhow::{Context, Result};
+use sqlx::migrate::Migrator;
+use sqlx::PgPool;
+
+/// Migrations of `migrations/`, embedded in the binary at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Row of the `_sqlx_migrations` table
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the SQL in this build differs from the one that was run
+    Modified,
+    /// Failed part way; the database needs to be repaired by hand
+    Failed,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Schema version of a database compared with the migrations embedded in this build
+#[derive(Debug, Clone)]
+pub struct SchemaStatus {
+    /// Migrations of this build, oldest first
+    pub migrations: Vec<MigrationInfo>,
+    /// Versions applied to the database that this build does not know (the database is ahead)
+    pub unknown: Vec<i64>,
+}
+
+impl SchemaStatus {
+    /// Compares the migrations applied to a database with those of this build
+    pub fn new(applied: &[AppliedMigration]) -> Self {
+        let migrations = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| {
+                let state = match applied.iter().find(|a| a.version == migration.version) {
+                    None => MigrationState::Pending,
+                    Some(a) if !a.success => MigrationState::Failed,
+                    Some(a) if *a.checksum != *migration.checksum => MigrationState::Modified,
+                    Some(_) => MigrationState::Applied,
+                };
+                MigrationInfo {
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    state,
+                }
+            })
+            .collect();
+        let unknown = applied
+            .iter()
+            .map(|a| a.version)
+            .filter(|&version| !MIGRATOR.version_exists(version))
+            .collect();
+
+        Self {
+            migrations,
+            unknown,
+        }
+    }
+
+    /// Latest migration version of this build
+    pub fn expected_version(&self) -> Option<i64> {
+        self.migrations.last().map(|migration| migration.version)
+    }
+
+    /// Latest migration version applied to the database
+    pub fn current_version(&self) -> Option<i64> {
+        self.migrations
+            .iter()
+            .filter(|migration| migration.state != MigrationState::Pending)
+            .map(|migration| migration.version)
+            .chain(self.unknown.iter().copied())
+            .max()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.count(MigrationState::Pending)
+    }
+
+    fn count(&self, state: MigrationState) -> usize {
+        self.migrations
+            .iter()
+            .filter(|migration| migration.state == state)
+            .count()
+    }
+
+    fn versions(&self, state: MigrationState) -> Vec<String> {
+        self.migrations
+            .iter()
+            .filter(|migration| migration.state == state)
+            .map(|migration| migration.version.to_string())
+            .collect()
+    }
+
+    /// Fails unless the database has every migration of this build applied as is
+    ///
+    /// A database ahead of the code (migrations unknown to this build) is accepted, so that
+    /// the previous build keeps running while a newer one is rolled out.
+    pub fn check(&self) -> Result<()> {
+        let failed = self.versions(MigrationState::Failed);
+        if !failed.is_empty() {
+            anyhow::bail!(
+                "Migration {} failed part way; repair the database by hand, then run `yjunction db migrate`",
+                failed.join(", ")
+            );
+        }
+
+        let modified = self.versions(MigrationState::Modified);
+        if !modified.is_empty() {
+            anyhow::bail!(
+                "Migration {} changed after it was applied; restore the original file \
+                 (or run `yjunction db reset` on a development database)",
+                modified.join(", ")
+            );
+        }
+
+        if self.pending() > 0 {
+            let current = self
+                .current_version()
+                .map_or_else(|| "empty".to_string(), |v| format!("at version {}", v));
+            anyhow::bail!(
+                "Database schema is behind the code: the database is {} but this build expects \
+                 version {} ({} pending migrations). Run `yjunction db migrate`, or set \
+                 MIGRATE_ON_STARTUP=true to migrate on startup",
+                current,
+                self.expected_version().unwrap_or_default(),
+                self.pending()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the migrations applied to the database and compares them with this build
+pub async fn schema_status(pool: &PgPool) -> Result<SchemaStatus, sqlx::Error> {
+    let (has_table,): (bool,) =
+        sqlx::query_as("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+
+    let applied: Vec<AppliedMigration> = if has_table {
+        sqlx::query_as("SELECT version, success, checksum FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(SchemaStatus::new(&applied))
+}
+
+/// Applies the pending migrations, returning how many were applied
+pub async fn migrate(pool: &PgPool) -> Result<usize> {
+    let pending = schema_status(pool).await?.pending();
+    MIGRATOR
+        .run(pool)
+        .await
+        .context("Failed to run migrations")?;
+
+    Ok(pending)
+}
+
+/// Drops every table of the schema (including the migration history) and migrates again
+///
+/// Extensions such as PostGIS are kept. All junctions and areas are lost.
+pub async fn reset(pool: &PgPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    // spatial_ref_sys は PostGIS 拡張のテーブルなので残す
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT quote_ident(tablename) FROM pg_tables \
+         WHERE schemaname = current_schema() AND tablename <> 'spatial_ref_sys'",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for (table,) in tables {
+        sqlx::query(&format!("DROP TABLE IF EXISTS {} CASCADE", table))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    migrate(pool).await?;
+
+    Ok(())
+}
+
+/// Checks the schema version on startup, applying the pending migrations first if `migrate`
+pub async fn prepare_schema(pool: &PgPool, migrate_first: bool) -> Result<()> {
+    if migrate_first {
+        let applied = migrate(pool).await?;
+        tracing::info!("Applied {} migrations", applied);
+    }
+
+    let status = schema_status(pool).await?;
+    status.check()?;
+    if !status.unknown.is_empty() {
+        tracing::warn!(
+            "Database has migrations unknown to this build (versions {:?}); the code may be outdated",
+            status.unknown
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(count: usize) -> Vec<AppliedMigration> {
+        MIGRATOR
+            .iter()
+            .take(count)
+            .map(|migration| AppliedMigration {
+                version: migration.version,
+                success: true,
+                checksum: migration.checksum.to_vec(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_up_to_date() {
+        let all = MIGRATOR.iter().count();
+        let status = SchemaStatus::new(&applied(all));
+
+        assert_eq!(status.pending(), 0);
+        assert_eq!(status.current_version(), status.expected_version());
+        assert!(status.check().is_ok());
+    }
+
+    #[test]
+    fn test_behind_the_code() {
+        let status = SchemaStatus::new(&applied(2));
+        let all = MIGRATOR.iter().count();
+
+        assert_eq!(status.pending(), all - 2);
+        assert_eq!(status.current_version(), Some(2));
+        let message = status.check().unwrap_err().to_string();
+        assert!(message.contains("at version 2"), "{}", message);
+        assert!(message.contains("db migrate"), "{}", message);
+
+        let empty = SchemaStatus::new(&[]);
+        assert_eq!(empty.current_version(), None);
+        assert!(empty
+            .check()
+            .unwrap_err()
+            .to_string()
+            .contains("database is empty"));
+    }
+
+    #[test]
+    fn test_failed_and_modified_migrations() {
+        let mut rows = applied(3);
+        rows[1].checksum = vec![0; 48];
+        let status = SchemaStatus::new(&rows);
+        assert_eq!(status.migrations[1].state, MigrationState::Modified);
+        assert!(status.check().unwrap_err().to_string().contains("changed"));
+
+        rows[2].success = false;
+        let status = SchemaStatus::new(&rows);
+        assert_eq!(status.migrations[2].state, MigrationState::Failed);
+        // 失敗した移行が最優先
+        assert!(status.check().unwrap_err().to_string().contains("failed"));
+    }
+
+    #[test]
+    fn test_database_ahead_of_the_code() {
+        let all = MIGRATOR.iter().count();
+        let mut rows = applied(all);
+        rows.push(AppliedMigration {
+            version: 9999,
+            success: true,
+            checksum: Vec::new(),
+        });
+        let status = SchemaStatus::new(&rows);
+
+        assert_eq!(status.unknown, vec![9999]);
+        assert_eq!(status.current_version(), Some(9999));
+        assert!(status.check().is_ok());
+    }
+}
@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::PgPool;
+
+use super::repository::{
+    self, ElevationCoverage, FilterParams, JunctionPage, JunctionStats, SearchArea,
+};
+use super::JunctionRepository;
+use crate::domain::{AdminLevel, AreaSummary, Junction, JunctionCluster};
+
+/// Junctions stored in PostgreSQL/PostGIS
+#[derive(Debug, Clone)]
+pub struct PgRepository {
+    pool: PgPool,
+}
+
+impl PgRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl JunctionRepository for PgRepository {
+    async fn find_junctions(
+        &self,
+        area: &SearchArea,
+        filters: FilterParams,
+    ) -> Result<JunctionPage, sqlx::Error> {
+        repository::find_junctions(&self.pool, area, filters).await
+    }
+
+    fn stream_junctions(
+        &self,
+        area: SearchArea,
+        filters: FilterParams,
+    ) -> BoxStream<'static, Result<Junction, sqlx::Error>> {
+        repository::stream_junctions(self.pool.clone(), area, filters).boxed()
+    }
+
+    async fn find_nearest(
+        &self,
+        lat: f64,
+        lon: f64,
+        k: i64,
+        filters: FilterParams,
+    ) -> Result<Vec<Junction>, sqlx::Error> {
+        repository::find_nearest(&self.pool, lat, lon, k, filters).await
+    }
+
+    async fn find_tile(
+        &self,
+        tile: (u32, u32, u32),
+        area: &SearchArea,
+        filters: &FilterParams,
+        max_features: i64,
+    ) -> Result<Vec<u8>, sqlx::Error> {
+        repository::find_tile(&self.pool, tile, area, filters, max_features).await
+    }
+
+    async fn find_clusters(
+        &self,
+        area: &SearchArea,
+        filters: &FilterParams,
+        cell_size_m: f64,
+        max_clusters: i64,
+    ) -> Result<Vec<JunctionCluster>, sqlx::Error> {
+        repository::find_clusters(&self.pool, area, filters, cell_size_m, max_clusters).await
+    }
+
+    async fn is_valid_polygon(&self, geojson: &str) -> Result<bool, sqlx::Error> {
+        repository::is_valid_polygon(&self.pool, geojson).await
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<Junction>, sqlx::Error> {
+        repository::find_by_id(&self.pool, id).await
+    }
+
+    async fn junction_stats(
+        &self,
+        area: Option<&SearchArea>,
+        filters: &FilterParams,
+    ) -> Result<JunctionStats, sqlx::Error> {
+        repository::junction_stats(&self.pool, area, filters).await
+    }
+
+    async fn list_areas(&self, level: AdminLevel) -> Result<Vec<AreaSummary>, sqlx::Error> {
+        repository::list_areas(&self.pool, level).await
+    }
+
+    async fn rank_areas(
+        &self,
+        level: AdminLevel,
+        area: Option<&SearchArea>,
+        filters: &FilterParams,
+        limit: i64,
+    ) -> Result<Vec<AreaSummary>, sqlx::Error> {
+        repository::rank_areas(&self.pool, level, area, filters, limit).await
+    }
+
+    async fn elevation_coverage(&self) -> Result<ElevationCoverage, sqlx::Error> {
+        repository::elevation_coverage(&self.pool).await
+    }
+}
@@ -1,6 +1,13 @@
-use crate::domain::{AngleType, Junction};
+use crate::domain::{
+    AdminLevel, AngleType, AngleTypeCounts, AreaRef, AreaSummary, ArmProfile, ElevationSkipReason,
+    Junction, JunctionAreas, JunctionCluster,
+};
 use chrono::{DateTime, Utc};
-use sqlx::{FromRow, PgPool, QueryBuilder};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, Transaction};
 use std::collections::HashMap;
 
 /// Elevation data for bulk updates
@@ -18,6 +25,198 @@ pub struct ElevationUpdate {
     pub min_angle_index: i16,
     pub min_elevation_diff: f32,
     pub max_elevation_diff: f32,
+    /// DEM product that supplied the junction elevation (e.g. "DEM5A")
+    pub elevation_source: String,
+    /// How the junction elevation was derived (e.g. "bilinear")
+    pub elevation_quality: String,
+    /// Number of samples along the arms that had no elevation data
+    pub nodata_samples: i16,
+    /// Grade (%) of each road arm, in bearing order
+    pub grades: [Option<f32>; 3],
+    pub elevation_profile: Vec<ArmProfile>,
+}
+
+/// A junction the elevation import could not enrich, and why
+#[derive(Debug, Clone)]
+pub struct ElevationSkip {
+    pub id: i64,
+    pub reason: ElevationSkipReason,
+    /// Number of samples along the arms that had no elevation data, if sampling got that far
+    pub nodata_samples: Option<i16>,
+}
+
+/// Summary of elevation coverage for `GET /api/stats/elevation`
+#[derive(Debug, Clone, Default)]
+pub struct ElevationCoverage {
+    pub total_count: i64,
+    pub with_elevation: i64,
+    /// Junctions the elevation import has not processed yet
+    pub unprocessed: i64,
+    pub by_source: HashMap<String, i64>,
+    pub by_quality: HashMap<String, i64>,
+    pub by_skip_reason: HashMap<String, i64>,
+    /// Junctions with elevation where at least one sample along the arms had no data
+    pub with_nodata_samples: i64,
+}
+
+/// Width of the minimum angle histogram bins (degrees)
+pub(super) const MIN_ANGLE_BIN_DEG: f64 = 5.0;
+/// Number of minimum angle bins; junctions with a minimum angle of 60° or more are not imported
+pub(super) const MIN_ANGLE_BINS: i32 = 12;
+/// Width of the elevation difference histogram bins (meters)
+pub(super) const ELEVATION_DIFF_BIN_M: f64 = 1.0;
+/// Number of elevation difference bins; the last one is open-ended
+pub(super) const ELEVATION_DIFF_BINS: i32 = 11;
+
+/// One bin of a histogram, covering `[from, to)`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistogramBin {
+    pub from: f64,
+    /// None for the open-ended last bin
+    pub to: Option<f64>,
+    pub count: i64,
+}
+
+/// Statistics of the junctions matching a search, for `GET /api/stats`
+#[derive(Debug, Clone, Default)]
+pub struct JunctionStats {
+    pub total_count: i64,
+    pub by_type: HashMap<String, i64>,
+    /// Minimum angle in 5° bins
+    pub min_angle: Vec<HistogramBin>,
+    /// Elevation difference across the minimum angle in 1 m bins, junctions without elevation excluded
+    pub elevation_diff: Vec<HistogramBin>,
+    /// Most important highway class among the roads ("unknown" for rows imported without it)
+    pub by_highway_class: HashMap<String, i64>,
+}
+
+/// A junction to be enriched with elevation data, with the geometry of its road arms
+#[derive(Debug, Clone)]
+pub struct ElevationTarget {
+    pub id: i64,
+    pub lat: f64,
+    pub lon: f64,
+    pub angles: [i16; 3],
+    pub bearings: Vec<f32>,
+    /// (lat, lon) path of each road arm starting at the junction, in bearing order
+    /// None for junctions imported before arm geometry was recorded
+    pub arm_geometries: [Option<Vec<(f64, f64)>>; 3],
+}
+
+/// Sort order of bbox queries
+///
+/// Ties are broken by id, so the order is deterministic and can be paged with a cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum JunctionSort {
+    #[default]
+    Id,
+    /// Sharpest minimum angle first
+    MinAngle,
+    /// Largest `min_angle_elevation_diff` first, junctions without elevation last
+    ElevationDiff,
+    /// Nearest to the point first
+    Distance { lat: f64, lon: f64 },
+}
+
+impl JunctionSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JunctionSort::Id => "id",
+            JunctionSort::MinAngle => "min_angle",
+            JunctionSort::ElevationDiff => "elevation_diff",
+            JunctionSort::Distance { .. } => "distance",
+        }
+    }
+
+    pub(super) fn descending(&self) -> bool {
+        matches!(self, JunctionSort::ElevationDiff)
+    }
+
+    // ソートキーの式（ページ境界の比較のため常に float8 で返す）
+    fn push_key(&self, builder: &mut QueryBuilder<sqlx::Postgres>) {
+        match self {
+            JunctionSort::Id => {
+                builder.push("id::float8");
+            }
+            JunctionSort::MinAngle => {
+                builder.push("LEAST(angle_1, angle_2, angle_3)::float8");
+            }
+            JunctionSort::ElevationDiff => {
+                builder.push("COALESCE(min_angle_elevation_diff, -1)::float8");
+            }
+            JunctionSort::Distance { lat, lon } => {
+                builder.push("ST_Distance(location, ");
+                push_point(builder, *lat, *lon);
+                builder.push(")");
+            }
+        }
+    }
+}
+
+/// How to pick a representative subset when more junctions match than the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// One junction per cell of a grid over the matches, preferring the sharpest
+    Grid,
+    /// The junctions with the sharpest minimum angle
+    Sharpest,
+    /// A pseudo-random subset that is stable across requests
+    Random,
+}
+
+impl Sampling {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sampling::Grid => "grid",
+            Sampling::Sharpest => "sharpest",
+            Sampling::Random => "random",
+        }
+    }
+}
+
+/// Area a junction search is restricted to
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchArea {
+    /// (min_lon, min_lat, max_lon, max_lat)
+    Bbox(f64, f64, f64, f64),
+    /// Circle around a point
+    Radius { lat: f64, lon: f64, radius_m: f64 },
+    /// GeoJSON Polygon or MultiPolygon
+    Polygon(String),
+    /// No spatial restriction (the search is narrowed by an administrative area instead)
+    Everywhere,
+}
+
+impl SearchArea {
+    /// Center of the area, from which `distance_m` is measured
+    pub fn center(&self) -> Option<(f64, f64)> {
+        match self {
+            SearchArea::Bbox(..) | SearchArea::Polygon(_) | SearchArea::Everywhere => None,
+            SearchArea::Radius { lat, lon, .. } => Some((*lat, *lon)),
+        }
+    }
+}
+
+/// Position of the last row of a page: its sort key and id
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageCursor {
+    pub key: f64,
+    pub id: i64,
+}
+
+/// One page of a bbox query
+#[derive(Debug)]
+pub struct JunctionPage {
+    pub junctions: Vec<Junction>,
+    /// Number of junctions matching the filters across all pages
+    ///
+    /// Only counted for the first page; None when the page continues from a cursor, so
+    /// paging through a dense area does not count the whole match set again for every page.
+    pub total_count: Option<i64>,
+    /// Cursor for the next page, None on the last page
+    pub next_cursor: Option<PageCursor>,
+    /// Set when the junctions are a sample of the matches rather than a page
+    pub sampled: Option<Sampling>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -30,6 +229,67 @@ pub struct FilterParams {
     pub min_angle_elevation_diff: Option<f64>,
     // 最大角の高低差フィルタ（範囲検索用）
     pub max_angle_elevation_diff: Option<f64>,
+    // いずれかの道路の上り勾配（%）の下限
+    pub min_arm_grade: Option<f64>,
+    // 標高・高低差・局所勾配の範囲フィルタ（いずれも両端を含む）
+    pub elevation_gte: Option<f64>,
+    pub elevation_lte: Option<f64>,
+    pub min_elevation_diff_gte: Option<f64>,
+    pub min_elevation_diff_lte: Option<f64>,
+    pub max_elevation_diff_gte: Option<f64>,
+    pub max_elevation_diff_lte: Option<f64>,
+    pub local_slope_gte: Option<f64>,
+    pub local_slope_lte: Option<f64>,
+    // 橋・トンネルを含む道路のY字路を含めるか（未指定時は含める）
+    pub include_bridges: bool,
+    pub include_tunnels: bool,
+    pub sort: JunctionSort,
+    // distance_m の基準点 (lat, lon)
+    pub center: Option<(f64, f64)>,
+    // 前のページの最後の行（このカーソルより後ろの行を返す）
+    pub after: Option<PageCursor>,
+    // limit を超えた場合の間引き方（None ならページングする）
+    pub sampling: Option<Sampling>,
+    // 都道府県・市区町村・区のいずれかのコード
+    pub area_code: Option<String>,
+    // 道路名（name, name:en, 読み）の部分一致検索
+    pub q: Option<String>,
+}
+
+// y_junctions の行政区域カラム（JunctionRow に flatten して使う）
+#[derive(Debug, FromRow)]
+struct AreaColumns {
+    prefecture_code: Option<String>,
+    prefecture_name: Option<String>,
+    city_code: Option<String>,
+    city_name: Option<String>,
+    ward_code: Option<String>,
+    ward_name: Option<String>,
+}
+
+impl From<AreaColumns> for JunctionAreas {
+    fn from(columns: AreaColumns) -> Self {
+        let area = |code: Option<String>, name: Option<String>| {
+            code.map(|code| AreaRef {
+                name: name.unwrap_or_default(),
+                code,
+            })
+        };
+        JunctionAreas {
+            prefecture: area(columns.prefecture_code, columns.prefecture_name),
+            city: area(columns.city_code, columns.city_name),
+            ward: area(columns.ward_code, columns.ward_name),
+        }
+    }
+}
+
+/// Columns of y_junctions holding the code and name of the area at the given level
+fn area_columns(level: AdminLevel) -> (&'static str, &'static str) {
+    match level {
+        AdminLevel::Prefecture => ("prefecture_code", "prefecture_name"),
+        AdminLevel::City => ("city_code", "city_name"),
+        AdminLevel::Ward => ("ward_code", "ward_name"),
+    }
 }
 
 #[derive(Debug, FromRow)]
@@ -47,48 +307,82 @@ struct JunctionRow {
     min_elevation_diff: Option<f32>,
     max_elevation_diff: Option<f32>,
     min_angle_elevation_diff: Option<f32>,
+    local_slope: Option<f32>,
+    elevation_source: Option<String>,
+    elevation_quality: Option<String>,
+    elevation_nodata_samples: Option<i16>,
+    elevation_skip_reason: Option<String>,
+    grade_1: Option<f32>,
+    grade_2: Option<f32>,
+    grade_3: Option<f32>,
+    // 一覧系のクエリでは取得しない
+    #[sqlx(default)]
+    elevation_profile: Option<Json<Vec<ArmProfile>>>,
+    // 地点を指定したクエリでのみ取得する
+    #[sqlx(default)]
+    distance_m: Option<f64>,
+    #[sqlx(flatten)]
+    areas: AreaColumns,
+    way_1_name: Option<String>,
+    way_2_name: Option<String>,
+    way_3_name: Option<String>,
+    highway_class: Option<String>,
+    has_bridge: bool,
+    has_tunnel: bool,
 }
 
+// ページングやサンプリングの並び順キー付きで取得した行
 #[derive(Debug, FromRow)]
-struct JunctionRowWithCount {
+struct SortedJunctionRow {
+    #[sqlx(flatten)]
+    junction: JunctionRow,
+    sort_key: f64,
+}
+
+#[derive(Debug, FromRow)]
+struct ElevationTargetRow {
     id: i64,
-    osm_node_id: i64,
     lat: f64,
     lon: f64,
     angle_1: i16,
     angle_2: i16,
     angle_3: i16,
     bearings: Vec<f32>,
-    created_at: DateTime<Utc>,
-    elevation: Option<f32>,
-    min_elevation_diff: Option<f32>,
-    max_elevation_diff: Option<f32>,
-    min_angle_elevation_diff: Option<f32>,
-    total_count: i64,
+    arm_geometry_1: Option<String>,
+    arm_geometry_2: Option<String>,
+    arm_geometry_3: Option<String>,
 }
 
-impl From<JunctionRow> for Junction {
-    fn from(row: JunctionRow) -> Self {
-        Junction {
+/// Parses a GeoJSON LineString into (lat, lon) coordinates
+fn parse_linestring_geojson(geojson: &str) -> Option<Vec<(f64, f64)>> {
+    let value: serde_json::Value = serde_json::from_str(geojson).ok()?;
+    value["coordinates"]
+        .as_array()?
+        .iter()
+        .map(|coord| Some((coord.get(1)?.as_f64()?, coord.get(0)?.as_f64()?)))
+        .collect()
+}
+
+impl From<ElevationTargetRow> for ElevationTarget {
+    fn from(row: ElevationTargetRow) -> Self {
+        let parse = |geojson: Option<String>| geojson.as_deref().and_then(parse_linestring_geojson);
+        ElevationTarget {
             id: row.id,
-            osm_node_id: row.osm_node_id,
             lat: row.lat,
             lon: row.lon,
-            angle_1: row.angle_1,
-            angle_2: row.angle_2,
-            angle_3: row.angle_3,
+            angles: [row.angle_1, row.angle_2, row.angle_3],
             bearings: row.bearings,
-            created_at: row.created_at,
-            elevation: row.elevation.map(|e| e as f64),
-            min_elevation_diff: row.min_elevation_diff.map(|e| e as f64),
-            max_elevation_diff: row.max_elevation_diff.map(|e| e as f64),
-            min_angle_elevation_diff: row.min_angle_elevation_diff.map(|e| e as f64),
+            arm_geometries: [
+                parse(row.arm_geometry_1),
+                parse(row.arm_geometry_2),
+                parse(row.arm_geometry_3),
+            ],
         }
     }
 }
 
-impl From<JunctionRowWithCount> for Junction {
-    fn from(row: JunctionRowWithCount) -> Self {
+impl From<JunctionRow> for Junction {
+    fn from(row: JunctionRow) -> Self {
         Junction {
             id: row.id,
             osm_node_id: row.osm_node_id,
@@ -103,21 +397,72 @@ impl From<JunctionRowWithCount> for Junction {
             min_elevation_diff: row.min_elevation_diff.map(|e| e as f64),
             max_elevation_diff: row.max_elevation_diff.map(|e| e as f64),
             min_angle_elevation_diff: row.min_angle_elevation_diff.map(|e| e as f64),
+            local_slope: row.local_slope.map(|s| s as f64),
+            elevation_source: row.elevation_source,
+            elevation_quality: row.elevation_quality,
+            elevation_nodata_samples: row.elevation_nodata_samples,
+            elevation_skip_reason: row.elevation_skip_reason,
+            arm_grades: [
+                row.grade_1.map(|g| g as f64),
+                row.grade_2.map(|g| g as f64),
+                row.grade_3.map(|g| g as f64),
+            ],
+            elevation_profile: row.elevation_profile.map(|p| p.0),
+            distance_m: row.distance_m,
+            areas: row.areas.into(),
+            road_names: [row.way_1_name, row.way_2_name, row.way_3_name],
+            highway_class: row.highway_class,
+            has_bridge: row.has_bridge,
+            has_tunnel: row.has_tunnel,
         }
     }
 }
 
-// ヘルパー関数: bboxフィルタを追加
-fn add_bbox_filter(builder: &mut QueryBuilder<sqlx::Postgres>, bbox: (f64, f64, f64, f64)) {
-    builder.push("WHERE location && ST_MakeEnvelope(");
-    builder.push_bind(bbox.0);
-    builder.push(", ");
-    builder.push_bind(bbox.1);
-    builder.push(", ");
-    builder.push_bind(bbox.2);
+impl From<SortedJunctionRow> for Junction {
+    fn from(row: SortedJunctionRow) -> Self {
+        row.junction.into()
+    }
+}
+
+// ヘルパー関数: 地点の geography を追加
+fn push_point(builder: &mut QueryBuilder<sqlx::Postgres>, lat: f64, lon: f64) {
+    builder.push("ST_SetSRID(ST_MakePoint(");
+    builder.push_bind(lon);
     builder.push(", ");
-    builder.push_bind(bbox.3);
-    builder.push(", 4326)");
+    builder.push_bind(lat);
+    builder.push("), 4326)::geography");
+}
+
+// ヘルパー関数: 検索範囲のフィルタを追加（WHERE 句を開始する）
+fn add_area_filter(builder: &mut QueryBuilder<sqlx::Postgres>, area: &SearchArea) {
+    match area {
+        &SearchArea::Bbox(min_lon, min_lat, max_lon, max_lat) => {
+            builder.push("WHERE location && ST_MakeEnvelope(");
+            builder.push_bind(min_lon);
+            builder.push(", ");
+            builder.push_bind(min_lat);
+            builder.push(", ");
+            builder.push_bind(max_lon);
+            builder.push(", ");
+            builder.push_bind(max_lat);
+            builder.push(", 4326)");
+        }
+        &SearchArea::Radius { lat, lon, radius_m } => {
+            builder.push("WHERE ST_DWithin(location, ");
+            push_point(builder, lat, lon);
+            builder.push(", ");
+            builder.push_bind(radius_m);
+            builder.push(")");
+        }
+        SearchArea::Polygon(geojson) => {
+            builder.push("WHERE ST_Intersects(location, ST_GeomFromGeoJSON(");
+            builder.push_bind(geojson.clone());
+            builder.push(")::geography)");
+        }
+        SearchArea::Everywhere => {
+            builder.push("WHERE TRUE");
+        }
+    }
 }
 
 // ヘルパー関数: angle_typeフィルタを追加
@@ -174,68 +519,491 @@ fn add_elevation_filters(builder: &mut QueryBuilder<sqlx::Postgres>, filters: &F
         builder.push(" AND min_angle_elevation_diff <= ");
         builder.push_bind(max);
     }
-}
 
-// ヘルパー関数: 橋・トンネル除外フィルタを追加（常に除外）
-fn add_bridge_tunnel_filter(builder: &mut QueryBuilder<sqlx::Postgres>) {
-    builder.push(
-        " AND NOT (way_1_bridge OR way_1_tunnel \
-                OR way_2_bridge OR way_2_tunnel \
-                OR way_3_bridge OR way_3_tunnel)",
-    );
+    if let Some(grade) = filters.min_arm_grade {
+        builder.push(" AND max_grade >= ");
+        builder.push_bind(grade);
+    }
+
+    let ranges = [
+        ("elevation", filters.elevation_gte, filters.elevation_lte),
+        (
+            "min_elevation_diff",
+            filters.min_elevation_diff_gte,
+            filters.min_elevation_diff_lte,
+        ),
+        (
+            "max_elevation_diff",
+            filters.max_elevation_diff_gte,
+            filters.max_elevation_diff_lte,
+        ),
+        (
+            "local_slope",
+            filters.local_slope_gte,
+            filters.local_slope_lte,
+        ),
+    ];
+    for (column, gte, lte) in ranges {
+        if let Some(min) = gte {
+            builder.push(format!(" AND {} >= ", column));
+            builder.push_bind(min);
+        }
+        if let Some(max) = lte {
+            builder.push(format!(" AND {} <= ", column));
+            builder.push_bind(max);
+        }
+    }
 }
 
-pub async fn find_by_bbox(
-    pool: &PgPool,
-    bbox: (f64, f64, f64, f64), // (min_lon, min_lat, max_lon, max_lat)
-    filters: FilterParams,
-) -> Result<(Vec<Junction>, i64), sqlx::Error> {
-    let limit = filters.limit.unwrap_or(500).min(1000);
+// ヘルパー関数: 橋・トンネル除外フィルタを追加（NULL は SELECT の has_bridge/has_tunnel と同じく false 扱い）
+fn add_bridge_tunnel_filter(builder: &mut QueryBuilder<sqlx::Postgres>, filters: &FilterParams) {
+    if !filters.include_bridges {
+        builder.push(" AND NOT COALESCE(way_1_bridge OR way_2_bridge OR way_3_bridge, FALSE)");
+    }
+    if !filters.include_tunnels {
+        builder.push(" AND NOT COALESCE(way_1_tunnel OR way_2_tunnel OR way_3_tunnel, FALSE)");
+    }
+}
 
-    let mut query_builder = QueryBuilder::new(
-        "SELECT id, osm_node_id, \
-         ST_Y(location::geometry) as lat, ST_X(location::geometry) as lon, \
-         angle_1, angle_2, angle_3, bearings, created_at, \
-         elevation, min_elevation_diff, max_elevation_diff, min_angle_elevation_diff, \
-         COUNT(*) OVER() as total_count \
-         FROM y_junctions ",
-    );
+// ヘルパー関数: 検索範囲とすべての絞り込み条件を追加
+fn add_filters(
+    builder: &mut QueryBuilder<sqlx::Postgres>,
+    area: &SearchArea,
+    filters: &FilterParams,
+) {
+    // 検索範囲フィルタ
+    add_area_filter(builder, area);
 
-    // bbox フィルタ
-    add_bbox_filter(&mut query_builder, bbox);
+    add_attribute_filters(builder, filters);
+}
 
+// ヘルパー関数: 角度・標高・橋/トンネルの絞り込み条件を追加
+fn add_attribute_filters(builder: &mut QueryBuilder<sqlx::Postgres>, filters: &FilterParams) {
     // angle_type フィルタ
     if let Some(ref angle_types) = filters.angle_type {
-        add_angle_type_filter(&mut query_builder, angle_types);
+        add_angle_type_filter(builder, angle_types);
     }
 
     // min_angle フィルタ
-    add_min_angle_filters(
-        &mut query_builder,
-        filters.min_angle_lt,
-        filters.min_angle_gt,
-    );
+    add_min_angle_filters(builder, filters.min_angle_lt, filters.min_angle_gt);
 
-    // 最小角の高低差フィルタ
-    add_elevation_filters(&mut query_builder, &filters);
+    // 標高・高低差・勾配フィルタ
+    add_elevation_filters(builder, filters);
 
-    // 橋・トンネル除外フィルタ（高低差検索時のみ適用）
-    if filters.min_angle_elevation_diff.is_some() || filters.max_angle_elevation_diff.is_some() {
-        add_bridge_tunnel_filter(&mut query_builder);
+    // 橋・トンネル除外フィルタ
+    add_bridge_tunnel_filter(builder, filters);
+
+    // 行政区域フィルタ（どの階層のコードでもよい）
+    if let Some(ref code) = filters.area_code {
+        builder.push(" AND (prefecture_code = ");
+        builder.push_bind(code.clone());
+        builder.push(" OR city_code = ");
+        builder.push_bind(code.clone());
+        builder.push(" OR ward_code = ");
+        builder.push_bind(code.clone());
+        builder.push(")");
+    }
+
+    // 道路名の部分一致検索（トライグラムインデックスを使う）
+    if let Some(ref q) = filters.q {
+        builder.push(" AND road_search_text ILIKE ");
+        builder.push_bind(like_pattern(q));
     }
+}
+
+/// Pattern matching `text` anywhere, with LIKE wildcards in it escaped
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
 
-    // LIMIT
+// ヘルパー関数: カーソルより後ろの行に絞り込む
+fn add_cursor_filter(
+    builder: &mut QueryBuilder<sqlx::Postgres>,
+    sort: &JunctionSort,
+    cursor: &PageCursor,
+) {
+    let comparison = if sort.descending() { " < " } else { " > " };
+
+    builder.push(" AND (");
+    sort.push_key(builder);
+    builder.push(comparison);
+    builder.push_bind(cursor.key);
+    builder.push(" OR (");
+    sort.push_key(builder);
+    builder.push(" = ");
+    builder.push_bind(cursor.key);
+    builder.push(" AND id > ");
+    builder.push_bind(cursor.id);
+    builder.push("))");
+}
+
+// ヘルパー関数: 基準点からの距離を distance_m として追加
+fn push_distance_column(builder: &mut QueryBuilder<sqlx::Postgres>, center: Option<(f64, f64)>) {
+    match center {
+        Some((lat, lon)) => {
+            builder.push("ST_Distance(location, ");
+            push_point(builder, lat, lon);
+            builder.push(")");
+        }
+        None => {
+            builder.push("NULL::float8");
+        }
+    }
+    builder.push(" as distance_m, ");
+}
+
+const JUNCTION_LIST_COLUMNS: &str = "SELECT id, osm_node_id, \
+     ST_Y(location::geometry) as lat, ST_X(location::geometry) as lon, \
+     angle_1, angle_2, angle_3, bearings, created_at, \
+     elevation, min_elevation_diff, max_elevation_diff, min_angle_elevation_diff, local_slope, \
+     elevation_source, elevation_quality, elevation_nodata_samples, elevation_skip_reason, \
+     grade_1, grade_2, grade_3, \
+     prefecture_code, prefecture_name, city_code, city_name, ward_code, ward_name, \
+     way_1_name, way_2_name, way_3_name, highway_class, \
+     COALESCE(way_1_bridge OR way_2_bridge OR way_3_bridge, FALSE) as has_bridge, \
+     COALESCE(way_1_tunnel OR way_2_tunnel OR way_3_tunnel, FALSE) as has_tunnel, ";
+
+pub async fn find_junctions(
+    pool: &PgPool,
+    area: &SearchArea,
+    filters: FilterParams,
+) -> Result<JunctionPage, sqlx::Error> {
+    let limit = filters.limit.unwrap_or(500).min(1000);
+
+    let total_count = match filters.after {
+        Some(_) => None,
+        None => {
+            let mut count_builder = QueryBuilder::new("SELECT COUNT(*) FROM y_junctions ");
+            add_filters(&mut count_builder, area, &filters);
+            let (count,): (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+            Some(count)
+        }
+    };
+
+    if let Some(sampling) = filters
+        .sampling
+        .filter(|_| total_count.is_some_and(|count| count > limit))
+    {
+        let junctions = find_sample(pool, area, &filters, sampling, limit).await?;
+        return Ok(JunctionPage {
+            junctions,
+            total_count,
+            next_cursor: None,
+            sampled: Some(sampling),
+        });
+    }
+
+    let mut query_builder = QueryBuilder::new(JUNCTION_LIST_COLUMNS);
+    push_distance_column(&mut query_builder, filters.center.or(area.center()));
+    filters.sort.push_key(&mut query_builder);
+    query_builder.push(" as sort_key FROM y_junctions ");
+
+    add_filters(&mut query_builder, area, &filters);
+
+    if let Some(ref cursor) = filters.after {
+        add_cursor_filter(&mut query_builder, &filters.sort, cursor);
+    }
+
+    // ORDER BY（同じキーの行は id 順）
+    query_builder.push(" ORDER BY sort_key");
+    if filters.sort.descending() {
+        query_builder.push(" DESC");
+    }
+    query_builder.push(", id");
+
+    // 次のページの有無を判定するため1件多く取得する
     query_builder.push(" LIMIT ");
-    query_builder.push_bind(limit);
+    query_builder.push_bind(limit + 1);
 
-    let rows: Vec<JunctionRowWithCount> = query_builder.build_query_as().fetch_all(pool).await?;
+    let mut rows: Vec<SortedJunctionRow> = query_builder.build_query_as().fetch_all(pool).await?;
 
-    // total_count を最初の行から取得（全行同じ値）
-    let total_count = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|row| PageCursor {
+            key: row.sort_key,
+            id: row.junction.id,
+        })
+    } else {
+        None
+    };
 
     let junctions: Vec<Junction> = rows.into_iter().map(Junction::from).collect();
 
-    Ok((junctions, total_count))
+    Ok(JunctionPage {
+        junctions,
+        total_count,
+        next_cursor,
+        sampled: None,
+    })
+}
+
+/// Number of junctions buffered between the database and the client of an export
+const EXPORT_BUFFER_ROWS: usize = 1000;
+
+/// Streams every junction matching the area and filters, in the requested sort order
+///
+/// Unlike [`find_junctions`] the result is not capped; rows are read from the database as the
+/// receiver consumes them. The query stops when the receiver is dropped.
+pub fn stream_junctions(
+    pool: PgPool,
+    area: SearchArea,
+    filters: FilterParams,
+) -> mpsc::Receiver<Result<Junction, sqlx::Error>> {
+    let (mut sender, receiver) = mpsc::channel(EXPORT_BUFFER_ROWS);
+
+    tokio::spawn(async move {
+        let mut query_builder = QueryBuilder::new(JUNCTION_LIST_COLUMNS);
+        push_distance_column(&mut query_builder, filters.center.or(area.center()));
+        filters.sort.push_key(&mut query_builder);
+        query_builder.push(" as sort_key FROM y_junctions ");
+        add_filters(&mut query_builder, &area, &filters);
+
+        query_builder.push(" ORDER BY sort_key");
+        if filters.sort.descending() {
+            query_builder.push(" DESC");
+        }
+        query_builder.push(", id");
+
+        let mut rows = query_builder
+            .build_query_as::<SortedJunctionRow>()
+            .fetch(&pool);
+
+        while let Some(row) = rows.next().await {
+            let failed = row.is_err();
+            if sender.send(row.map(Junction::from)).await.is_err() || failed {
+                // クライアントが切断したか、エラーを送った
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+/// Picks `limit` junctions out of the matches with the given strategy, in the requested sort order
+async fn find_sample(
+    pool: &PgPool,
+    area: &SearchArea,
+    filters: &FilterParams,
+    sampling: Sampling,
+    limit: i64,
+) -> Result<Vec<Junction>, sqlx::Error> {
+    let mut query_builder = QueryBuilder::new("WITH matched AS (");
+    query_builder.push(JUNCTION_LIST_COLUMNS);
+    push_distance_column(&mut query_builder, filters.center.or(area.center()));
+    filters.sort.push_key(&mut query_builder);
+    query_builder.push(
+        " as sort_key, LEAST(angle_1, angle_2, angle_3) as sample_min_angle FROM y_junctions ",
+    );
+    add_filters(&mut query_builder, area, filters);
+    query_builder.push(") ");
+
+    match sampling {
+        Sampling::Grid => {
+            // マッチした範囲を約 limit 個のセルに分け、各セルで最も鋭いY字路を選ぶ。
+            // 空のセル（海・山や正方形からはみ出した部分）があると limit に届かないため、
+            // 残りは選ばれなかった中から鋭い順に補う（全件が同じ位置なら cell は NULL で、
+            // セルは 1 つになる）
+            query_builder.push(
+                ", extent AS ( \
+                   SELECT MIN(lon) AS min_lon, MIN(lat) AS min_lat, \
+                     NULLIF(GREATEST(MAX(lon) - MIN(lon), MAX(lat) - MIN(lat)), 0) \
+                       / CEIL(SQRT(",
+            );
+            query_builder.push_bind(limit as f64);
+            query_builder.push(
+                ")) AS cell \
+                   FROM matched), \
+                 cells AS ( \
+                   SELECT DISTINCT ON (cell_x, cell_y) id FROM ( \
+                     SELECT matched.id, matched.sample_min_angle, \
+                       FLOOR((lon - min_lon) / cell) AS cell_x, \
+                       FLOOR((lat - min_lat) / cell) AS cell_y \
+                     FROM matched CROSS JOIN extent) gridded \
+                   ORDER BY cell_x, cell_y, sample_min_angle, id) \
+                 SELECT * FROM ( \
+                   SELECT matched.* FROM matched LEFT JOIN cells ON cells.id = matched.id \
+                   ORDER BY cells.id IS NULL, matched.sample_min_angle, matched.id LIMIT ",
+            );
+        }
+        Sampling::Sharpest => {
+            query_builder.push(
+                "SELECT * FROM ( \
+                   SELECT * FROM matched ORDER BY sample_min_angle, id LIMIT ",
+            );
+        }
+        Sampling::Random => {
+            // id のハッシュ順にすることで、同じ条件なら同じ結果を返す
+            query_builder.push(
+                "SELECT * FROM ( \
+                   SELECT * FROM matched ORDER BY md5(id::text), id LIMIT ",
+            );
+        }
+    }
+    query_builder.push_bind(limit);
+
+    query_builder.push(") sample ORDER BY sort_key");
+    if filters.sort.descending() {
+        query_builder.push(" DESC");
+    }
+    query_builder.push(", id");
+
+    let rows: Vec<SortedJunctionRow> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    Ok(rows.into_iter().map(Junction::from).collect())
+}
+
+/// Renders the junctions in an XYZ tile as a Mapbox Vector Tile with a single `junctions` layer
+///
+/// `area` should be the bounds of the tile. When more than `max_features` junctions match,
+/// the sharpest ones are kept.
+pub async fn find_tile(
+    pool: &PgPool,
+    (z, x, y): (u32, u32, u32),
+    area: &SearchArea,
+    filters: &FilterParams,
+    max_features: i64,
+) -> Result<Vec<u8>, sqlx::Error> {
+    let mut query_builder = QueryBuilder::new("WITH tile AS (SELECT ST_TileEnvelope(");
+    query_builder.push_bind(z as i32);
+    query_builder.push(", ");
+    query_builder.push_bind(x as i32);
+    query_builder.push(", ");
+    query_builder.push_bind(y as i32);
+    query_builder.push(format!(
+        ") AS envelope), \
+         features AS ( \
+           SELECT ST_AsMVTGeom(ST_Transform(location::geometry, 3857), tile.envelope) AS geom, \
+             id, angle_1, angle_2, angle_3, \
+             LEAST(angle_1, angle_2, angle_3) AS min_angle, \
+             {ANGLE_TYPE_SQL} AS angle_type, \
+             elevation, min_angle_elevation_diff \
+           FROM y_junctions CROSS JOIN tile "
+    ));
+
+    add_filters(&mut query_builder, area, filters);
+
+    query_builder.push(" ORDER BY min_angle, id LIMIT ");
+    query_builder.push_bind(max_features);
+    query_builder.push(") SELECT ST_AsMVT(features, 'junctions', 4096, 'geom') FROM features");
+
+    let (tile,): (Option<Vec<u8>>,) = query_builder.build_query_as().fetch_one(pool).await?;
+
+    Ok(tile.unwrap_or_default())
+}
+
+#[derive(Debug, FromRow)]
+struct ClusterRow {
+    lat: f64,
+    lon: f64,
+    count: i64,
+    min_angle: i16,
+    sharpest_id: i64,
+    verysharp: i64,
+    sharp: i64,
+    normal: i64,
+}
+
+impl From<ClusterRow> for JunctionCluster {
+    fn from(row: ClusterRow) -> Self {
+        JunctionCluster {
+            lat: row.lat,
+            lon: row.lon,
+            count: row.count,
+            min_angle: row.min_angle,
+            sharpest_id: row.sharpest_id,
+            by_type: AngleTypeCounts {
+                verysharp: row.verysharp,
+                sharp: row.sharp,
+                normal: row.normal,
+            },
+        }
+    }
+}
+
+/// Aggregates the matching junctions into square grid cells of `cell_size_m` Web Mercator meters
+///
+/// Returns at most `max_clusters` cells, the most populated first.
+pub async fn find_clusters(
+    pool: &PgPool,
+    area: &SearchArea,
+    filters: &FilterParams,
+    cell_size_m: f64,
+    max_clusters: i64,
+) -> Result<Vec<JunctionCluster>, sqlx::Error> {
+    let mut query_builder = QueryBuilder::new(
+        "WITH cells AS ( \
+           SELECT id, location, LEAST(angle_1, angle_2, angle_3) AS min_angle, \
+             ST_SnapToGrid(ST_Transform(location::geometry, 3857), ",
+    );
+    query_builder.push_bind(cell_size_m);
+    query_builder.push(
+        ") AS cell \
+         FROM y_junctions ",
+    );
+
+    add_filters(&mut query_builder, area, filters);
+
+    query_builder.push(
+        ") SELECT \
+           ST_Y(ST_Centroid(ST_Collect(location::geometry))) AS lat, \
+           ST_X(ST_Centroid(ST_Collect(location::geometry))) AS lon, \
+           COUNT(*) AS count, \
+           MIN(min_angle) AS min_angle, \
+           (ARRAY_AGG(id ORDER BY min_angle, id))[1] AS sharpest_id, \
+           COUNT(*) FILTER (WHERE min_angle < 30) AS verysharp, \
+           COUNT(*) FILTER (WHERE min_angle >= 30 AND min_angle < 45) AS sharp, \
+           COUNT(*) FILTER (WHERE min_angle >= 45) AS normal \
+         FROM cells \
+         GROUP BY cell \
+         ORDER BY count DESC, sharpest_id \
+         LIMIT ",
+    );
+    query_builder.push_bind(max_clusters);
+
+    let rows: Vec<ClusterRow> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    Ok(rows.into_iter().map(JunctionCluster::from).collect())
+}
+
+/// Returns true if PostGIS considers the GeoJSON polygon valid (e.g. not self-intersecting)
+pub async fn is_valid_polygon(pool: &PgPool, geojson: &str) -> Result<bool, sqlx::Error> {
+    let (valid,): (bool,) = sqlx::query_as("SELECT ST_IsValid(ST_GeomFromGeoJSON($1))")
+        .bind(geojson)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(valid)
+}
+
+/// Finds the `k` junctions nearest to a point that match the filters, nearest first
+///
+/// Uses the KNN operator so the GIST index on `location` is walked in distance order.
+pub async fn find_nearest(
+    pool: &PgPool,
+    lat: f64,
+    lon: f64,
+    k: i64,
+    filters: FilterParams,
+) -> Result<Vec<Junction>, sqlx::Error> {
+    let mut query_builder = QueryBuilder::new(JUNCTION_LIST_COLUMNS);
+    push_distance_column(&mut query_builder, Some((lat, lon)));
+    query_builder.push("id::float8 as sort_key FROM y_junctions WHERE TRUE");
+
+    add_attribute_filters(&mut query_builder, &filters);
+
+    query_builder.push(" ORDER BY location <-> ");
+    push_point(&mut query_builder, lat, lon);
+    query_builder.push(", id LIMIT ");
+    query_builder.push_bind(k);
+
+    let rows: Vec<SortedJunctionRow> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    Ok(rows.into_iter().map(Junction::from).collect())
 }
 
 pub async fn find_by_id(pool: &PgPool, id: i64) -> Result<Option<Junction>, sqlx::Error> {
@@ -243,7 +1011,13 @@ pub async fn find_by_id(pool: &PgPool, id: i64) -> Result<Option<Junction>, sqlx
         "SELECT id, osm_node_id, \
          ST_Y(location::geometry) as lat, ST_X(location::geometry) as lon, \
          angle_1, angle_2, angle_3, bearings, created_at, \
-         elevation, min_elevation_diff, max_elevation_diff, min_angle_elevation_diff \
+         elevation, min_elevation_diff, max_elevation_diff, min_angle_elevation_diff, local_slope, \
+         elevation_source, elevation_quality, elevation_nodata_samples, elevation_skip_reason, \
+         grade_1, grade_2, grade_3, elevation_profile, \
+         prefecture_code, prefecture_name, city_code, city_name, ward_code, ward_name, \
+         way_1_name, way_2_name, way_3_name, highway_class, \
+         COALESCE(way_1_bridge OR way_2_bridge OR way_3_bridge, FALSE) as has_bridge, \
+         COALESCE(way_1_tunnel OR way_2_tunnel OR way_3_tunnel, FALSE) as has_tunnel \
          FROM y_junctions \
          WHERE id = $1",
     )
@@ -254,62 +1028,260 @@ pub async fn find_by_id(pool: &PgPool, id: i64) -> Result<Option<Junction>, sqlx
     Ok(row.map(Junction::from))
 }
 
-pub async fn count_by_type(pool: &PgPool) -> Result<HashMap<String, i64>, sqlx::Error> {
-    let rows: Vec<(String, i64)> = sqlx::query_as(
-        "SELECT \
-           CASE \
-             WHEN LEAST(angle_1, angle_2, angle_3) < 30 THEN 'verysharp' \
-             WHEN LEAST(angle_1, angle_2, angle_3) < 45 THEN 'sharp' \
-             ELSE 'normal' \
-           END as angle_type, \
-           COUNT(*) as count \
-         FROM y_junctions \
-         GROUP BY angle_type",
-    )
-    .fetch_all(pool)
-    .await?;
+// 最小角から angle_type を求める式（AngleType::from_angles と同じ閾値）
+const ANGLE_TYPE_SQL: &str = "CASE \
+     WHEN LEAST(angle_1, angle_2, angle_3) < 30 THEN 'verysharp' \
+     WHEN LEAST(angle_1, angle_2, angle_3) < 45 THEN 'sharp' \
+     ELSE 'normal' \
+   END";
 
-    let mut result = HashMap::new();
-    for (angle_type, count) in rows {
-        result.insert(angle_type, count);
+// ヘルパー関数: 統計用の絞り込み条件を追加（範囲指定がなければ全件が対象）
+fn add_stats_filters(
+    builder: &mut QueryBuilder<sqlx::Postgres>,
+    area: Option<&SearchArea>,
+    filters: &FilterParams,
+) {
+    match area {
+        Some(area) => add_filters(builder, area, filters),
+        None => {
+            builder.push("WHERE TRUE ");
+            add_attribute_filters(builder, filters);
+        }
     }
+}
 
-    Ok(result)
+/// Fills the bins without matches with zero counts
+///
+/// `counts` maps a bin index to its count. When `open_ended` is set, the last bin has no
+/// upper bound.
+pub(super) fn dense_histogram(
+    counts: &[(i32, i64)],
+    bin_width: f64,
+    num_bins: i32,
+    open_ended: bool,
+) -> Vec<HistogramBin> {
+    (0..num_bins)
+        .map(|i| HistogramBin {
+            from: i as f64 * bin_width,
+            to: if open_ended && i == num_bins - 1 {
+                None
+            } else {
+                Some((i + 1) as f64 * bin_width)
+            },
+            count: counts
+                .iter()
+                .filter(|&&(bin, _)| bin == i)
+                .map(|&(_, count)| count)
+                .sum(),
+        })
+        .collect()
 }
 
-pub async fn count_total(pool: &PgPool) -> Result<i64, sqlx::Error> {
-    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM y_junctions")
-        .fetch_one(pool)
+/// Counts the junctions matching the area and filters, by type, minimum angle, elevation
+/// difference and highway class
+pub async fn junction_stats(
+    pool: &PgPool,
+    area: Option<&SearchArea>,
+    filters: &FilterParams,
+) -> Result<JunctionStats, sqlx::Error> {
+    let mut by_type_builder = QueryBuilder::new(format!(
+        "SELECT {ANGLE_TYPE_SQL} as angle_type, COUNT(*) as count FROM y_junctions "
+    ));
+    add_stats_filters(&mut by_type_builder, area, filters);
+    by_type_builder.push(" GROUP BY angle_type");
+    let by_type: Vec<(String, i64)> = by_type_builder.build_query_as().fetch_all(pool).await?;
+
+    // 範囲外の値は最後のビンにまとめる
+    let mut min_angle_builder =
+        QueryBuilder::new("SELECT LEAST(FLOOR(LEAST(angle_1, angle_2, angle_3) / ");
+    min_angle_builder.push_bind(MIN_ANGLE_BIN_DEG);
+    min_angle_builder.push("), ");
+    min_angle_builder.push_bind(MIN_ANGLE_BINS - 1);
+    min_angle_builder.push(")::int as bin, COUNT(*) FROM y_junctions ");
+    add_stats_filters(&mut min_angle_builder, area, filters);
+    min_angle_builder.push(" GROUP BY bin");
+    let min_angle: Vec<(i32, i64)> = min_angle_builder.build_query_as().fetch_all(pool).await?;
+
+    let mut elevation_diff_builder =
+        QueryBuilder::new("SELECT LEAST(FLOOR(min_angle_elevation_diff / ");
+    elevation_diff_builder.push_bind(ELEVATION_DIFF_BIN_M);
+    elevation_diff_builder.push("), ");
+    elevation_diff_builder.push_bind(ELEVATION_DIFF_BINS - 1);
+    elevation_diff_builder.push(")::int as bin, COUNT(*) FROM y_junctions ");
+    add_stats_filters(&mut elevation_diff_builder, area, filters);
+    elevation_diff_builder.push(" AND min_angle_elevation_diff IS NOT NULL GROUP BY bin");
+    let elevation_diff: Vec<(i32, i64)> = elevation_diff_builder
+        .build_query_as()
+        .fetch_all(pool)
         .await?;
 
-    Ok(row.0)
+    let mut highway_class_builder = QueryBuilder::new(
+        "SELECT COALESCE(highway_class, 'unknown') as class, COUNT(*) FROM y_junctions ",
+    );
+    add_stats_filters(&mut highway_class_builder, area, filters);
+    highway_class_builder.push(" GROUP BY class");
+    let by_highway_class: Vec<(String, i64)> = highway_class_builder
+        .build_query_as()
+        .fetch_all(pool)
+        .await?;
+
+    Ok(JunctionStats {
+        total_count: by_type.iter().map(|(_, count)| count).sum(),
+        by_type: by_type.into_iter().collect(),
+        min_angle: dense_histogram(&min_angle, MIN_ANGLE_BIN_DEG, MIN_ANGLE_BINS, false),
+        elevation_diff: dense_histogram(
+            &elevation_diff,
+            ELEVATION_DIFF_BIN_M,
+            ELEVATION_DIFF_BINS,
+            true,
+        ),
+        by_highway_class: by_highway_class.into_iter().collect(),
+    })
 }
 
-pub async fn find_all(pool: &PgPool) -> Result<Vec<Junction>, sqlx::Error> {
-    let rows: Vec<JunctionRow> = sqlx::query_as(
-        "SELECT id, osm_node_id, \
+// 行政区域ごとの件数の行 (code, name, total, verysharp, sharp, normal)
+type AreaCountRow = (String, String, i64, i64, i64, i64);
+
+fn to_area_summary(level: AdminLevel, row: AreaCountRow) -> AreaSummary {
+    let (code, name, total_count, verysharp, sharp, normal) = row;
+    AreaSummary {
+        code,
+        name,
+        level,
+        total_count,
+        by_type: AngleTypeCounts {
+            verysharp,
+            sharp,
+            normal,
+        },
+    }
+}
+
+// ヘルパー関数: angle_type ごとの件数の列を追加（count_column が NULL の行は数えない）
+fn push_type_counts(builder: &mut QueryBuilder<sqlx::Postgres>, count_column: &str) {
+    for angle_type in ["verysharp", "sharp", "normal"] {
+        builder.push(format!(
+            ", COUNT({count_column}) FILTER (WHERE {ANGLE_TYPE_SQL} = '{angle_type}')"
+        ));
+    }
+}
+
+/// Lists the administrative areas of a level with the number of junctions in each, by code
+pub async fn list_areas(pool: &PgPool, level: AdminLevel) -> Result<Vec<AreaSummary>, sqlx::Error> {
+    let (code_column, _) = area_columns(level);
+
+    let mut query_builder =
+        QueryBuilder::new("SELECT a.code, a.name, COUNT(j.id)::bigint as total_count");
+    push_type_counts(&mut query_builder, "j.id");
+    query_builder.push(format!(
+        " FROM admin_areas a \
+         LEFT JOIN y_junctions j ON j.{code_column} = a.code \
+         WHERE a.admin_level = "
+    ));
+    query_builder.push_bind(level.osm_level());
+    query_builder.push(" GROUP BY a.code, a.name ORDER BY a.code");
+
+    let rows: Vec<AreaCountRow> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| to_area_summary(level, row))
+        .collect())
+}
+
+/// Ranks the areas of a level by the number of junctions matching the search, most first
+pub async fn rank_areas(
+    pool: &PgPool,
+    level: AdminLevel,
+    area: Option<&SearchArea>,
+    filters: &FilterParams,
+    limit: i64,
+) -> Result<Vec<AreaSummary>, sqlx::Error> {
+    let (code_column, name_column) = area_columns(level);
+
+    let mut query_builder = QueryBuilder::new(format!(
+        "SELECT {code_column}, MAX({name_column}), COUNT(*) as total_count"
+    ));
+    push_type_counts(&mut query_builder, "id");
+    query_builder.push(" FROM y_junctions ");
+    add_stats_filters(&mut query_builder, area, filters);
+    query_builder.push(format!(
+        " AND {code_column} IS NOT NULL \
+         GROUP BY {code_column} \
+         ORDER BY total_count DESC, {code_column} \
+         LIMIT "
+    ));
+    query_builder.push_bind(limit);
+
+    let rows: Vec<AreaCountRow> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| to_area_summary(level, row))
+        .collect())
+}
+
+/// Fetches the next batch of junctions with `id > after_id`, ordered by id
+///
+/// Used for keyset pagination over the whole table. When `only_missing` is set, rows that
+/// already have an elevation are skipped.
+pub async fn find_batch_after_id(
+    pool: &PgPool,
+    after_id: i64,
+    limit: i64,
+    only_missing: bool,
+) -> Result<Vec<ElevationTarget>, sqlx::Error> {
+    let mut query_builder = QueryBuilder::new(
+        "SELECT id, \
          ST_Y(location::geometry) as lat, ST_X(location::geometry) as lon, \
-         angle_1, angle_2, angle_3, bearings, created_at, \
-         elevation, min_elevation_diff, max_elevation_diff, min_angle_elevation_diff \
-         FROM y_junctions",
-    )
-    .fetch_all(pool)
-    .await?;
+         angle_1, angle_2, angle_3, bearings, \
+         ST_AsGeoJSON(arm_geometry_1) as arm_geometry_1, \
+         ST_AsGeoJSON(arm_geometry_2) as arm_geometry_2, \
+         ST_AsGeoJSON(arm_geometry_3) as arm_geometry_3 \
+         FROM y_junctions \
+         WHERE id > ",
+    );
+    query_builder.push_bind(after_id);
 
-    Ok(rows.into_iter().map(Junction::from).collect())
+    if only_missing {
+        query_builder.push(" AND elevation IS NULL");
+    }
+
+    query_builder.push(" ORDER BY id LIMIT ");
+    query_builder.push_bind(limit);
+
+    let rows: Vec<ElevationTargetRow> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    Ok(rows.into_iter().map(ElevationTarget::from).collect())
 }
 
-pub async fn bulk_update_elevations(
+/// Counts the junctions [`find_batch_after_id`] would return across all batches
+pub async fn count_after_id(
     pool: &PgPool,
+    after_id: i64,
+    only_missing: bool,
+) -> Result<i64, sqlx::Error> {
+    let mut query_builder = QueryBuilder::new("SELECT COUNT(*) FROM y_junctions WHERE id > ");
+    query_builder.push_bind(after_id);
+
+    if only_missing {
+        query_builder.push(" AND elevation IS NULL");
+    }
+
+    let row: (i64,) = query_builder.build_query_as().fetch_one(pool).await?;
+
+    Ok(row.0)
+}
+
+/// Writes the elevations of a batch in the caller's transaction
+pub async fn bulk_update_elevations(
+    tx: &mut Transaction<'_, Postgres>,
     updates: &[ElevationUpdate],
 ) -> Result<usize, sqlx::Error> {
     if updates.is_empty() {
         return Ok(0);
     }
 
-    // Use transaction for atomicity
-    let mut tx = pool.begin().await?;
-
     // Batch updates in chunks of 1000 to avoid exceeding PostgreSQL parameter limits
     const BATCH_SIZE: usize = 1000;
     let mut total_updated = 0;
@@ -326,7 +1298,15 @@ pub async fn bulk_update_elevations(
              elevation_diff_3 = updates.elevation_diff_3, \
              min_angle_index = updates.min_angle_index, \
              min_elevation_diff = updates.min_elevation_diff, \
-             max_elevation_diff = updates.max_elevation_diff \
+             max_elevation_diff = updates.max_elevation_diff, \
+             elevation_source = updates.elevation_source, \
+             elevation_quality = updates.elevation_quality, \
+             elevation_nodata_samples = updates.elevation_nodata_samples, \
+             elevation_skip_reason = NULL, \
+             grade_1 = updates.grade_1, \
+             grade_2 = updates.grade_2, \
+             grade_3 = updates.grade_3, \
+             elevation_profile = updates.elevation_profile \
              FROM (VALUES ",
         );
 
@@ -356,20 +1336,188 @@ pub async fn bulk_update_elevations(
             query_builder.push_bind(update.min_elevation_diff);
             query_builder.push(", ");
             query_builder.push_bind(update.max_elevation_diff);
+            query_builder.push(", ");
+            query_builder.push_bind(&update.elevation_source);
+            query_builder.push(", ");
+            query_builder.push_bind(&update.elevation_quality);
+            query_builder.push(", ");
+            query_builder.push_bind(update.nodata_samples);
+            query_builder.push(", ");
+            query_builder.push_bind(update.grades[0]);
+            query_builder.push(", ");
+            query_builder.push_bind(update.grades[1]);
+            query_builder.push(", ");
+            query_builder.push_bind(update.grades[2]);
+            query_builder.push(", ");
+            query_builder.push_bind(Json(&update.elevation_profile));
             query_builder.push(")");
         }
 
         query_builder.push(
             ") AS updates(id, elevation, neighbor_elevation_1, neighbor_elevation_2, neighbor_elevation_3, \
              elevation_diff_1, elevation_diff_2, elevation_diff_3, min_angle_index, \
-             min_elevation_diff, max_elevation_diff) \
+             min_elevation_diff, max_elevation_diff, elevation_source, \
+             elevation_quality, elevation_nodata_samples, \
+             grade_1, grade_2, grade_3, elevation_profile) \
              WHERE y_junctions.id = updates.id"
         );
 
-        let result = query_builder.build().execute(&mut *tx).await?;
+        let result = query_builder.build().execute(&mut **tx).await?;
         total_updated += result.rows_affected() as usize;
     }
 
-    tx.commit().await?;
     Ok(total_updated)
 }
+
+/// Records why junctions were left without elevation, in the caller's transaction
+///
+/// Only the skip columns are written, so an elevation from an earlier run is kept.
+pub async fn bulk_record_elevation_skips(
+    tx: &mut Transaction<'_, Postgres>,
+    skips: &[ElevationSkip],
+) -> Result<usize, sqlx::Error> {
+    if skips.is_empty() {
+        return Ok(0);
+    }
+
+    const BATCH_SIZE: usize = 1000;
+    let mut total_updated = 0;
+
+    for chunk in skips.chunks(BATCH_SIZE) {
+        let mut query_builder = QueryBuilder::new(
+            "UPDATE y_junctions SET \
+             elevation_skip_reason = skips.reason, \
+             elevation_nodata_samples = skips.nodata_samples \
+             FROM (VALUES ",
+        );
+
+        for (i, skip) in chunk.iter().enumerate() {
+            if i > 0 {
+                query_builder.push(", ");
+            }
+            query_builder.push("(");
+            query_builder.push_bind(skip.id);
+            query_builder.push(", ");
+            query_builder.push_bind(skip.reason.as_str());
+            query_builder.push(", ");
+            query_builder.push_bind(skip.nodata_samples);
+            query_builder.push(")");
+        }
+
+        query_builder.push(
+            ") AS skips(id, reason, nodata_samples) \
+             WHERE y_junctions.id = skips.id",
+        );
+
+        let result = query_builder.build().execute(&mut **tx).await?;
+        total_updated += result.rows_affected() as usize;
+    }
+
+    Ok(total_updated)
+}
+
+/// Counts junctions by elevation coverage, source, quality and skip reason
+pub async fn elevation_coverage(pool: &PgPool) -> Result<ElevationCoverage, sqlx::Error> {
+    let (total_count, with_elevation, unprocessed, with_nodata_samples): (i64, i64, i64, i64) =
+        sqlx::query_as(
+            "SELECT \
+               COUNT(*), \
+               COUNT(*) FILTER (WHERE elevation IS NOT NULL), \
+               COUNT(*) FILTER (WHERE elevation IS NULL AND elevation_skip_reason IS NULL), \
+               COUNT(*) FILTER (WHERE elevation IS NOT NULL AND elevation_nodata_samples > 0) \
+             FROM y_junctions",
+        )
+        .fetch_one(pool)
+        .await?;
+
+    let count_by = |column: &'static str, condition: &'static str| {
+        format!(
+            "SELECT {column}, COUNT(*) FROM y_junctions \
+             WHERE {column} IS NOT NULL AND {condition} \
+             GROUP BY {column}"
+        )
+    };
+
+    let by_source: Vec<(String, i64)> =
+        sqlx::query_as(&count_by("elevation_source", "elevation IS NOT NULL"))
+            .fetch_all(pool)
+            .await?;
+    let by_quality: Vec<(String, i64)> =
+        sqlx::query_as(&count_by("elevation_quality", "elevation IS NOT NULL"))
+            .fetch_all(pool)
+            .await?;
+    let by_skip_reason: Vec<(String, i64)> =
+        sqlx::query_as(&count_by("elevation_skip_reason", "elevation IS NULL"))
+            .fetch_all(pool)
+            .await?;
+
+    Ok(ElevationCoverage {
+        total_count,
+        with_elevation,
+        unprocessed,
+        by_source: by_source.into_iter().collect(),
+        by_quality: by_quality.into_iter().collect(),
+        by_skip_reason: by_skip_reason.into_iter().collect(),
+        with_nodata_samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("旧中山道"), "%旧中山道%");
+        assert_eq!(like_pattern("100%_a\\b"), "%100\\%\\_a\\\\b%");
+    }
+
+    #[test]
+    fn test_dense_histogram_fills_empty_bins() {
+        let bins = dense_histogram(&[(0, 2), (2, 5)], 5.0, 3, false);
+        assert_eq!(
+            bins,
+            vec![
+                HistogramBin {
+                    from: 0.0,
+                    to: Some(5.0),
+                    count: 2
+                },
+                HistogramBin {
+                    from: 5.0,
+                    to: Some(10.0),
+                    count: 0
+                },
+                HistogramBin {
+                    from: 10.0,
+                    to: Some(15.0),
+                    count: 5
+                },
+            ]
+        );
+
+        let bins = dense_histogram(&[(1, 3)], 1.0, 2, true);
+        assert_eq!(
+            bins[1],
+            HistogramBin {
+                from: 1.0,
+                to: None,
+                count: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_linestring_geojson() {
+        let path = parse_linestring_geojson(
+            r#"{"type":"LineString","coordinates":[[139.0,35.0],[139.001,35.002]]}"#,
+        )
+        .unwrap();
+        assert_eq!(path, vec![(35.0, 139.0), (35.002, 139.001)]);
+
+        assert!(parse_linestring_geojson("not json").is_none());
+        assert!(
+            parse_linestring_geojson(r#"{"type":"LineString","coordinates":[[139.0]]}"#).is_none()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use super::cluster::AngleTypeCounts;
+
+/// Administrative levels junctions are tagged with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminLevel {
+    /// 都道府県 (admin_level=4)
+    Prefecture,
+    /// 市区町村 (admin_level=7)
+    City,
+    /// 政令指定都市の区 (admin_level=8)
+    Ward,
+}
+
+impl AdminLevel {
+    pub const ALL: [AdminLevel; 3] = [AdminLevel::Prefecture, AdminLevel::City, AdminLevel::Ward];
+
+    /// Value of the OSM `admin_level` tag
+    pub fn osm_level(&self) -> i16 {
+        match self {
+            AdminLevel::Prefecture => 4,
+            AdminLevel::City => 7,
+            AdminLevel::Ward => 8,
+        }
+    }
+
+    pub fn from_osm_level(level: i16) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.osm_level() == level)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminLevel::Prefecture => "prefecture",
+            AdminLevel::City => "city",
+            AdminLevel::Ward => "ward",
+        }
+    }
+
+    /// Parses a level name ("prefecture", "city", "ward") or an OSM admin_level ("4", "7", "8")
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|l| l.as_str() == s)
+            .or_else(|| s.parse().ok().and_then(Self::from_osm_level))
+    }
+}
+
+/// Code and name of an administrative area
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreaRef {
+    pub code: String,
+    pub name: String,
+}
+
+/// Administrative areas a junction lies in
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JunctionAreas {
+    pub prefecture: Option<AreaRef>,
+    pub city: Option<AreaRef>,
+    pub ward: Option<AreaRef>,
+}
+
+/// Junction counts of one administrative area
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AreaSummary {
+    pub code: String,
+    pub name: String,
+    pub level: AdminLevel,
+    pub total_count: i64,
+    pub by_type: AngleTypeCounts,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_level_parse() {
+        assert_eq!(
+            AdminLevel::parse("prefecture"),
+            Some(AdminLevel::Prefecture)
+        );
+        assert_eq!(AdminLevel::parse("city"), Some(AdminLevel::City));
+        assert_eq!(AdminLevel::parse("8"), Some(AdminLevel::Ward));
+        assert_eq!(AdminLevel::parse("6"), None);
+        assert_eq!(AdminLevel::parse("town"), None);
+    }
+
+    #[test]
+    fn test_junction_areas_serialization() {
+        let areas = JunctionAreas {
+            prefecture: Some(AreaRef {
+                code: "JP-13".to_string(),
+                name: "東京都".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&areas).unwrap();
+        assert_eq!(json["prefecture"]["code"], "JP-13");
+        assert_eq!(json["prefecture"]["name"], "東京都");
+        assert!(json["city"].is_null());
+        assert!(json["ward"].is_null());
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Serialize;
+
+/// Number of junctions of each angle type in a cluster
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct AngleTypeCounts {
+    pub verysharp: i64,
+    pub sharp: i64,
+    pub normal: i64,
+}
+
+/// Junctions aggregated into one grid cell for low zoom levels
+#[derive(Debug, Clone, PartialEq)]
+pub struct JunctionCluster {
+    /// Centroid of the junctions in the cell
+    pub lat: f64,
+    pub lon: f64,
+    pub count: i64,
+    /// Smallest minimum angle among the junctions in the cell
+    pub min_angle: i16,
+    /// Id of the junction with that angle
+    pub sharpest_id: i64,
+    pub by_type: AngleTypeCounts,
+}
+
+impl JunctionCluster {
+    pub fn to_feature(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [self.lon, self.lat]
+            },
+            "properties": {
+                "count": self.count,
+                "min_angle": self.min_angle,
+                "sharpest_id": self.sharpest_id,
+                "by_type": self.by_type,
+            }
+        })
+    }
+
+    /// FeatureCollection of clusters with the grid cell size they were computed with
+    pub fn to_feature_collection(
+        clusters: &[JunctionCluster],
+        cell_size_m: f64,
+    ) -> serde_json::Value {
+        let features: Vec<serde_json::Value> = clusters.iter().map(|c| c.to_feature()).collect();
+        let total_count: i64 = clusters.iter().map(|c| c.count).sum();
+
+        serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features,
+            "total_count": total_count,
+            "cell_size_m": cell_size_m
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_feature_collection() {
+        let clusters = vec![
+            JunctionCluster {
+                lat: 35.0,
+                lon: 139.0,
+                count: 3,
+                min_angle: 18,
+                sharpest_id: 42,
+                by_type: AngleTypeCounts {
+                    verysharp: 1,
+                    sharp: 0,
+                    normal: 2,
+                },
+            },
+            JunctionCluster {
+                lat: 35.5,
+                lon: 139.5,
+                count: 1,
+                min_angle: 50,
+                sharpest_id: 7,
+                by_type: AngleTypeCounts {
+                    verysharp: 0,
+                    sharp: 0,
+                    normal: 1,
+                },
+            },
+        ];
+
+        let collection = JunctionCluster::to_feature_collection(&clusters, 2445.98);
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(collection["total_count"], 4);
+        assert_eq!(collection["cell_size_m"], 2445.98);
+        let feature = &collection["features"][0];
+        assert_eq!(feature["geometry"]["coordinates"][0], 139.0);
+        assert_eq!(feature["properties"]["count"], 3);
+        assert_eq!(feature["properties"]["min_angle"], 18);
+        assert_eq!(feature["properties"]["sharpest_id"], 42);
+        assert_eq!(feature["properties"]["by_type"]["verysharp"], 1);
+        assert_eq!(feature["properties"]["by_type"]["normal"], 2);
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+/// Grade (%) below which an arm is considered flat
+pub const FLAT_GRADE_THRESHOLD_PERCENT: f64 = 2.0;
+
+/// Direction of slope along a road arm, leaving the junction
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArmSlope {
+    Uphill,
+    Downhill,
+    Flat,
+}
+
+impl ArmSlope {
+    pub fn from_grade(grade_percent: f64) -> Self {
+        if grade_percent >= FLAT_GRADE_THRESHOLD_PERCENT {
+            Self::Uphill
+        } else if grade_percent <= -FLAT_GRADE_THRESHOLD_PERCENT {
+            Self::Downhill
+        } else {
+            Self::Flat
+        }
+    }
+}
+
+/// How an elevation value was derived from the DEM grid
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElevationQuality {
+    /// Bilinear interpolation between the four surrounding cells
+    Bilinear,
+    /// Value of the cell containing the point (a surrounding cell had no data)
+    Nearest,
+    /// The cell had no data and was filled from valid cells nearby
+    Filled,
+}
+
+impl ElevationQuality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bilinear => "bilinear",
+            Self::Nearest => "nearest",
+            Self::Filled => "filled",
+        }
+    }
+}
+
+/// Why a junction has no elevation after the elevation import
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationSkipReason {
+    /// No DEM tile covers the junction
+    NoTile,
+    /// A tile covers the junction but has no data there
+    NodataAtJunction,
+    /// The junction has data but a neighbor point on one of the arms does not
+    NodataAtNeighbor,
+}
+
+impl ElevationSkipReason {
+    pub const ALL: [Self; 3] = [Self::NoTile, Self::NodataAtJunction, Self::NodataAtNeighbor];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoTile => "no_tile",
+            Self::NodataAtJunction => "nodata_at_junction",
+            Self::NodataAtNeighbor => "nodata_at_neighbor",
+        }
+    }
+}
+
+/// Elevation sampled at a distance along a road arm
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ProfileSample {
+    /// Distance from the junction node along the arm (meters)
+    pub distance_m: f64,
+    /// Elevation in meters, None if no data was available
+    pub elevation: Option<f64>,
+    /// How the elevation was derived (absent in profiles stored before it was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<ElevationQuality>,
+}
+
+/// Elevation profile of one road arm
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArmProfile {
+    /// Bearing of the arm in degrees (same as the junction's bearings)
+    pub bearing: f64,
+    /// True if sampled along the way geometry, false if along a straight line on the bearing
+    pub along_way: bool,
+    /// Grade in percent, positive when climbing away from the junction
+    pub grade_percent: Option<f64>,
+    pub slope: Option<ArmSlope>,
+    pub samples: Vec<ProfileSample>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm_slope_from_grade() {
+        assert_eq!(ArmSlope::from_grade(8.0), ArmSlope::Uphill);
+        assert_eq!(ArmSlope::from_grade(2.0), ArmSlope::Uphill);
+        assert_eq!(ArmSlope::from_grade(1.9), ArmSlope::Flat);
+        assert_eq!(ArmSlope::from_grade(0.0), ArmSlope::Flat);
+        assert_eq!(ArmSlope::from_grade(-1.9), ArmSlope::Flat);
+        assert_eq!(ArmSlope::from_grade(-2.0), ArmSlope::Downhill);
+    }
+}
@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::area::JunctionAreas;
+use super::elevation_profile::{ArmProfile, ArmSlope};
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum AngleType {
@@ -19,6 +22,24 @@ impl AngleType {
             Self::Normal
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::VerySharp => "verysharp",
+            Self::Sharp => "sharp",
+            Self::Normal => "normal",
+        }
+    }
+
+    /// Parses a name returned by `as_str` ("verysharp", "sharp", "normal")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "verysharp" => Some(Self::VerySharp),
+            "sharp" => Some(Self::Sharp),
+            "normal" => Some(Self::Normal),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +65,33 @@ pub struct Junction {
     pub max_elevation_diff: Option<f64>,
     /// Elevation difference at the minimum angle
     pub min_angle_elevation_diff: Option<f64>,
+    /// Steepest slope (%) between the junction and its neighbors 10m away
+    pub local_slope: Option<f64>,
+    /// DEM product that supplied the junction elevation (e.g. "DEM5A")
+    pub elevation_source: Option<String>,
+    /// How the junction elevation was derived ("bilinear", "nearest" or "filled")
+    pub elevation_quality: Option<String>,
+    /// Number of samples along the arms that had no elevation data
+    pub elevation_nodata_samples: Option<i16>,
+    /// Why the elevation import left this junction without elevation
+    pub elevation_skip_reason: Option<String>,
+    /// Grade (%) of each road arm, positive when climbing away from the junction
+    /// Order corresponds to bearings
+    pub arm_grades: [Option<f64>; 3],
+    /// Elevation profile along each road arm (only loaded for the detail view)
+    pub elevation_profile: Option<Vec<ArmProfile>>,
+    /// Distance in meters from the search center (only set for searches around a point)
+    pub distance_m: Option<f64>,
+    /// Prefecture, city and ward the junction lies in
+    pub areas: JunctionAreas,
+    /// Name of each road, in the same order as bearings
+    pub road_names: [Option<String>; 3],
+    /// Most important highway class among the roads (e.g. "primary"), None for rows imported before it was recorded
+    pub highway_class: Option<String>,
+    /// Whether any of the roads is a bridge
+    pub has_bridge: bool,
+    /// Whether any of the roads is a tunnel
+    pub has_tunnel: bool,
 }
 
 impl Junction {
@@ -57,6 +105,42 @@ impl Junction {
         [self.angle_1, self.angle_2, self.angle_3]
     }
 
+    pub fn arm_slopes(&self) -> [Option<ArmSlope>; 3] {
+        self.arm_grades.map(|grade| grade.map(ArmSlope::from_grade))
+    }
+
+    /// Human-readable label such as "旧中山道と県道1号の分岐（松山市）"
+    ///
+    /// Roads sharing a name are mentioned once. The area is the ward (with its city),
+    /// the city or the prefecture, whichever is the most specific.
+    pub fn label(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self.road_names.iter().flatten() {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+
+        let roads = if names.is_empty() {
+            "名称のない道路の分岐".to_string()
+        } else {
+            format!("{}の分岐", names.join("と"))
+        };
+
+        let area = match (&self.areas.city, &self.areas.ward, &self.areas.prefecture) {
+            (Some(city), Some(ward), _) => Some(format!("{}{}", city.name, ward.name)),
+            (_, Some(area), _) | (Some(area), None, _) | (None, None, Some(area)) => {
+                Some(area.name.clone())
+            }
+            (None, None, None) => None,
+        };
+
+        match area {
+            Some(area) => format!("{}（{}）", roads, area),
+            None => roads,
+        }
+    }
+
     pub fn streetview_url(&self) -> String {
         let base_url = format!(
             "https://www.google.com/maps/@?api=1&map_action=pano&viewpoint={},{}",
@@ -118,17 +202,41 @@ impl Junction {
                 "min_elevation_diff": self.min_elevation_diff,
                 "max_elevation_diff": self.max_elevation_diff,
                 "min_angle_elevation_diff": self.min_angle_elevation_diff,
+                "local_slope": self.local_slope,
+                "elevation_source": self.elevation_source,
+                "elevation_quality": self.elevation_quality,
+                "elevation_nodata_samples": self.elevation_nodata_samples,
+                "elevation_skip_reason": self.elevation_skip_reason,
+                "arm_grades": self.arm_grades,
+                "arm_slopes": self.arm_slopes(),
+                "distance_m": self.distance_m,
+                "areas": self.areas,
+                "road_names": self.road_names,
+                "label": self.label(),
             }
         })
     }
 
-    pub fn to_feature_collection(junctions: Vec<Junction>, total_count: i64) -> serde_json::Value {
+    /// Feature for the detail view, including the elevation profile along each arm
+    pub fn to_detail_feature(&self) -> serde_json::Value {
+        let mut feature = self.to_feature();
+        feature["properties"]["elevation_profile"] = serde_json::json!(self.elevation_profile);
+        feature
+    }
+
+    /// FeatureCollection of one page, with the cursor of the next page if there is one
+    pub fn to_feature_collection(
+        junctions: Vec<Junction>,
+        total_count: Option<i64>,
+        next_cursor: Option<String>,
+    ) -> serde_json::Value {
         let features: Vec<serde_json::Value> = junctions.iter().map(|j| j.to_feature()).collect();
 
         serde_json::json!({
             "type": "FeatureCollection",
             "features": features,
-            "total_count": total_count
+            "total_count": total_count,
+            "next_cursor": next_cursor
         })
     }
 }
@@ -158,6 +266,14 @@ mod tests {
         assert_eq!(angle_type, AngleType::Normal);
     }
 
+    #[test]
+    fn test_angle_type_parse() {
+        for angle_type in [AngleType::VerySharp, AngleType::Sharp, AngleType::Normal] {
+            assert_eq!(AngleType::parse(angle_type.as_str()), Some(angle_type));
+        }
+        assert_eq!(AngleType::parse("Sharp"), None);
+    }
+
     #[test]
     fn test_junction_angle_type() {
         let junction = Junction {
@@ -174,6 +290,19 @@ mod tests {
             min_elevation_diff: None,
             max_elevation_diff: None,
             min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
         };
 
         assert_eq!(junction.angle_type(), AngleType::Sharp);
@@ -195,6 +324,19 @@ mod tests {
             min_elevation_diff: None,
             max_elevation_diff: None,
             min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
         };
 
         assert_eq!(junction.angles(), [30, 150, 180]);
@@ -216,6 +358,19 @@ mod tests {
             min_elevation_diff: None,
             max_elevation_diff: None,
             min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
         };
 
         let url = junction.streetview_url();
@@ -241,6 +396,19 @@ mod tests {
             min_elevation_diff: None,
             max_elevation_diff: None,
             min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
         };
 
         let feature = junction.to_feature();
@@ -258,6 +426,119 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_label() {
+        use crate::domain::AreaRef;
+
+        let area = |code: &str, name: &str| {
+            Some(AreaRef {
+                code: code.to_string(),
+                name: name.to_string(),
+            })
+        };
+        let mut junction = Junction {
+            id: 1,
+            osm_node_id: 123456,
+            lat: 35.6812,
+            lon: 139.7671,
+            angle_1: 30,
+            angle_2: 150,
+            angle_3: 180,
+            bearings: vec![10.0, 40.0, 190.0],
+            created_at: Utc::now(),
+            elevation: None,
+            min_elevation_diff: None,
+            max_elevation_diff: None,
+            min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
+        };
+
+        assert_eq!(junction.label(), "名称のない道路の分岐");
+
+        junction.road_names = [
+            Some("旧中山道".to_string()),
+            None,
+            Some("旧中山道".to_string()),
+        ];
+        junction.areas.prefecture = area("JP-20", "長野県");
+        assert_eq!(junction.label(), "旧中山道の分岐（長野県）");
+
+        junction.road_names[1] = Some("県道1号".to_string());
+        junction.areas.city = area("r1", "横浜市");
+        junction.areas.ward = area("r2", "中区");
+        assert_eq!(junction.label(), "旧中山道と県道1号の分岐（横浜市中区）");
+
+        let feature = junction.to_feature();
+        assert_eq!(feature["properties"]["label"], junction.label());
+        assert_eq!(feature["properties"]["road_names"][1], "県道1号");
+    }
+
+    #[test]
+    fn test_to_detail_feature_includes_profile() {
+        let junction = Junction {
+            id: 1,
+            osm_node_id: 123456,
+            lat: 35.6812,
+            lon: 139.7671,
+            angle_1: 30,
+            angle_2: 150,
+            angle_3: 180,
+            bearings: vec![10.0, 40.0, 190.0],
+            created_at: Utc::now(),
+            elevation: Some(100.0),
+            min_elevation_diff: None,
+            max_elevation_diff: None,
+            min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: Some("DEM5A".to_string()),
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [Some(8.5), Some(-3.0), None],
+            elevation_profile: Some(vec![ArmProfile {
+                bearing: 10.0,
+                along_way: true,
+                grade_percent: Some(8.5),
+                slope: Some(ArmSlope::Uphill),
+                samples: vec![crate::domain::ProfileSample {
+                    distance_m: 10.0,
+                    elevation: Some(100.85),
+                    quality: Some(crate::domain::ElevationQuality::Bilinear),
+                }],
+            }]),
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
+        };
+
+        let feature = junction.to_feature();
+        assert_eq!(
+            feature["properties"]["arm_slopes"],
+            serde_json::json!(["uphill", "downhill", null])
+        );
+        assert!(feature["properties"].get("elevation_profile").is_none());
+
+        let detail = junction.to_detail_feature();
+        let profile = &detail["properties"]["elevation_profile"];
+        assert_eq!(profile[0]["slope"], "uphill");
+        assert_eq!(profile[0]["samples"][0]["distance_m"], 10.0);
+    }
+
     #[test]
     fn test_to_feature_collection() {
         let junction1 = Junction {
@@ -274,6 +555,19 @@ mod tests {
             min_elevation_diff: None,
             max_elevation_diff: None,
             min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
         };
 
         let junction2 = Junction {
@@ -290,12 +584,30 @@ mod tests {
             min_elevation_diff: None,
             max_elevation_diff: None,
             min_angle_elevation_diff: None,
+            local_slope: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
+            arm_grades: [None; 3],
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: None,
+            has_bridge: false,
+            has_tunnel: false,
         };
 
-        let collection = Junction::to_feature_collection(vec![junction1, junction2], 2);
+        let collection = Junction::to_feature_collection(
+            vec![junction1, junction2],
+            Some(3),
+            Some("cursor".to_string()),
+        );
 
         assert_eq!(collection["type"], "FeatureCollection");
-        assert_eq!(collection["total_count"], 2);
+        assert_eq!(collection["total_count"], 3);
+        assert_eq!(collection["next_cursor"], "cursor");
         assert_eq!(collection["features"].as_array().unwrap().len(), 2);
         assert_eq!(collection["features"][0]["properties"]["id"], 1);
         assert_eq!(collection["features"][1]["properties"]["id"], 2);
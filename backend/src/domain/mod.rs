@@ -1,3 +1,11 @@
+pub mod area;
+pub mod cluster;
+pub mod elevation_profile;
 pub mod junction;
 
+pub use area::{AdminLevel, AreaRef, AreaSummary, JunctionAreas};
+pub use cluster::{AngleTypeCounts, JunctionCluster};
+pub use elevation_profile::{
+    ArmProfile, ArmSlope, ElevationQuality, ElevationSkipReason, ProfileSample,
+};
 pub use junction::{AngleType, Junction};
@@ -0,0 +1,355 @@
+use anyhow::Result;
+use geo::{Contains, Coord, LineString, MultiPolygon, Point, Polygon};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+
+use crate::domain::AdminLevel;
+
+/// Administrative area assembled from a `boundary=administrative` relation
+#[derive(Debug, Clone)]
+pub struct AdminArea {
+    pub relation_id: i64,
+    pub level: AdminLevel,
+    pub code: String,
+    pub name: String,
+    pub name_en: Option<String>,
+    /// Boundary polygons in (lon, lat) coordinates
+    pub geometry: MultiPolygon<f64>,
+}
+
+/// Boundary relation collected in the 1st pass, before its ways are resolved
+struct BoundaryRelation {
+    relation_id: i64,
+    level: AdminLevel,
+    code: String,
+    name: String,
+    name_en: Option<String>,
+    outer_ways: Vec<i64>,
+    inner_ways: Vec<i64>,
+}
+
+/// Code of an area: the ISO 3166-2 code or `ref` tag, falling back to the relation id
+pub fn area_code(iso3166_2: Option<&str>, ref_tag: Option<&str>, relation_id: i64) -> String {
+    iso3166_2
+        .or(ref_tag)
+        .filter(|code| !code.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("r{}", relation_id))
+}
+
+/// Extracts prefecture, city and ward boundaries (admin_level 4, 7 and 8) from a PBF file
+pub fn parse_admin_areas(input_path: &str) -> Result<Vec<AdminArea>> {
+    // 1st pass: boundary=administrative のリレーションとメンバーのwayを集める
+    tracing::info!("Starting 1st pass: collecting administrative boundary relations");
+    let mut relations = Vec::new();
+
+    let reader = osmpbf::ElementReader::new(File::open(input_path)?);
+    reader.for_each(|element| {
+        if let osmpbf::Element::Relation(relation) = element {
+            let tags: HashMap<&str, &str> = relation.tags().collect();
+            if tags.get("boundary") != Some(&"administrative") {
+                return;
+            }
+            let Some(level) = tags
+                .get("admin_level")
+                .and_then(|level| level.parse().ok())
+                .and_then(AdminLevel::from_osm_level)
+            else {
+                return;
+            };
+            let Some(name) = tags.get("name") else {
+                return;
+            };
+
+            let mut outer_ways = Vec::new();
+            let mut inner_ways = Vec::new();
+            for member in relation.members() {
+                if member.member_type != osmpbf::RelMemberType::Way {
+                    continue;
+                }
+                match member.role() {
+                    Ok("inner") => inner_ways.push(member.member_id),
+                    // role が空のメンバーは outer とみなす
+                    Ok("outer") | Ok("") => outer_ways.push(member.member_id),
+                    _ => {}
+                }
+            }
+
+            relations.push(BoundaryRelation {
+                relation_id: relation.id(),
+                level,
+                code: area_code(
+                    tags.get("ISO3166-2").copied(),
+                    tags.get("ref").copied(),
+                    relation.id(),
+                ),
+                name: name.to_string(),
+                name_en: tags.get("name:en").map(|name| name.to_string()),
+                outer_ways,
+                inner_ways,
+            });
+        }
+    })?;
+
+    tracing::info!(
+        "Found {} administrative boundary relations",
+        relations.len()
+    );
+    if relations.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 2nd pass: メンバーのwayのノード列を集める
+    let member_way_ids: HashSet<i64> = relations
+        .iter()
+        .flat_map(|r| r.outer_ways.iter().chain(r.inner_ways.iter()).copied())
+        .collect();
+    let mut way_nodes: HashMap<i64, Vec<i64>> = HashMap::new();
+
+    let reader = osmpbf::ElementReader::new(File::open(input_path)?);
+    reader.for_each(|element| {
+        if let osmpbf::Element::Way(way) = element {
+            if member_way_ids.contains(&way.id()) {
+                way_nodes.insert(way.id(), way.refs().collect());
+            }
+        }
+    })?;
+
+    tracing::info!(
+        "2nd pass complete: {}/{} boundary ways found",
+        way_nodes.len(),
+        member_way_ids.len()
+    );
+
+    // 3rd pass: wayを構成するノードの座標を集める
+    let needed_node_ids: HashSet<i64> = way_nodes.values().flatten().copied().collect();
+    let mut node_coords: HashMap<i64, Coord<f64>> = HashMap::new();
+
+    let reader = osmpbf::ElementReader::new(File::open(input_path)?);
+    reader.for_each(|element| {
+        let (id, lat, lon) = match element {
+            osmpbf::Element::Node(node) => (node.id(), node.lat(), node.lon()),
+            osmpbf::Element::DenseNode(node) => (node.id(), node.lat(), node.lon()),
+            _ => return,
+        };
+        if needed_node_ids.contains(&id) {
+            node_coords.insert(id, Coord { x: lon, y: lat });
+        }
+    })?;
+
+    tracing::info!(
+        "3rd pass complete: {}/{} boundary nodes found",
+        node_coords.len(),
+        needed_node_ids.len()
+    );
+
+    // リング・ポリゴンを組み立てる（同じコードは最初のものだけ残す）
+    let mut areas = Vec::new();
+    let mut seen_codes = HashSet::new();
+    let mut incomplete = 0;
+
+    for relation in relations {
+        let rings = |way_ids: &[i64]| -> Vec<LineString<f64>> {
+            let ways = way_ids
+                .iter()
+                .filter_map(|id| way_nodes.get(id).cloned())
+                .collect();
+            assemble_rings(ways)
+                .into_iter()
+                .filter_map(|ring| {
+                    ring.iter()
+                        .map(|id| node_coords.get(id).copied())
+                        .collect::<Option<Vec<_>>>()
+                        .map(LineString::from)
+                })
+                .collect()
+        };
+
+        let geometry = build_multipolygon(rings(&relation.outer_ways), rings(&relation.inner_ways));
+        if geometry.0.is_empty() {
+            // 範囲外にはみ出した境界など、閉じたリングが作れないもの
+            incomplete += 1;
+            continue;
+        }
+
+        if !seen_codes.insert(relation.code.clone()) {
+            tracing::warn!(
+                "Skipping relation {} ({}): duplicate area code {}",
+                relation.relation_id,
+                relation.name,
+                relation.code
+            );
+            continue;
+        }
+
+        areas.push(AdminArea {
+            relation_id: relation.relation_id,
+            level: relation.level,
+            code: relation.code,
+            name: relation.name,
+            name_en: relation.name_en,
+            geometry,
+        });
+    }
+
+    tracing::info!(
+        "Assembled {} administrative areas ({} without a closed boundary skipped)",
+        areas.len(),
+        incomplete
+    );
+
+    Ok(areas)
+}
+
+/// Joins ways (as node id sequences) end to end into closed rings
+///
+/// Ways may be reversed to fit. Chains that cannot be closed are dropped.
+pub fn assemble_rings(mut ways: Vec<Vec<i64>>) -> Vec<Vec<i64>> {
+    ways.retain(|way| way.len() >= 2);
+    let mut rings = Vec::new();
+
+    while let Some(mut ring) = ways.pop() {
+        loop {
+            if ring.len() >= 4 && ring.first() == ring.last() {
+                rings.push(ring);
+                break;
+            }
+
+            let last = *ring.last().unwrap();
+            let Some(index) = ways
+                .iter()
+                .position(|way| way.first() == Some(&last) || way.last() == Some(&last))
+            else {
+                break;
+            };
+
+            let mut next = ways.swap_remove(index);
+            if next.first() != Some(&last) {
+                next.reverse();
+            }
+            ring.extend_from_slice(&next[1..]);
+        }
+    }
+
+    rings
+}
+
+/// Builds polygons from outer rings, attaching each inner ring to the outer ring containing it
+pub fn build_multipolygon(
+    outers: Vec<LineString<f64>>,
+    inners: Vec<LineString<f64>>,
+) -> MultiPolygon<f64> {
+    let mut polygons: Vec<(LineString<f64>, Vec<LineString<f64>>)> = outers
+        .into_iter()
+        .map(|outer| (outer, Vec::new()))
+        .collect();
+
+    for inner in inners {
+        let Some(&first) = inner.0.first() else {
+            continue;
+        };
+        let point = Point::from(first);
+        if let Some((_, holes)) = polygons
+            .iter_mut()
+            .find(|(outer, _)| Polygon::new(outer.clone(), Vec::new()).contains(&point))
+        {
+            holes.push(inner);
+        }
+    }
+
+    MultiPolygon::new(
+        polygons
+            .into_iter()
+            .map(|(outer, holes)| Polygon::new(outer, holes))
+            .collect(),
+    )
+}
+
+/// Formats a multipolygon as WKT `MULTIPOLYGON(((lon lat, ...), ...), ...)`
+pub fn multipolygon_wkt(geometry: &MultiPolygon<f64>) -> String {
+    let ring = |line: &LineString<f64>| {
+        let coords: Vec<String> = line.coords().map(|c| format!("{} {}", c.x, c.y)).collect();
+        format!("({})", coords.join(", "))
+    };
+
+    let polygons: Vec<String> = geometry
+        .iter()
+        .map(|polygon| {
+            let rings: Vec<String> = std::iter::once(polygon.exterior())
+                .chain(polygon.interiors())
+                .map(ring)
+                .collect();
+            format!("({})", rings.join(", "))
+        })
+        .collect();
+
+    format!("MULTIPOLYGON({})", polygons.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f64, max: f64) -> LineString<f64> {
+        LineString::from(vec![
+            (min, min),
+            (max, min),
+            (max, max),
+            (min, max),
+            (min, min),
+        ])
+    }
+
+    #[test]
+    fn test_area_code_fallback() {
+        assert_eq!(area_code(Some("JP-13"), Some("13"), 1), "JP-13");
+        assert_eq!(area_code(None, Some("13101"), 1), "13101");
+        assert_eq!(area_code(None, Some(""), 42), "r42");
+        assert_eq!(area_code(None, None, 42), "r42");
+    }
+
+    #[test]
+    fn test_assemble_rings_joins_and_reverses_ways() {
+        // 1-2-3, 5-4-3（逆向き）, 5-6-1 で1つのリング
+        let ways = vec![vec![1, 2, 3], vec![5, 4, 3], vec![5, 6, 1]];
+        let rings = assemble_rings(ways);
+
+        assert_eq!(rings.len(), 1);
+        let ring = &rings[0];
+        assert_eq!(ring.first(), ring.last());
+        assert_eq!(ring.len(), 7);
+        let nodes: HashSet<i64> = ring.iter().copied().collect();
+        assert_eq!(nodes, (1..=6).collect());
+    }
+
+    #[test]
+    fn test_assemble_rings_drops_open_chains() {
+        let ways = vec![vec![1, 2, 3, 1], vec![10, 11], vec![11, 12]];
+        let rings = assemble_rings(ways);
+
+        assert_eq!(rings, vec![vec![1, 2, 3, 1]]);
+    }
+
+    #[test]
+    fn test_build_multipolygon_assigns_holes() {
+        let geometry = build_multipolygon(
+            vec![square(0.0, 10.0), square(20.0, 30.0)],
+            vec![square(22.0, 24.0)],
+        );
+
+        assert_eq!(geometry.0.len(), 2);
+        assert!(geometry.0[0].interiors().is_empty());
+        assert_eq!(geometry.0[1].interiors().len(), 1);
+        assert!(!geometry.contains(&Point::new(23.0, 23.0)));
+        assert!(geometry.contains(&Point::new(21.0, 21.0)));
+    }
+
+    #[test]
+    fn test_multipolygon_wkt() {
+        let geometry = build_multipolygon(vec![square(0.0, 1.0)], Vec::new());
+        assert_eq!(
+            multipolygon_wkt(&geometry),
+            "MULTIPOLYGON(((0 0, 1 0, 1 1, 0 1, 0 0)))"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use geo::{HaversineBearing, HaversineDestination, Point};
+use geo::{HaversineBearing, HaversineDestination, HaversineDistance, Point};
 
 /// Calculate a coordinate at a given bearing and distance from a starting point
 ///
@@ -21,9 +21,96 @@ pub fn calculate_neighbor_coord(
     (destination.y(), destination.x()) // (lat, lon)
 }
 
+/// Calculate the distance in meters between two coordinates
+fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let point1: Point<f64> = Point::new(lon1, lat1);
+    let point2: Point<f64> = Point::new(lon2, lat2);
+    point1.haversine_distance(&point2)
+}
+
+/// Calculate the coordinate at a given distance along a path
+///
+/// # Arguments
+/// * `path` - List of (lat, lon) coordinates, starting at the origin
+/// * `distance_meters` - Distance measured along the path from its first coordinate
+///
+/// # Returns
+/// * `Some((lat, lon))` - Interpolated coordinate on the path
+/// * `None` - The path is shorter than `distance_meters` (or has fewer than 2 points)
+pub fn point_along_path(path: &[(f64, f64)], distance_meters: f64) -> Option<(f64, f64)> {
+    let mut remaining = distance_meters;
+
+    for segment in path.windows(2) {
+        let (lat1, lon1) = segment[0];
+        let (lat2, lon2) = segment[1];
+        let length = calculate_distance(lat1, lon1, lat2, lon2);
+
+        if remaining <= length {
+            let bearing = calculate_bearing(lat1, lon1, lat2, lon2);
+            return Some(calculate_neighbor_coord(lat1, lon1, bearing, remaining));
+        }
+
+        remaining -= length;
+    }
+
+    None
+}
+
+/// Truncate a path to the nodes needed to cover `max_distance_meters`
+///
+/// The first node at or beyond the distance is kept, so that [`point_along_path`] can
+/// interpolate up to `max_distance_meters`.
+pub fn truncate_path(path: &[(f64, f64)], max_distance_meters: f64) -> Vec<(f64, f64)> {
+    let mut truncated = Vec::new();
+    let mut travelled = 0.0;
+
+    for (i, &point) in path.iter().enumerate() {
+        if i > 0 {
+            let (lat1, lon1) = path[i - 1];
+            travelled += calculate_distance(lat1, lon1, point.0, point.1);
+        }
+        truncated.push(point);
+        if travelled >= max_distance_meters {
+            break;
+        }
+    }
+
+    truncated
+}
+
+/// Calculate the grade (%) of a profile by least-squares fit
+///
+/// # Arguments
+/// * `samples` - List of (distance in meters, elevation in meters), including the origin
+///
+/// # Returns
+/// * `Some(grade)` - Rise over run in percent, positive when climbing away from the origin
+/// * `None` - Fewer than 2 samples or all samples at the same distance
+pub fn calculate_grade_percent(samples: &[(f64, f64)]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let n = samples.len() as f64;
+    let mean_d = samples.iter().map(|(d, _)| d).sum::<f64>() / n;
+    let mean_h = samples.iter().map(|(_, h)| h).sum::<f64>() / n;
+
+    let covariance: f64 = samples
+        .iter()
+        .map(|(d, h)| (d - mean_d) * (h - mean_h))
+        .sum();
+    let variance: f64 = samples.iter().map(|(d, _)| (d - mean_d).powi(2)).sum();
+
+    if variance == 0.0 {
+        return None;
+    }
+
+    Some(covariance / variance * 100.0)
+}
+
 /// Calculate the bearing (azimuth) from point1 to point2
 /// Returns bearing in degrees (0-360), where 0° is North, 90° is East
-fn calculate_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+pub fn calculate_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     let point1: Point<f64> = Point::new(lon1, lat1);
     let point2: Point<f64> = Point::new(lon2, lat2);
 
@@ -213,6 +300,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_point_along_path() {
+        // 北へ向かう直線（約111m）
+        let path = vec![
+            (CENTER_LAT, CENTER_LON),
+            (CENTER_LAT + LAT_OFFSET_SMALL, CENTER_LON),
+        ];
+
+        let (lat, lon) = point_along_path(&path, 50.0).unwrap();
+        let distance = calculate_distance(CENTER_LAT, CENTER_LON, lat, lon);
+        assert!(
+            (distance - 50.0).abs() < 0.1,
+            "Expected 50m, got {}m",
+            distance
+        );
+        assert!((lon - CENTER_LON).abs() < 1e-9);
+
+        assert!(point_along_path(&path, 200.0).is_none());
+        assert!(point_along_path(&path[..1], 5.0).is_none());
+    }
+
+    #[test]
+    fn test_point_along_path_follows_bend() {
+        // 北へ約111m進んでから東へ曲がる経路
+        let path = vec![
+            (CENTER_LAT, CENTER_LON),
+            (CENTER_LAT + LAT_OFFSET_SMALL, CENTER_LON),
+            (CENTER_LAT + LAT_OFFSET_SMALL, CENTER_LON + LON_OFFSET_SMALL),
+        ];
+        let first_leg = calculate_distance(path[0].0, path[0].1, path[1].0, path[1].1);
+
+        let (lat, lon) = point_along_path(&path, first_leg + 20.0).unwrap();
+        let from_corner = calculate_distance(path[1].0, path[1].1, lat, lon);
+
+        assert!((from_corner - 20.0).abs() < 0.1);
+        assert!(lon > CENTER_LON, "Point should be east of the corner");
+    }
+
+    #[test]
+    fn test_truncate_path() {
+        let path: Vec<(f64, f64)> = (0..10)
+            .map(|i| (CENTER_LAT + LAT_OFFSET_SMALL * 0.1 * i as f64, CENTER_LON))
+            .collect();
+
+        // 各区間は約11m、50mをカバーするには先頭から6点目（約55m）まで必要
+        let truncated = truncate_path(&path, 50.0);
+        assert_eq!(truncated.len(), 6);
+        assert!(point_along_path(&truncated, 50.0).is_some());
+
+        assert_eq!(truncate_path(&path, 1000.0).len(), path.len());
+    }
+
+    #[test]
+    fn test_calculate_grade_percent() {
+        // 50mで4m上る一定勾配 → 8%
+        let samples = [(0.0, 100.0), (5.0, 100.4), (10.0, 100.8), (50.0, 104.0)];
+        let grade = calculate_grade_percent(&samples).unwrap();
+        assert!((grade - 8.0).abs() < 1e-9, "Expected 8%, got {}", grade);
+
+        // 下り勾配は負
+        let samples = [(0.0, 50.0), (20.0, 49.0)];
+        let grade = calculate_grade_percent(&samples).unwrap();
+        assert!((grade + 5.0).abs() < 1e-9, "Expected -5%, got {}", grade);
+
+        assert!(calculate_grade_percent(&[(0.0, 10.0)]).is_none());
+        assert!(calculate_grade_percent(&[(0.0, 10.0), (0.0, 12.0)]).is_none());
+    }
+
     #[test]
     fn test_calculate_junction_angles_invalid_input() {
         let center = (CENTER_LAT, CENTER_LON);
@@ -0,0 +1,462 @@
+//! Compact binary format for preprocessed GSI DEM tiles
+//!
+//! Parsing JPGIS XML is the slowest part of the elevation import, so
+//! `yjunction elevation compile` converts a directory of XML into a single file that can be
+//! memory-mapped and read without parsing.
+//!
+//! Layout (all integers and floats little-endian):
+//!
+//! ```text
+//! header   magic "YDEM" | version u32 | tile count u32 | reserved u32 | index offset u64
+//! data     per tile: width*height f32 elevations (+x-y order), then a nodata mask of
+//!          ceil(width*height / 8) bytes (bit set = valid value, LSB first)
+//! index    per tile: product [u8; 8] | date [u8; 8] | mesh code u32 | width u32 | height u32
+//!          | reserved u32 | data offset u64 | lower lat f64 | lower lon f64
+//!          | upper lat f64 | upper lon f64
+//! ```
+
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::elevation::{DemProduct, ElevationProvider, GsiTile, MeshLevel, TileKey};
+
+/// File extension of compiled DEM files
+pub const COMPILED_EXTENSION: &str = "ydem";
+
+const MAGIC: &[u8; 4] = b"YDEM";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 24;
+const ENTRY_LEN: usize = 72;
+
+/// Value stored for cells without data, matching the GSI XML convention
+const NODATA_VALUE: f32 = -9999.0;
+
+/// Number of tiles parsed in parallel before they are written out
+const COMPILE_CHUNK_TILES: usize = 256;
+
+/// One tile in the index of a compiled file
+#[derive(Debug, Clone)]
+struct CompiledEntry {
+    key: TileKey,
+    date: String,
+    lower_corner: (f64, f64),
+    upper_corner: (f64, f64),
+    width: usize,
+    height: usize,
+    data_offset: usize,
+}
+
+impl CompiledEntry {
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn data_len(&self) -> usize {
+        self.cell_count() * 4 + self.cell_count().div_ceil(8)
+    }
+}
+
+/// A memory-mapped file written by `yjunction elevation compile`
+pub struct CompiledDem {
+    mmap: Mmap,
+    entries: Vec<CompiledEntry>,
+}
+
+impl CompiledDem {
+    /// Maps a compiled file and reads its tile index
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).context(format!("Failed to open {:?}", path))?;
+        // SAFETY: the file is only read, and compiled files are not modified in place
+        // (`yjunction elevation compile` writes a temporary file and renames it).
+        let mmap = unsafe { Mmap::map(&file) }.context(format!("Failed to map {:?}", path))?;
+
+        anyhow::ensure!(
+            mmap.len() >= HEADER_LEN && &mmap[..4] == MAGIC,
+            "Not a compiled DEM file"
+        );
+        let version = read_u32(&mmap, 4);
+        anyhow::ensure!(
+            version == VERSION,
+            "Unsupported compiled DEM version {}",
+            version
+        );
+
+        let tile_count = read_u32(&mmap, 8) as usize;
+        let index_offset = read_u64(&mmap, 16) as usize;
+        anyhow::ensure!(
+            tile_count
+                .checked_mul(ENTRY_LEN)
+                .and_then(|len| len.checked_add(index_offset))
+                .is_some_and(|end| end <= mmap.len()),
+            "Truncated tile index"
+        );
+
+        let entries = (0..tile_count)
+            .map(|i| {
+                let entry = parse_entry(&mmap[index_offset + i * ENTRY_LEN..][..ENTRY_LEN])?;
+                anyhow::ensure!(
+                    entry
+                        .data_offset
+                        .checked_add(entry.data_len())
+                        .is_some_and(|end| end <= index_offset),
+                    "Tile {} data is out of bounds",
+                    i
+                );
+                Ok(entry)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        tracing::info!("Opened compiled DEM {:?}: {} tiles", path, entries.len());
+
+        Ok(Self { mmap, entries })
+    }
+
+    /// Keys and publication dates of the tiles, in index order
+    pub(super) fn tiles(&self) -> impl Iterator<Item = (&TileKey, &str)> {
+        self.entries.iter().map(|e| (&e.key, e.date.as_str()))
+    }
+
+    /// Decodes one tile; cells masked as nodata are returned as -9999
+    pub(super) fn read_tile(&self, entry: usize) -> Result<GsiTile> {
+        let entry = self
+            .entries
+            .get(entry)
+            .context(format!("No tile at index {}", entry))?;
+
+        let cells = entry.cell_count();
+        let data = &self.mmap[entry.data_offset..][..entry.data_len()];
+        let (values, mask) = data.split_at(cells * 4);
+
+        let elevations = values
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(i, bytes)| {
+                if mask[i / 8] & (1 << (i % 8)) != 0 {
+                    f32::from_le_bytes(bytes.try_into().unwrap()) as f64
+                } else {
+                    NODATA_VALUE as f64
+                }
+            })
+            .collect();
+
+        Ok(GsiTile {
+            lower_corner: entry.lower_corner,
+            upper_corner: entry.upper_corner,
+            grid_width: entry.width,
+            grid_height: entry.height,
+            elevations,
+        })
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_f64(bytes: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Reads a zero-padded ASCII field
+fn read_str(bytes: &[u8]) -> Result<&str> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).context("Invalid string in tile index")
+}
+
+/// Writes `value` into a fixed-size zero-padded ASCII field
+fn write_str<const N: usize>(value: &str) -> Result<[u8; N]> {
+    anyhow::ensure!(value.len() <= N, "Value too long for index: {}", value);
+    let mut field = [0u8; N];
+    field[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(field)
+}
+
+fn parse_entry(bytes: &[u8]) -> Result<CompiledEntry> {
+    let product_code = read_str(&bytes[0..8])?;
+    let product = DemProduct::from_code(product_code)
+        .context(format!("Unknown DEM product: {}", product_code))?;
+    let date = read_str(&bytes[8..16])?.to_string();
+
+    let mesh = read_u32(bytes, 16);
+    let mesh_code = match product.mesh_level() {
+        MeshLevel::Second => format!("{:04}-{:02}", mesh / 100, mesh % 100),
+        MeshLevel::Third => format!(
+            "{:04}-{:02}-{:02}",
+            mesh / 10_000,
+            mesh / 100 % 100,
+            mesh % 100
+        ),
+    };
+
+    Ok(CompiledEntry {
+        key: TileKey { product, mesh_code },
+        date,
+        width: read_u32(bytes, 20) as usize,
+        height: read_u32(bytes, 24) as usize,
+        data_offset: read_u64(bytes, 32) as usize,
+        lower_corner: (read_f64(bytes, 40), read_f64(bytes, 48)),
+        upper_corner: (read_f64(bytes, 56), read_f64(bytes, 64)),
+    })
+}
+
+fn encode_entry(entry: &CompiledEntry) -> Result<Vec<u8>> {
+    let mesh: u32 = entry
+        .key
+        .mesh_code
+        .replace('-', "")
+        .parse()
+        .context(format!("Invalid mesh code: {}", entry.key.mesh_code))?;
+
+    let mut bytes = Vec::with_capacity(ENTRY_LEN);
+    bytes.extend_from_slice(&write_str::<8>(entry.key.product.as_str())?);
+    bytes.extend_from_slice(&write_str::<8>(&entry.date)?);
+    bytes.extend_from_slice(&mesh.to_le_bytes());
+    bytes.extend_from_slice(&(entry.width as u32).to_le_bytes());
+    bytes.extend_from_slice(&(entry.height as u32).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&(entry.data_offset as u64).to_le_bytes());
+    for value in [
+        entry.lower_corner.0,
+        entry.lower_corner.1,
+        entry.upper_corner.0,
+        entry.upper_corner.1,
+    ] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    debug_assert_eq!(bytes.len(), ENTRY_LEN);
+
+    Ok(bytes)
+}
+
+/// Summary of a `yjunction elevation compile` run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompileSummary {
+    pub tiles: usize,
+    pub skipped: usize,
+    pub bytes: u64,
+}
+
+/// Converts the elevation data under `data_dir` into a compiled file at `output`
+///
+/// Tiles are resolved exactly as [`ElevationProvider`] would (zip archives, newest version
+/// of duplicated tiles), so the compiled file gives the same elevations as the source data.
+pub fn compile(data_dir: &str, output: &Path) -> Result<CompileSummary> {
+    let provider = ElevationProvider::new(data_dir)?;
+
+    let mut tiles: Vec<_> = provider.tile_index.iter().collect();
+    tiles.sort_by(|a, b| a.0.cmp(b.0));
+
+    // 一時ファイルに書き込んでからリネームする（mmap中のファイルを書き換えないため）
+    let tmp_path = PathBuf::from(format!("{}.tmp", output.display()));
+    let mut writer = BufWriter::new(
+        File::create(&tmp_path).context(format!("Failed to create {:?}", tmp_path))?,
+    );
+    writer.write_all(&[0u8; HEADER_LEN])?;
+
+    let mut offset = HEADER_LEN;
+    let mut entries = Vec::with_capacity(tiles.len());
+    let mut summary = CompileSummary::default();
+
+    for chunk in tiles.chunks(COMPILE_CHUNK_TILES) {
+        let parsed: Vec<_> = chunk
+            .par_iter()
+            .map(|(key, indexed)| (*key, *indexed, provider.read_tile(&indexed.source)))
+            .collect();
+
+        for (key, indexed, result) in parsed {
+            let tile = match result {
+                Ok(tile) => tile,
+                Err(e) => {
+                    tracing::warn!("Skipping {}: {}", indexed.source, e);
+                    summary.skipped += 1;
+                    continue;
+                }
+            };
+
+            let entry = CompiledEntry {
+                key: key.clone(),
+                date: indexed.date.clone(),
+                lower_corner: tile.lower_corner,
+                upper_corner: tile.upper_corner,
+                width: tile.grid_width,
+                height: tile.grid_height,
+                data_offset: offset,
+            };
+
+            // 境界タイルでデータが不足するセルもnodataとして書き込む
+            let cells = entry.cell_count();
+            let mut mask = vec![0u8; cells.div_ceil(8)];
+            for i in 0..cells {
+                let value = tile
+                    .elevations
+                    .get(i)
+                    .copied()
+                    .filter(|&e| e != NODATA_VALUE as f64);
+                match value {
+                    Some(elevation) => {
+                        writer.write_all(&(elevation as f32).to_le_bytes())?;
+                        mask[i / 8] |= 1 << (i % 8);
+                    }
+                    None => writer.write_all(&NODATA_VALUE.to_le_bytes())?,
+                }
+            }
+            writer.write_all(&mask)?;
+
+            offset += entry.data_len();
+            entries.push(entry);
+        }
+
+        tracing::info!("Compiled {}/{} tiles", entries.len(), tiles.len());
+    }
+
+    let index_offset = offset;
+    for entry in &entries {
+        writer.write_all(&encode_entry(entry)?)?;
+    }
+
+    writer.seek(SeekFrom::Start(0))?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&(index_offset as u64).to_le_bytes())?;
+    writer.flush()?;
+    drop(writer);
+
+    std::fs::rename(&tmp_path, output).context(format!("Failed to write {:?}", output))?;
+
+    summary.tiles = entries.len();
+    summary.bytes = (index_offset + entries.len() * ENTRY_LEN) as u64;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE_DIR: &str = "tests/fixtures/gsi";
+    const FIXTURE_XML: &str = "tests/fixtures/gsi/xml/FG-GML-5238-40-00-DEM5B-20210115.xml";
+
+    fn compile_to_tempdir(data_dir: &str) -> (tempfile::TempDir, PathBuf) {
+        let out_dir = tempfile::tempdir().unwrap();
+        let output = out_dir.path().join("dem.ydem");
+        compile(data_dir, &output).unwrap();
+        (out_dir, output)
+    }
+
+    #[test]
+    fn test_compiled_matches_xml() {
+        let (_dir, output) = compile_to_tempdir(FIXTURE_DIR);
+
+        let xml = ElevationProvider::new(FIXTURE_DIR).unwrap();
+        let compiled = ElevationProvider::new(output.to_str().unwrap()).unwrap();
+
+        for i in 0..10 {
+            for j in 0..10 {
+                let lat = 35.0005 + i as f64 * 0.001;
+                let lon = 138.0005 + j as f64 * 0.001;
+                let expected = xml.get_elevation_with_product(lat, lon).unwrap();
+                let actual = compiled.get_elevation_with_product(lat, lon).unwrap();
+
+                match (expected, actual) {
+                    (Some((e, ep)), Some((a, ap))) => {
+                        assert!((e - a).abs() < 1e-3, "({}, {}): {} vs {}", lat, lon, e, a);
+                        assert_eq!(ep, ap);
+                    }
+                    (None, None) => {}
+                    other => panic!("({}, {}): mismatch {:?}", lat, lon, other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compiled_file_in_data_dir() {
+        let (dir, _output) = compile_to_tempdir(FIXTURE_DIR);
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(provider.tile_index.len(), 1);
+        assert!(provider.get_elevation(35.005, 138.005).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_nodata_mask() {
+        let xml = std::fs::read_to_string(FIXTURE_XML).unwrap();
+        let xml = xml
+            .lines()
+            .map(|line| {
+                if line.starts_with("地表面,") {
+                    "地表面,-9999.".to_string()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let data_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            data_dir.path().join("FG-GML-5238-40-00-DEM5B-20210115.xml"),
+            xml,
+        )
+        .unwrap();
+
+        let (_dir, output) = compile_to_tempdir(data_dir.path().to_str().unwrap());
+        let dem = CompiledDem::open(&output).unwrap();
+        let tile = dem.read_tile(0).unwrap();
+        assert!(tile.elevations.iter().all(|&e| e == NODATA_VALUE as f64));
+
+        let provider = ElevationProvider::new(output.to_str().unwrap()).unwrap();
+        assert_eq!(provider.get_elevation(35.005, 138.005).unwrap(), None);
+    }
+
+    #[test]
+    fn test_entry_round_trip() {
+        let entry = CompiledEntry {
+            key: TileKey {
+                product: DemProduct::Dem10B,
+                mesh_code: "5238-40".to_string(),
+            },
+            date: "20161001".to_string(),
+            lower_corner: (35.0, 138.0),
+            upper_corner: (35.083333, 138.125),
+            width: 1125,
+            height: 750,
+            data_offset: 24,
+        };
+
+        let parsed = parse_entry(&encode_entry(&entry).unwrap()).unwrap();
+        assert_eq!(parsed.key, entry.key);
+        assert_eq!(parsed.date, entry.date);
+        assert_eq!(parsed.lower_corner, entry.lower_corner);
+        assert_eq!(parsed.upper_corner, entry.upper_corner);
+        assert_eq!((parsed.width, parsed.height), (1125, 750));
+        assert_eq!(parsed.data_offset, 24);
+    }
+
+    #[test]
+    fn test_rejects_invalid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.ydem");
+        std::fs::write(&path, b"not a compiled dem").unwrap();
+        assert!(CompiledDem::open(&path).is_err());
+
+        // ヘッダのみでインデックスが欠けているファイル
+        let mut truncated = Vec::new();
+        truncated.extend_from_slice(MAGIC);
+        truncated.extend_from_slice(&VERSION.to_le_bytes());
+        truncated.extend_from_slice(&5u32.to_le_bytes());
+        truncated.extend_from_slice(&0u32.to_le_bytes());
+        truncated.extend_from_slice(&(HEADER_LEN as u64).to_le_bytes());
+        std::fs::write(&path, truncated).unwrap();
+        assert!(CompiledDem::open(&path).is_err());
+    }
+}
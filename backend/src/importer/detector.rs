@@ -5,6 +5,68 @@ use std::collections::{HashMap, HashSet};
 pub struct WayTagInfo {
     pub bridge: bool,
     pub tunnel: bool,
+    /// Value of the highway tag (e.g. "primary", "residential_link")
+    pub highway: String,
+    /// Value of the name tag
+    pub name: Option<String>,
+    /// Other names to search by (name:en, readings, alt_name)
+    pub alt_names: Vec<String>,
+}
+
+/// Tags whose values are searched in addition to `name`
+pub const SEARCH_NAME_TAGS: [&str; 6] = [
+    "alt_name",
+    "name:en",
+    "name:ja-Hira",
+    "name:ja_kana",
+    "name:ja-Latn",
+    "name:ja_rm",
+];
+
+/// Joins the distinct names of the ways into one text for road name search
+/// Returns None if none of the ways has a name
+pub fn road_search_text<'a>(tags: impl IntoIterator<Item = &'a WayTagInfo>) -> Option<String> {
+    let mut names: Vec<&str> = Vec::new();
+    for tag in tags {
+        for name in tag.name.iter().chain(tag.alt_names.iter()) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+    }
+
+    if names.is_empty() {
+        None
+    } else {
+        Some(names.join("\n"))
+    }
+}
+
+/// Highway classes from the most to the least important
+/// Link roads are ranked with their main road class
+const HIGHWAY_CLASS_RANK: [&str; 8] = [
+    "motorway",
+    "trunk",
+    "primary",
+    "secondary",
+    "tertiary",
+    "unclassified",
+    "residential",
+    "service",
+];
+
+/// Returns the most important highway class among the given ways, with "_link" stripped
+pub fn highway_class<'a>(tags: impl IntoIterator<Item = &'a WayTagInfo>) -> Option<String> {
+    tags.into_iter()
+        .filter_map(|tag| {
+            let class = tag.highway.trim_end_matches("_link");
+            HIGHWAY_CLASS_RANK
+                .iter()
+                .position(|&c| c == class)
+                .map(|rank| (rank, HIGHWAY_CLASS_RANK[rank]))
+        })
+        .min_by_key(|&(rank, _)| rank)
+        .map(|(_, class)| class.to_string())
 }
 
 /// Y-junction candidate information
@@ -50,6 +112,10 @@ pub struct JunctionForInsert {
     #[allow(dead_code)]
     pub max_elevation_diff: Option<f64>,
 
+    /// Geometry of each road arm as (lat, lon) coordinates, starting at the junction node
+    /// Order corresponds to bearings; an empty path means the geometry is unavailable
+    pub arm_geometries: [Vec<(f64, f64)>; 3],
+
     // Way tag information for filtering
     pub way_1_bridge: bool,
     pub way_1_tunnel: bool,
@@ -57,6 +123,12 @@ pub struct JunctionForInsert {
     pub way_2_tunnel: bool,
     pub way_3_bridge: bool,
     pub way_3_tunnel: bool,
+    /// Most important highway class among the three roads (see `highway_class`)
+    pub highway_class: Option<String>,
+    /// Name of each road, in the same order as bearings
+    pub road_names: [Option<String>; 3],
+    /// Names of the roads for text search (see `road_search_text`)
+    pub road_search_text: Option<String>,
 }
 
 impl JunctionForInsert {
@@ -139,7 +211,7 @@ impl NodeConnectionCounter {
         &mut self,
         way_id: i64,
         node_ids: &[i64],
-        _highway_type: &str,
+        highway_type: &str,
         bridge: bool,
         tunnel: bool,
     ) {
@@ -147,13 +219,29 @@ impl NodeConnectionCounter {
         self.way_nodes.insert(way_id, node_ids.to_vec());
 
         // Store way tags
-        self.way_tags.insert(way_id, WayTagInfo { bridge, tunnel });
+        self.way_tags.insert(
+            way_id,
+            WayTagInfo {
+                bridge,
+                tunnel,
+                highway: highway_type.to_string(),
+                ..Default::default()
+            },
+        );
 
         for &node_id in node_ids {
             self.node_to_ways.entry(node_id).or_default().insert(way_id);
         }
     }
 
+    /// Set the names of a way added with [`Self::add_way`]
+    pub fn set_way_names(&mut self, way_id: i64, name: Option<String>, alt_names: Vec<String>) {
+        if let Some(tags) = self.way_tags.get_mut(&way_id) {
+            tags.name = name;
+            tags.alt_names = alt_names;
+        }
+    }
+
     /// Get the neighboring node IDs for a Y-junction node
     /// Returns up to 3 neighboring nodes (one per connected way)
     pub fn get_neighboring_nodes(&self, junction_node_id: i64) -> Vec<i64> {
@@ -210,6 +298,36 @@ impl NodeConnectionCounter {
         result
     }
 
+    /// Get the road arms leaving a Y-junction node with their way tags
+    /// Each arm is the list of node IDs walking away from the junction along one connected way
+    /// (excluding the junction node itself), limited to `max_nodes` nodes.
+    /// The direction follows [`Self::get_neighbors_with_tags`] (prefer next, fallback to previous).
+    pub fn get_arms(&self, junction_node_id: i64, max_nodes: usize) -> Vec<(Vec<i64>, WayTagInfo)> {
+        let mut result = Vec::new();
+
+        if let Some(way_ids) = self.node_to_ways.get(&junction_node_id) {
+            for &way_id in way_ids {
+                if let Some(nodes) = self.way_nodes.get(&way_id) {
+                    if let Some(pos) = nodes.iter().position(|&id| id == junction_node_id) {
+                        let arm: Vec<i64> = if pos + 1 < nodes.len() {
+                            nodes[pos + 1..].iter().take(max_nodes).copied().collect()
+                        } else if pos > 0 {
+                            nodes[..pos].iter().rev().take(max_nodes).copied().collect()
+                        } else {
+                            continue;
+                        };
+
+                        let tags = self.way_tags.get(&way_id).cloned().unwrap_or_default();
+
+                        result.push((arm, tags));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
     /// Find all nodes that have exactly 3 way connections (Y-junction candidates)
     pub fn find_y_junction_candidates(&self) -> Vec<YJunctionCandidate> {
         self.node_to_ways
@@ -289,6 +407,58 @@ mod tests {
         assert_eq!(candidates[0].connected_ways.len(), 3);
     }
 
+    #[test]
+    fn test_highway_class_picks_most_important_road() {
+        let tag = |highway: &str| WayTagInfo {
+            highway: highway.to_string(),
+            ..Default::default()
+        };
+
+        let tags = [tag("residential"), tag("secondary_link"), tag("tertiary")];
+        assert_eq!(highway_class(&tags), Some("secondary".to_string()));
+
+        let tags = [tag("service"), tag("service"), tag("unclassified")];
+        assert_eq!(highway_class(&tags), Some("unclassified".to_string()));
+
+        assert_eq!(highway_class(&[tag("footway")]), None);
+        assert_eq!(highway_class(&[]), None);
+    }
+
+    #[test]
+    fn test_way_names_and_search_text() {
+        let mut counter = NodeConnectionCounter::new();
+        counter.add_way(1, &[1, 2], "secondary", false, false);
+        counter.add_way(2, &[2, 3], "secondary", false, false);
+        counter.add_way(3, &[2, 4], "residential", false, false);
+        counter.set_way_names(
+            1,
+            Some("旧中山道".to_string()),
+            vec!["Kyu-Nakasendo".to_string()],
+        );
+        counter.set_way_names(
+            2,
+            Some("旧中山道".to_string()),
+            vec!["きゅうなかせんどう".to_string()],
+        );
+
+        assert_eq!(
+            counter.way_tags.get(&1).unwrap().name.as_deref(),
+            Some("旧中山道")
+        );
+
+        let tags = counter.get_connected_way_tags(2);
+        let text = road_search_text(&tags).unwrap();
+        let mut names: Vec<&str> = text.split('\n').collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec!["Kyu-Nakasendo", "きゅうなかせんどう", "旧中山道"]
+        );
+
+        let unnamed = [WayTagInfo::default()];
+        assert_eq!(road_search_text(&unnamed), None);
+    }
+
     #[test]
     fn test_valid_highway_types() {
         let counter = NodeConnectionCounter::new();
@@ -366,6 +536,8 @@ mod tests {
 
         assert!(!counter.way_tags.get(&3).unwrap().bridge);
         assert!(!counter.way_tags.get(&3).unwrap().tunnel);
+
+        assert_eq!(counter.way_tags.get(&1).unwrap().highway, "primary");
     }
 
     #[test]
@@ -392,6 +564,30 @@ mod tests {
         assert!(has_neither, "Should have a normal way");
     }
 
+    #[test]
+    fn test_get_arms() {
+        let mut counter = NodeConnectionCounter::new();
+
+        // Y-junction at node 2
+        counter.add_way(1, &[13, 12, 11, 2], "primary", true, false); // ends at junction
+        counter.add_way(2, &[2, 21, 22, 23], "secondary", false, false); // starts at junction
+        counter.add_way(3, &[2, 31], "tertiary", false, true);
+
+        let arms = counter.get_arms(2, 2);
+        assert_eq!(arms.len(), 3);
+
+        let arm_1 = arms.iter().find(|(nodes, _)| nodes[0] == 11).unwrap();
+        assert_eq!(arm_1.0, vec![11, 12], "Should walk away from the junction");
+        assert!(arm_1.1.bridge);
+
+        let arm_2 = arms.iter().find(|(nodes, _)| nodes[0] == 21).unwrap();
+        assert_eq!(arm_2.0, vec![21, 22], "Should be limited to max_nodes");
+
+        let arm_3 = arms.iter().find(|(nodes, _)| nodes[0] == 31).unwrap();
+        assert_eq!(arm_3.0, vec![31]);
+        assert!(arm_3.1.tunnel);
+    }
+
     #[test]
     fn test_get_neighbors_with_tags() {
         let mut counter = NodeConnectionCounter::new();
@@ -2,9 +2,96 @@ use anyhow::{Context, Result};
 use glob::glob;
 use roxmltree::Document;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zip::ZipArchive;
+
+use super::compiled_dem::{CompiledDem, COMPILED_EXTENSION};
+use super::tile_cache::{CacheStats, SharedTileCache, Weighted};
+use crate::domain::ElevationQuality;
+
+/// Default memory budget for parsed tiles (256 MiB)
+pub const DEFAULT_CACHE_CAPACITY_BYTES: usize = 256 * 1024 * 1024;
+
+/// How far (in cells) to look for valid data when the cell at a point has none
+pub const FILL_RADIUS_CELLS: i64 = 2;
+
+/// Value GSI uses for cells without data
+const NODATA_VALUE: f64 = -9999.0;
+
+/// Memory budget for nested zip archives kept decompressed between tile loads (128 MiB)
+const NESTED_ZIP_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+/// Tolerance for points on a tile seam, in degrees (about 0.1 mm)
+const SEAM_EPSILON_DEG: f64 = 1e-9;
+
+/// Mesh level a DEM product is distributed at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshLevel {
+    /// 2次メッシュ (e.g. "5238-40"), used by 10m products
+    Second,
+    /// 3次メッシュ (e.g. "5238-40-00"), used by 5m products
+    Third,
+}
+
+/// GSI DEM product type
+///
+/// Variants are declared in order of precedence: when several products cover a point,
+/// the first one with valid data wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DemProduct {
+    /// 5mメッシュ（航空レーザ測量）
+    Dem5A,
+    /// 5mメッシュ（写真測量）
+    Dem5B,
+    /// 5mメッシュ（写真測量、補間）
+    Dem5C,
+    /// 10mメッシュ（火山標高）
+    Dem10A,
+    /// 10mメッシュ（地形図の等高線）
+    Dem10B,
+}
+
+impl DemProduct {
+    pub(super) fn from_code(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "DEM5A" => Some(Self::Dem5A),
+            "DEM5B" => Some(Self::Dem5B),
+            "DEM5C" => Some(Self::Dem5C),
+            "DEM10A" => Some(Self::Dem10A),
+            "DEM10B" => Some(Self::Dem10B),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dem5A => "DEM5A",
+            Self::Dem5B => "DEM5B",
+            Self::Dem5C => "DEM5C",
+            Self::Dem10A => "DEM10A",
+            Self::Dem10B => "DEM10B",
+        }
+    }
 
-fn calculate_mesh_code(lat: f64, lon: f64) -> String {
+    pub fn mesh_level(&self) -> MeshLevel {
+        match self {
+            Self::Dem5A | Self::Dem5B | Self::Dem5C => MeshLevel::Third,
+            Self::Dem10A | Self::Dem10B => MeshLevel::Second,
+        }
+    }
+}
+
+impl fmt::Display for DemProduct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn calculate_mesh_code(lat: f64, lon: f64, level: MeshLevel) -> String {
     // 基盤地図情報の標準メッシュコード計算式
     //
     // 計算方法の出典:
@@ -26,6 +113,10 @@ fn calculate_mesh_code(lat: f64, lon: f64) -> String {
     let lon_2 = (lon_mesh / 10) % 8;
     let second_mesh = format!("{}{}", lat_2, lon_2);
 
+    if level == MeshLevel::Second {
+        return format!("{}-{}", first_mesh, second_mesh);
+    }
+
     // 3次メッシュ番号 (rr)
     let lat_3 = lat_mesh % 10;
     let lon_3 = lon_mesh % 10;
@@ -34,45 +125,154 @@ fn calculate_mesh_code(lat: f64, lon: f64) -> String {
     format!("{}-{}-{}", first_mesh, second_mesh, third_mesh)
 }
 
+/// Identifies one DEM tile: a product at a mesh code of that product's level
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(super) struct TileKey {
+    pub(super) product: DemProduct,
+    pub(super) mesh_code: String,
+}
+
+impl TileKey {
+    /// Parses a GSI file name such as `FG-GML-5238-40-00-DEM5A-20161001.xml`
+    /// (5m products, 3次メッシュ) or `FG-GML-5238-40-DEM10B-20161001.xml` (10m products, 2次メッシュ)
+    ///
+    /// Returns the key together with the publication date, used to pick the newest file
+    /// when the same tile appears more than once.
+    fn from_filename(filename: &str) -> Option<(Self, String)> {
+        let start = filename.find("FG-GML-")?;
+        let stem = filename[start + 7..].split('.').next()?;
+        let parts: Vec<&str> = stem.split('-').collect();
+
+        let product_pos = parts
+            .iter()
+            .position(|p| DemProduct::from_code(p).is_some())?;
+        let product = DemProduct::from_code(parts[product_pos])?;
+        let mesh_parts = &parts[..product_pos];
+
+        let expected_parts = match product.mesh_level() {
+            MeshLevel::Second => 2,
+            MeshLevel::Third => 3,
+        };
+        if mesh_parts.len() != expected_parts {
+            return None;
+        }
+
+        let date = parts.get(product_pos + 1).copied().unwrap_or_default();
+
+        Some((
+            Self {
+                product,
+                mesh_code: mesh_parts.join("-"),
+            },
+            date.to_string(),
+        ))
+    }
+
+    /// Derives the key from the `<mesh>` and `<type>` metadata of a DEM document
+    ///
+    /// Used for files that do not follow the GSI naming convention. The metadata only tells
+    /// the resolution, so 5m data is treated as DEM5B and 10m data as DEM10B.
+    fn from_metadata(xml: &str) -> Option<Self> {
+        let element_text = |tag: &str| {
+            let open = format!("<{}>", tag);
+            let close = format!("</{}>", tag);
+            let start = xml.find(&open)? + open.len();
+            let end = start + xml[start..].find(&close)?;
+            Some(xml[start..end].trim())
+        };
+
+        let mesh = element_text("mesh")?;
+        let product = match element_text("type")? {
+            t if t.starts_with("5m") => DemProduct::Dem5B,
+            t if t.starts_with("10m") => DemProduct::Dem10B,
+            _ => return None,
+        };
+
+        if !mesh.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let mesh_code = match (product.mesh_level(), mesh.len()) {
+            (MeshLevel::Second, 6) => format!("{}-{}", &mesh[..4], &mesh[4..6]),
+            (MeshLevel::Third, 8) => format!("{}-{}-{}", &mesh[..4], &mesh[4..6], &mesh[6..8]),
+            _ => return None,
+        };
+
+        Some(Self { product, mesh_code })
+    }
+}
+
 /// GSI DEM tile (one XML file)
 #[derive(Debug, Clone)]
-struct GsiTile {
-    lower_corner: (f64, f64), // (lat, lon) - Southwest corner
-    upper_corner: (f64, f64), // (lat, lon) - Northeast corner
-    grid_width: usize,        // Number of columns (X direction)
-    grid_height: usize,       // Number of rows (Y direction)
-    elevations: Vec<f64>,     // Elevation values in +x-y order
+pub(super) struct GsiTile {
+    pub(super) lower_corner: (f64, f64), // (lat, lon) - Southwest corner
+    pub(super) upper_corner: (f64, f64), // (lat, lon) - Northeast corner
+    pub(super) grid_width: usize,        // Number of columns (X direction)
+    pub(super) grid_height: usize,       // Number of rows (Y direction)
+    pub(super) elevations: Vec<f64>,     // Elevation values in +x-y order
+}
+
+impl Weighted for GsiTile {
+    fn weight_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.elevations.capacity() * std::mem::size_of::<f64>()
+    }
 }
 
 impl GsiTile {
     /// Check if this tile contains the given coordinate
+    ///
+    /// Bounds are half-open (south and west edges inclusive, north and east edges exclusive),
+    /// so a point on a seam belongs to exactly one of the adjacent tiles, matching the
+    /// `floor` in the mesh code calculation.
     fn contains(&self, lat: f64, lon: f64) -> bool {
         lat >= self.lower_corner.0
-            && lat <= self.upper_corner.0
+            && lat < self.upper_corner.0
             && lon >= self.lower_corner.1
-            && lon <= self.upper_corner.1
+            && lon < self.upper_corner.1
+    }
+
+    /// Size of one cell in degrees: (lat, lon)
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            (self.upper_corner.0 - self.lower_corner.0) / self.grid_height as f64,
+            (self.upper_corner.1 - self.lower_corner.1) / self.grid_width as f64,
+        )
+    }
+
+    /// Column and row (counted from the south) of the cell containing the coordinate,
+    /// in fractional cell units from the tile's south-west corner
+    fn cell_position(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (cell_lat, cell_lon) = self.cell_size();
+        (
+            (lon - self.lower_corner.1) / cell_lon,
+            (lat - self.lower_corner.0) / cell_lat,
+        )
     }
 
-    /// Get elevation at the given coordinate
-    /// Returns None if coordinate is outside tile or elevation is invalid
+    /// Coordinate of the center of a cell, which may lie outside this tile
+    fn cell_center(&self, column: i64, row_from_south: i64) -> (f64, f64) {
+        let (cell_lat, cell_lon) = self.cell_size();
+        (
+            self.lower_corner.0 + (row_from_south as f64 + 0.5) * cell_lat,
+            self.lower_corner.1 + (column as f64 + 0.5) * cell_lon,
+        )
+    }
+
+    /// Get the raw value of the cell containing the given coordinate
+    /// Returns None if coordinate is outside tile (values may be -9999 for no data)
     fn get_elevation(&self, lat: f64, lon: f64) -> Option<f64> {
         if !self.contains(lat, lon) {
             return None;
         }
 
-        // Calculate fractional position within tile (0.0 to 1.0)
-        let lat_frac = (lat - self.lower_corner.0) / (self.upper_corner.0 - self.lower_corner.0);
-        let lon_frac = (lon - self.lower_corner.1) / (self.upper_corner.1 - self.lower_corner.1);
+        let (x, y) = self.cell_position(lat, lon);
 
         // Convert to grid coordinates
         // Note: GSI data is ordered +x-y (west to east, north to south)
         // Clamp to prevent floating point edge cases
-        let x = ((lon_frac * (self.grid_width - 1) as f64)
-            .round()
-            .clamp(0.0, (self.grid_width - 1) as f64)) as usize;
-        let y = (((1.0 - lat_frac) * (self.grid_height - 1) as f64)
-            .round()
-            .clamp(0.0, (self.grid_height - 1) as f64)) as usize;
+        let x = (x.floor() as usize).min(self.grid_width - 1);
+        let y_from_south = (y.floor() as usize).min(self.grid_height - 1);
+        let y = self.grid_height - 1 - y_from_south;
 
         // Calculate index in flat array
         let index = y * self.grid_width + x;
@@ -81,64 +281,355 @@ impl GsiTile {
     }
 }
 
+/// Location of a GSI DEM tile
+///
+/// GSI distributes DEM data as zip archives that may themselves contain zip archives,
+/// so an XML tile is either a plain file or a chain of archive members ending in an XML file.
+/// Tiles can also come from a file written by `yjunction elevation compile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum TileSource {
+    File(PathBuf),
+    ZipMember {
+        archive: PathBuf,
+        /// Member names from the outermost archive inwards; the last one is the XML file
+        members: Vec<String>,
+    },
+    Compiled {
+        path: PathBuf,
+        /// Position of the tile in the compiled file's index
+        entry: usize,
+    },
+}
+
+/// A nested zip archive decompressed into memory
+struct NestedZip(Vec<u8>);
+
+impl Weighted for NestedZip {
+    fn weight_bytes(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Nested zip archives keyed by the outer archive and the chain of members leading to them
+type NestedZipCache = SharedTileCache<(PathBuf, Vec<String>), NestedZip>;
+
+impl TileSource {
+    /// Reads the XML document without extracting anything to disk
+    ///
+    /// Nested zip archives are decompressed once and kept in `nested_zips`, since a national
+    /// archive holds many tiles per inner zip and reopening it for each tile is quadratic.
+    fn read_to_string(&self, nested_zips: &NestedZipCache) -> Result<String> {
+        match self {
+            TileSource::File(path) => std::fs::read_to_string(path)
+                .context(format!("Failed to read XML file: {:?}", path)),
+            TileSource::ZipMember { archive, members } => {
+                let (xml_member, nested) = members
+                    .split_last()
+                    .context("Zip member path must not be empty")?;
+
+                if nested.is_empty() {
+                    return read_member_to_string(&mut open_zip(archive)?, xml_member);
+                }
+
+                let key = (archive.clone(), nested.to_vec());
+                let inner = nested_zips
+                    .get_or_load(&key, || match read_nested_zip(archive, nested) {
+                        Ok(data) => Some(NestedZip(data)),
+                        Err(e) => {
+                            tracing::warn!("Failed to read nested zip in {:?}: {:#}", archive, e);
+                            None
+                        }
+                    })
+                    .context(format!("Failed to read nested zip in {:?}", archive))?;
+                let mut zip = ZipArchive::new(Cursor::new(inner.0.as_slice()))
+                    .context(format!("Failed to read nested zip in {:?}", archive))?;
+                read_member_to_string(&mut zip, xml_member)
+            }
+            TileSource::Compiled { path, .. } => {
+                anyhow::bail!("Compiled tile is not an XML document: {:?}", path)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileSource::File(path) => write!(f, "{}", path.display()),
+            TileSource::ZipMember { archive, members } => {
+                write!(f, "{}", archive.display())?;
+                for member in members {
+                    write!(f, "!{}", member)?;
+                }
+                Ok(())
+            }
+            TileSource::Compiled { path, entry } => write!(f, "{}#{}", path.display(), entry),
+        }
+    }
+}
+
+fn open_zip(archive: &Path) -> Result<ZipArchive<File>> {
+    let file = File::open(archive).context(format!("Failed to open zip archive: {:?}", archive))?;
+    ZipArchive::new(file).context(format!("Failed to read zip archive: {:?}", archive))
+}
+
+/// Decompresses the zip reached by following `nested` members from the outer archive
+fn read_nested_zip(archive: &Path, nested: &[String]) -> Result<Vec<u8>> {
+    let (first, rest) = nested
+        .split_first()
+        .context("Nested zip path must not be empty")?;
+
+    // 入れ子のzipはディスクに展開せず、メモリ上で順に開いて辿る
+    let mut data = read_member(&mut open_zip(archive)?, first)?;
+    for name in rest {
+        let mut inner = ZipArchive::new(Cursor::new(data))
+            .context(format!("Failed to read nested zip: {}", name))?;
+        data = read_member(&mut inner, name)?;
+    }
+
+    Ok(data)
+}
+
+fn read_member<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>> {
+    let mut entry = zip
+        .by_name(name)
+        .context(format!("Zip member not found: {}", name))?;
+    let mut buf = Vec::with_capacity(entry.size() as usize);
+    entry
+        .read_to_end(&mut buf)
+        .context(format!("Failed to read zip member: {}", name))?;
+    Ok(buf)
+}
+
+fn read_member_to_string<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    let bytes = read_member(zip, name)?;
+    String::from_utf8(bytes).context(format!("Zip member is not valid UTF-8: {}", name))
+}
+
+fn has_extension(name: &str, ext: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// Adds a tile to the index unless a newer version of the same tile is already there
+fn insert_newest(tile_index: &mut HashMap<TileKey, IndexedTile>, key: TileKey, tile: IndexedTile) {
+    // 同じタイルが複数ある場合は新しい版を優先する
+    match tile_index.get(&key) {
+        Some(existing) if existing.date >= tile.date => {}
+        _ => {
+            tile_index.insert(key, tile);
+        }
+    }
+}
+
+/// An indexed tile and the publication date taken from its file name
+#[derive(Debug, Clone)]
+pub(super) struct IndexedTile {
+    pub(super) source: TileSource,
+    pub(super) date: String,
+}
+
 /// Provides elevation data from GSI JPGIS XML files
+///
+/// Lookups take `&self`, so one provider can be shared across worker threads.
 pub struct ElevationProvider {
-    /// Cache of loaded tiles, keyed by mesh code
-    cache: HashMap<String, GsiTile>,
-    /// Map from mesh code to XML file path
-    mesh_to_file: HashMap<String, PathBuf>,
+    /// Memory-bounded LRU cache of parsed tiles; tiles that failed to parse are not retried
+    cache: SharedTileCache<TileKey, GsiTile>,
+    /// Map from tile key to the XML document or compiled file holding that tile
+    pub(super) tile_index: HashMap<TileKey, IndexedTile>,
+    /// Nested zip archives decompressed into memory, shared by the tiles they hold
+    nested_zips: NestedZipCache,
+    /// Memory-mapped files written by `yjunction elevation compile`
+    compiled: HashMap<PathBuf, CompiledDem>,
+    /// Products present in the index, in order of precedence
+    products: Vec<DemProduct>,
 }
 
 impl ElevationProvider {
     /// Creates a new ElevationProvider
     ///
+    /// The directory is searched recursively. Plain `.xml` files are indexed directly and
+    /// `.xml` members of `.zip` archives (including zips nested inside zips) are indexed
+    /// in place, without extracting them to disk. Files written by
+    /// `yjunction elevation compile` (`.ydem`) are memory-mapped, and only their tile index is
+    /// read up front.
+    ///
     /// # Arguments
-    /// * `data_dir` - Path to directory containing GSI .xml files (e.g., "data/gsi")
+    /// * `data_dir` - Path to directory containing GSI .xml, .zip or .ydem files
+    ///   (e.g., "data/gsi"), or to a single .ydem file
     ///
     /// # Returns
     /// * `Ok(Self)` - Successfully initialized with elevation data files
-    /// * `Err(...)` - No XML files found in the specified directory
+    /// * `Err(...)` - No elevation data found in the specified directory
     pub fn new(data_dir: &str) -> Result<Self> {
-        let pattern = format!("{}/xml/*.xml", data_dir);
-        let xml_files: Vec<PathBuf> = glob(&pattern)
-            .map(|paths| paths.filter_map(|p| p.ok()).collect())
-            .unwrap_or_default();
+        let files: Vec<PathBuf> = if Path::new(data_dir).is_file() {
+            vec![PathBuf::from(data_dir)]
+        } else {
+            let pattern = format!("{}/**/*", data_dir);
+            glob(&pattern)
+                .map(|paths| {
+                    paths
+                        .filter_map(|p| p.ok())
+                        .filter(|p| p.is_file())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut sources = Vec::new();
+        let mut compiled = HashMap::new();
+        for path in files {
+            let Some(filename) = path.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+
+            if has_extension(filename, "xml") {
+                sources.push((filename.to_string(), TileSource::File(path)));
+            } else if has_extension(filename, "zip") {
+                if let Err(e) = Self::index_zip_file(&path, &mut sources) {
+                    tracing::warn!("Failed to index zip archive {:?}: {}", path, e);
+                }
+            } else if has_extension(filename, COMPILED_EXTENSION) {
+                match CompiledDem::open(&path) {
+                    Ok(dem) => {
+                        compiled.insert(path, dem);
+                    }
+                    Err(e) => tracing::warn!("Failed to open compiled DEM {:?}: {}", path, e),
+                }
+            }
+        }
 
         anyhow::ensure!(
-            !xml_files.is_empty(),
-            "No XML files found in {}. Cannot proceed with elevation import.",
-            pattern
+            !sources.is_empty() || !compiled.is_empty(),
+            "No XML or compiled DEM files found under {} (searched recursively, including zip archives). Cannot proceed with elevation import.",
+            data_dir
         );
 
-        let mut mesh_to_file = HashMap::new();
-        for path in xml_files {
-            if let Some(filename) = path.file_name().and_then(|f| f.to_str()) {
-                if let Some(mesh_code) = Self::extract_mesh_code(filename) {
-                    mesh_to_file.insert(mesh_code, path);
-                }
+        let mut tile_index: HashMap<TileKey, IndexedTile> = HashMap::new();
+        for (path, dem) in &compiled {
+            for (entry, (key, date)) in dem.tiles().enumerate() {
+                let source = TileSource::Compiled {
+                    path: path.clone(),
+                    entry,
+                };
+                insert_newest(
+                    &mut tile_index,
+                    key.clone(),
+                    IndexedTile {
+                        source,
+                        date: date.to_string(),
+                    },
+                );
             }
         }
 
+        let nested_zips = NestedZipCache::new(NESTED_ZIP_CACHE_BYTES);
+        for (filename, source) in sources {
+            let Some((key, date)) = TileKey::from_filename(&filename).or_else(|| {
+                let xml = source.read_to_string(&nested_zips).ok()?;
+                TileKey::from_metadata(&xml).map(|key| (key, String::new()))
+            }) else {
+                tracing::debug!("Skipping {}: not a recognizable DEM tile", source);
+                continue;
+            };
+
+            insert_newest(&mut tile_index, key, IndexedTile { source, date });
+        }
+
+        let mut products: Vec<DemProduct> = tile_index.keys().map(|k| k.product).collect();
+        products.sort();
+        products.dedup();
+
+        for product in &products {
+            let count = tile_index.keys().filter(|k| k.product == *product).count();
+            tracing::info!("Indexed {} {} tiles", count, product);
+        }
+
         tracing::info!(
-            "Initialized ElevationProvider: {} mesh codes indexed",
-            mesh_to_file.len()
+            "Initialized ElevationProvider: {} tiles indexed",
+            tile_index.len()
         );
 
         Ok(Self {
-            cache: HashMap::new(),
-            mesh_to_file,
+            cache: SharedTileCache::new(DEFAULT_CACHE_CAPACITY_BYTES),
+            tile_index,
+            nested_zips,
+            compiled,
+            products,
         })
     }
 
-    fn extract_mesh_code(filename: &str) -> Option<String> {
-        if let Some(start) = filename.find("FG-GML-") {
-            let after_prefix = &filename[start + 7..];
-            let parts: Vec<&str> = after_prefix.split('-').collect();
-            if parts.len() >= 3 {
-                return Some(format!("{}-{}-{}", parts[0], parts[1], parts[2]));
+    /// Sets the memory budget for parsed tiles, replacing the default of 256 MiB
+    pub fn with_cache_capacity(self, capacity_bytes: usize) -> Self {
+        Self {
+            cache: SharedTileCache::new(capacity_bytes),
+            ..self
+        }
+    }
+
+    /// Returns the mesh code used to group coordinates that are served by the same tiles
+    ///
+    /// Coordinates sharing a group key are best processed together so that each tile
+    /// is parsed once while it is hot in the cache.
+    pub fn group_key(&self, lat: f64, lon: f64) -> String {
+        calculate_mesh_code(lat, lon, MeshLevel::Third)
+    }
+
+    fn index_zip_file(path: &Path, sources: &mut Vec<(String, TileSource)>) -> Result<()> {
+        let file = File::open(path)?;
+        let mut zip = ZipArchive::new(file)?;
+        Self::index_zip_archive(&mut zip, path, &[], sources)
+    }
+
+    /// Collects XML members of an archive, descending into nested zip members
+    fn index_zip_archive<R: Read + Seek>(
+        zip: &mut ZipArchive<R>,
+        archive: &Path,
+        parents: &[String],
+        sources: &mut Vec<(String, TileSource)>,
+    ) -> Result<()> {
+        for i in 0..zip.len() {
+            let name = {
+                let entry = zip.by_index(i)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                entry.name().to_string()
+            };
+
+            // アーカイブ内のディレクトリ構造は無視してファイル名で判定する
+            let filename = name.rsplit('/').next().unwrap_or(&name).to_string();
+
+            let mut members = parents.to_vec();
+            members.push(name.clone());
+
+            if has_extension(&filename, "xml") {
+                sources.push((
+                    filename,
+                    TileSource::ZipMember {
+                        archive: archive.to_path_buf(),
+                        members,
+                    },
+                ));
+            } else if has_extension(&filename, "zip") {
+                let data = read_member(zip, &name)?;
+                match ZipArchive::new(Cursor::new(data)) {
+                    Ok(mut inner) => {
+                        Self::index_zip_archive(&mut inner, archive, &members, sources)?
+                    }
+                    Err(e) => tracing::warn!(
+                        "Failed to read nested zip {}!{}: {}",
+                        archive.display(),
+                        name,
+                        e
+                    ),
+                }
             }
         }
-        None
+
+        Ok(())
     }
 
     /// Gets elevation at a specific coordinate
@@ -151,36 +642,199 @@ impl ElevationProvider {
     /// * `Ok(Some(elevation))` - Elevation in meters
     /// * `Ok(None)` - Valid coordinate but no data available (XML parse errors are logged and skipped)
     /// * `Err(...)` - File read error
-    pub fn get_elevation(&mut self, lat: f64, lon: f64) -> Result<Option<f64>> {
-        let mesh_code = calculate_mesh_code(lat, lon);
+    pub fn get_elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>> {
+        Ok(self
+            .get_elevation_with_product(lat, lon)?
+            .map(|(elevation, _)| elevation))
+    }
+
+    /// Gets elevation at a specific coordinate together with the product that supplied it
+    pub fn get_elevation_with_product(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<Option<(f64, DemProduct)>> {
+        Ok(self
+            .sample_with_product(lat, lon)?
+            .map(|(elevation, product, _)| (elevation, product)))
+    }
 
-        if let Some(tile) = self.cache.get(&mesh_code) {
-            // -9999（データ欠損を示す特殊値）をNULLとして扱う
-            return Ok(tile.get_elevation(lat, lon).filter(|&e| e != -9999.0));
+    /// Gets elevation at a specific coordinate with the product and quality of the value
+    ///
+    /// Products are tried in order of precedence (DEM5A, DEM5B, DEM5C, DEM10A, DEM10B), each at
+    /// its own mesh level. A product whose tile is missing or has no data (-9999) at the
+    /// coordinate falls through to the next one. Only when no product has data at the
+    /// coordinate is the value filled from valid cells within [`FILL_RADIUS_CELLS`].
+    ///
+    /// Surrounding cells are looked up by coordinate, so near a tile edge they are read from
+    /// the neighbouring tile.
+    pub fn sample_with_product(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<Option<(f64, DemProduct, ElevationQuality)>> {
+        for &product in &self.products {
+            if let Some((elevation, quality)) = self.interpolate(product, lat, lon) {
+                return Ok(Some((elevation, product, quality)));
+            }
         }
 
-        if let Some(xml_path) = self.mesh_to_file.get(&mesh_code) {
-            match Self::parse_xml_file(xml_path) {
-                Ok(tile) => {
-                    let elevation = tile.get_elevation(lat, lon);
-                    self.cache.insert(mesh_code, tile);
-                    // -9999（データ欠損を示す特殊値）をNULLとして扱う
-                    return Ok(elevation.filter(|&e| e != -9999.0));
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to parse XML {:?}: {}", xml_path, e);
-                    return Ok(None);
-                }
+        for &product in &self.products {
+            if let Some(elevation) = self.fill(product, lat, lon) {
+                return Ok(Some((elevation, product, ElevationQuality::Filled)));
             }
         }
 
         Ok(None)
     }
 
-    /// Parses a GSI JPGIS XML file and extracts elevation data
-    fn parse_xml_file(xml_path: &PathBuf) -> Result<GsiTile> {
-        let xml_content = std::fs::read_to_string(xml_path)
-            .context(format!("Failed to read XML file: {:?}", xml_path))?;
+    /// Returns true if a tile of any product contains the coordinate
+    pub fn has_tile(&self, lat: f64, lon: f64) -> bool {
+        self.products
+            .iter()
+            .any(|&product| self.containing_tile(product, lat, lon).is_some())
+    }
+
+    /// Returns the tile of a product that contains the coordinate
+    ///
+    /// The mesh code of a point on a seam can round to either side, and tile envelopes are
+    /// written with limited precision, so the meshes just across the seam are tried as well.
+    fn containing_tile(&self, product: DemProduct, lat: f64, lon: f64) -> Option<Arc<GsiTile>> {
+        const SEAM_OFFSETS: [(f64, f64); 9] = [
+            (0.0, 0.0),
+            (SEAM_EPSILON_DEG, 0.0),
+            (-SEAM_EPSILON_DEG, 0.0),
+            (0.0, SEAM_EPSILON_DEG),
+            (0.0, -SEAM_EPSILON_DEG),
+            (SEAM_EPSILON_DEG, SEAM_EPSILON_DEG),
+            (SEAM_EPSILON_DEG, -SEAM_EPSILON_DEG),
+            (-SEAM_EPSILON_DEG, SEAM_EPSILON_DEG),
+            (-SEAM_EPSILON_DEG, -SEAM_EPSILON_DEG),
+        ];
+
+        let mut tried: Vec<String> = Vec::new();
+        for (dlat, dlon) in SEAM_OFFSETS {
+            let mesh_code = calculate_mesh_code(lat + dlat, lon + dlon, product.mesh_level());
+            if tried.contains(&mesh_code) {
+                continue;
+            }
+
+            let key = TileKey {
+                product,
+                mesh_code: mesh_code.clone(),
+            };
+            if let Some(tile) = self.load_tile(&key).filter(|tile| tile.contains(lat, lon)) {
+                return Some(tile);
+            }
+            tried.push(mesh_code);
+        }
+
+        None
+    }
+
+    /// Returns the valid value of the cell containing the coordinate, in whichever tile
+    fn cell_value(&self, product: DemProduct, lat: f64, lon: f64) -> Option<f64> {
+        // -9999（データ欠損を示す特殊値）をNULLとして扱う
+        self.containing_tile(product, lat, lon)?
+            .get_elevation(lat, lon)
+            .filter(|&e| e != NODATA_VALUE)
+    }
+
+    /// Bilinear interpolation between the centers of the four cells around the coordinate
+    ///
+    /// Falls back to the value of the containing cell when a surrounding cell has no data.
+    /// Returns None if the containing cell itself has no data.
+    fn interpolate(
+        &self,
+        product: DemProduct,
+        lat: f64,
+        lon: f64,
+    ) -> Option<(f64, ElevationQuality)> {
+        let tile = self.containing_tile(product, lat, lon)?;
+        let nearest = tile
+            .get_elevation(lat, lon)
+            .filter(|&e| e != NODATA_VALUE)?;
+
+        let (x, y) = tile.cell_position(lat, lon);
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let corner = |dx: i64, dy: i64| {
+            let (lat, lon) = tile.cell_center(x0 + dx, y0 + dy);
+            self.cell_value(product, lat, lon)
+        };
+
+        let (Some(sw), Some(se), Some(nw), Some(ne)) =
+            (corner(0, 0), corner(1, 0), corner(0, 1), corner(1, 1))
+        else {
+            return Some((nearest, ElevationQuality::Nearest));
+        };
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let south = lerp(sw, se, tx);
+        let north = lerp(nw, ne, tx);
+
+        Some((lerp(south, north, ty), ElevationQuality::Bilinear))
+    }
+
+    /// Mean of the valid cells in the nearest ring (up to [`FILL_RADIUS_CELLS`]) around the
+    /// coordinate that has any
+    fn fill(&self, product: DemProduct, lat: f64, lon: f64) -> Option<f64> {
+        let tile = self.containing_tile(product, lat, lon)?;
+        let (x, y) = tile.cell_position(lat, lon);
+        let (column, row) = (x.floor() as i64, y.floor() as i64);
+
+        for radius in 1..=FILL_RADIUS_CELLS {
+            let values: Vec<f64> = (-radius..=radius)
+                .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+                .filter(|&(dx, dy)| dx.abs().max(dy.abs()) == radius)
+                .filter_map(|(dx, dy)| {
+                    let (lat, lon) = tile.cell_center(column + dx, row + dy);
+                    self.cell_value(product, lat, lon)
+                })
+                .collect();
+
+            if !values.is_empty() {
+                return Some(values.iter().sum::<f64>() / values.len() as f64);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the tile for the given key, parsing and caching it on first access
+    fn load_tile(&self, key: &TileKey) -> Option<Arc<GsiTile>> {
+        // インデックスにないタイルはキャッシュのミスとして数えない
+        let indexed = self.tile_index.get(key)?;
+
+        // 同じタイルを複数のスレッドが同時に要求しても、パースは1回だけ行う
+        self.cache
+            .get_or_load(key, || match self.read_tile(&indexed.source) {
+                Ok(tile) => Some(tile),
+                Err(e) => {
+                    tracing::warn!("Failed to read tile {}: {}", indexed.source, e);
+                    None
+                }
+            })
+    }
+
+    /// Reads a tile from an XML document or a compiled file
+    pub(super) fn read_tile(&self, source: &TileSource) -> Result<GsiTile> {
+        match source {
+            TileSource::Compiled { path, entry } => self
+                .compiled
+                .get(path)
+                .context(format!("Compiled DEM not loaded: {:?}", path))?
+                .read_tile(*entry),
+            _ => Self::parse_xml_source(source, &self.nested_zips),
+        }
+    }
+
+    /// Parses a GSI JPGIS XML document and extracts elevation data
+    fn parse_xml_source(source: &TileSource, nested_zips: &NestedZipCache) -> Result<GsiTile> {
+        let xml_content = source.read_to_string(nested_zips)?;
 
         let doc = Document::parse(&xml_content).context("Failed to parse XML")?;
 
@@ -264,8 +918,8 @@ impl ElevationProvider {
         // Allow partial data for boundary tiles (海や国境でデータが欠損している場合)
         if elevations.len() != grid_width * grid_height {
             tracing::debug!(
-                "Partial elevation data in {:?}: expected {}, got {} (boundary tile)",
-                xml_path,
+                "Partial elevation data in {}: expected {}, got {} (boundary tile)",
+                source,
                 grid_width * grid_height,
                 elevations.len()
             );
@@ -280,11 +934,9 @@ impl ElevationProvider {
         })
     }
 
-    /// Returns statistics about cache usage
-    pub fn cache_stats(&self) -> (usize, usize) {
-        let loaded_files = self.cache.len();
-        let capacity = self.cache.capacity();
-        (loaded_files, capacity)
+    /// Returns statistics about cache usage (hits, misses, evictions and memory)
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
     }
 }
 
@@ -321,9 +973,9 @@ mod tests {
     #[test]
     fn test_new_provider() {
         let provider = ElevationProvider::new("tests/fixtures/gsi").unwrap();
-        assert_eq!(provider.cache.len(), 0);
+        assert_eq!(provider.cache_stats().entries, 0);
         assert!(
-            !provider.mesh_to_file.is_empty(),
+            !provider.tile_index.is_empty(),
             "Should find at least one XML file in fixtures"
         );
     }
@@ -331,7 +983,7 @@ mod tests {
     #[test]
     fn test_fixture_data() {
         // Deterministic test using fixture (always runs in CI)
-        let mut provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
+        let provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
 
         // Test coordinates within fixture bounds (35.0-35.01, 138.0-138.01)
         let result = provider.get_elevation(35.005, 138.005);
@@ -357,7 +1009,7 @@ mod tests {
             return;
         };
 
-        let mut provider = ElevationProvider::new(&data_dir).unwrap();
+        let provider = ElevationProvider::new(&data_dir).unwrap();
         let result = provider.get_elevation(TEST_LAT_FUJI, TEST_LON_FUJI);
 
         assert!(result.is_ok());
@@ -379,7 +1031,7 @@ mod tests {
             return;
         };
 
-        let mut provider = ElevationProvider::new(&data_dir).unwrap();
+        let provider = ElevationProvider::new(&data_dir).unwrap();
         let result = provider.get_elevation(TEST_LAT_TOKYO, TEST_LON_TOKYO);
 
         assert!(result.is_ok());
@@ -400,27 +1052,498 @@ mod tests {
         assert!(result.is_err(), "Should error when no XML files found");
     }
 
+    /// Fixture XML file used to build zip archives in tests
+    const FIXTURE_XML_NAME: &str = "FG-GML-5238-40-00-DEM5B-20210115.xml";
+
+    fn fixture_xml() -> Vec<u8> {
+        std::fs::read(format!("{}/xml/{}", get_fixture_dir(), FIXTURE_XML_NAME)).unwrap()
+    }
+
+    fn dem5b_key() -> TileKey {
+        TileKey {
+            product: DemProduct::Dem5B,
+            mesh_code: "5238-40-00".to_string(),
+        }
+    }
+
+    /// Fixture XML with every elevation value replaced by `value`
+    fn fixture_xml_with_elevation(value: &str) -> String {
+        let xml = String::from_utf8(fixture_xml()).unwrap();
+        xml.lines()
+            .map(|line| {
+                if line.starts_with("地表面,") {
+                    format!("地表面,{}", value)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_recursive_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested_dir = dir.path().join("a/b/c");
+        std::fs::create_dir_all(&nested_dir).unwrap();
+        std::fs::write(nested_dir.join(FIXTURE_XML_NAME), fixture_xml()).unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(provider.tile_index.len(), 1);
+        assert!(matches!(
+            provider.get_elevation(35.005, 138.005),
+            Ok(Some(_))
+        ));
+    }
+
+    #[test]
+    fn test_zip_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let xml = fixture_xml();
+        let archive = build_zip(&[(&format!("dem/{}", FIXTURE_XML_NAME), &xml)]);
+        std::fs::write(dir.path().join("FG-GML-5238-40-DEM5B.zip"), archive).unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        assert!(matches!(
+            provider.tile_index.get(&dem5b_key()).map(|t| &t.source),
+            Some(TileSource::ZipMember { members, .. }) if members.len() == 1
+        ));
+        let elevation = provider.get_elevation(35.005, 138.005).unwrap();
+        assert!(elevation.is_some(), "Should read elevation from zip member");
+    }
+
+    #[test]
+    fn test_nested_zip_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let xml = fixture_xml();
+        let inner = build_zip(&[(FIXTURE_XML_NAME, &xml)]);
+        let outer = build_zip(&[("PackDLMap/FG-GML-5238-40-DEM5B.zip", &inner)]);
+        std::fs::write(dir.path().join("PackDLMap.zip"), outer).unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        assert!(matches!(
+            provider.tile_index.get(&dem5b_key()).map(|t| &t.source),
+            Some(TileSource::ZipMember { members, .. }) if members.len() == 2
+        ));
+        let elevation = provider.get_elevation(35.005, 138.005).unwrap();
+        assert!(
+            elevation.is_some(),
+            "Should read elevation from nested zip member"
+        );
+
+        // 同じ入れ子のzipのタイルは、外側のアーカイブから解凍し直さずに読む
+        let source = &provider.tile_index[&dem5b_key()].source;
+        source.read_to_string(&provider.nested_zips).unwrap();
+        let stats = provider.nested_zips.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[test]
+    fn test_calculate_mesh_code_levels() {
+        assert_eq!(
+            calculate_mesh_code(35.005, 138.005, MeshLevel::Third),
+            "5238-40-00"
+        );
+        assert_eq!(
+            calculate_mesh_code(35.005, 138.005, MeshLevel::Second),
+            "5238-40"
+        );
+    }
+
+    #[test]
+    fn test_tile_key_from_filename() {
+        let (key, date) = TileKey::from_filename("FG-GML-5238-40-00-DEM5A-20161001.xml").unwrap();
+        assert_eq!(key.product, DemProduct::Dem5A);
+        assert_eq!(key.mesh_code, "5238-40-00");
+        assert_eq!(date, "20161001");
+
+        let (key, _) = TileKey::from_filename("FG-GML-5238-40-DEM10B-20161001.xml").unwrap();
+        assert_eq!(key.product, DemProduct::Dem10B);
+        assert_eq!(key.mesh_code, "5238-40");
+
+        // 10mメッシュに3次メッシュコードが付いている等、製品とメッシュレベルが一致しない
+        assert!(TileKey::from_filename("FG-GML-5238-40-00-DEM10B-20161001.xml").is_none());
+        assert!(TileKey::from_filename("dem.xml").is_none());
+    }
+
+    #[test]
+    fn test_tile_key_from_metadata() {
+        let xml = "<DEM><type>10mメッシュ（標高）</type><mesh>523840</mesh></DEM>";
+        let key = TileKey::from_metadata(xml).unwrap();
+        assert_eq!(key.product, DemProduct::Dem10B);
+        assert_eq!(key.mesh_code, "5238-40");
+
+        let xml = "<DEM><type>5mメッシュ（標高）</type><mesh>52384000</mesh></DEM>";
+        let key = TileKey::from_metadata(xml).unwrap();
+        assert_eq!(key.product, DemProduct::Dem5B);
+        assert_eq!(key.mesh_code, "5238-40-00");
+
+        // The fixture uses a placeholder mesh value
+        let fixture = String::from_utf8(fixture_xml()).unwrap();
+        assert!(TileKey::from_metadata(&fixture).is_none());
+    }
+
+    #[test]
+    fn test_dem10b_indexed_at_second_level_mesh() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-DEM10B-20161001.xml"),
+            fixture_xml(),
+        )
+        .unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let result = provider
+            .get_elevation_with_product(35.005, 138.005)
+            .unwrap();
+
+        assert!(matches!(result, Some((_, DemProduct::Dem10B))));
+    }
+
+    #[test]
+    fn test_product_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-DEM10B-20161001.xml"),
+            fixture_xml_with_elevation("10.0"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5B-20161001.xml"),
+            fixture_xml_with_elevation("5.0"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            fixture_xml_with_elevation("1.0"),
+        )
+        .unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let result = provider
+            .get_elevation_with_product(35.005, 138.005)
+            .unwrap();
+
+        assert_eq!(result, Some((1.0, DemProduct::Dem5A)));
+    }
+
+    #[test]
+    fn test_product_fallback_on_nodata() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-DEM10B-20161001.xml"),
+            fixture_xml_with_elevation("10.0"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            fixture_xml_with_elevation("-9999."),
+        )
+        .unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let result = provider
+            .get_elevation_with_product(35.005, 138.005)
+            .unwrap();
+
+        assert_eq!(result, Some((10.0, DemProduct::Dem10B)));
+    }
+
+    #[test]
+    fn test_newest_file_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            fixture_xml_with_elevation("1.0"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20230101.xml"),
+            fixture_xml_with_elevation("2.0"),
+        )
+        .unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(provider.get_elevation(35.005, 138.005).unwrap(), Some(2.0));
+    }
+
     #[test]
     fn test_caching_behavior() {
         // Deterministic test using fixture (always runs in CI)
-        let mut provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
+        let provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
 
         // First query - should parse XML and cache
         let _ = provider.get_elevation(35.005, 138.005);
-        let (files_1, _) = provider.cache_stats();
+        let stats_1 = provider.cache_stats();
 
         // Second query - should use cache, no additional files
         let _ = provider.get_elevation(35.005, 138.005);
-        let (files_2, _) = provider.cache_stats();
+        let stats_2 = provider.cache_stats();
+
+        assert_eq!(
+            stats_1.entries, stats_2.entries,
+            "Cache size should not increase"
+        );
+        assert!(stats_1.entries > 0, "At least one file should be cached");
+        // 補間で周囲のセルも参照するため、1回の問い合わせで複数回キャッシュを引く
+        assert_eq!(stats_1.misses, 1);
+        assert_eq!(stats_2.misses, 1, "Tile should be parsed only once");
+        assert!(stats_2.hits > stats_1.hits);
+    }
+
+    #[test]
+    fn test_cache_capacity_bound() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            fixture_xml(),
+        )
+        .unwrap();
+        // 同じ内容を隣の3次メッシュとしても配置する
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-01-DEM5A-20161001.xml"),
+            fixture_xml(),
+        )
+        .unwrap();
+
+        // 1タイル分しか保持できない予算
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap())
+            .unwrap()
+            .with_cache_capacity(1);
+
+        let first = TileKey {
+            product: DemProduct::Dem5A,
+            mesh_code: "5238-40-00".to_string(),
+        };
+        let second = TileKey {
+            product: DemProduct::Dem5A,
+            mesh_code: "5238-40-01".to_string(),
+        };
+        assert!(provider.load_tile(&first).is_some());
+        assert!(provider.load_tile(&second).is_some());
+
+        let stats = provider.cache_stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.evictions, 1);
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let elevation = provider.get_elevation(35.005, 138.005).unwrap();
+                    assert!(elevation.is_some());
+                });
+            }
+        });
 
-        assert_eq!(files_1, files_2, "Cache size should not increase");
-        assert!(files_1 > 0, "At least one file should be cached");
+        assert_eq!(provider.cache_stats().entries, 1);
+    }
+
+    // 3次メッシュ 5238-40-00 の範囲（緯度 1/120°、経度 1/80°）
+    const MESH_LOWER: (f64, f64) = (35.0, 138.0);
+    const MESH_SIZE: (f64, f64) = (1.0 / 120.0, 1.0 / 80.0);
+
+    /// 5x5 grid XML for a 3次メッシュ; `value(column, row_from_south)` gives each cell
+    fn grid_xml(lower: (f64, f64), value: impl Fn(usize, usize) -> f64) -> String {
+        let xml = String::from_utf8(fixture_xml()).unwrap();
+        let upper = (lower.0 + MESH_SIZE.0, lower.1 + MESH_SIZE.1);
+
+        let tuples: Vec<String> = (0..5)
+            .rev()
+            .flat_map(|row| (0..5).map(move |column| (column, row)))
+            .map(|(column, row)| format!("地表面,{}", value(column, row)))
+            .collect();
+
+        let start = xml.find("<gml:tupleList>").unwrap() + "<gml:tupleList>".len();
+        let end = xml.find("</gml:tupleList>").unwrap();
+
+        format!("{}\n{}\n{}", &xml[..start], tuples.join("\n"), &xml[end..])
+            .replace(
+                "<gml:lowerCorner>35.0 138.0</gml:lowerCorner>",
+                &format!("<gml:lowerCorner>{} {}</gml:lowerCorner>", lower.0, lower.1),
+            )
+            .replace(
+                "<gml:upperCorner>35.01 138.01</gml:upperCorner>",
+                &format!("<gml:upperCorner>{} {}</gml:upperCorner>", upper.0, upper.1),
+            )
+    }
+
+    /// Coordinate of the center of a cell of the 5x5 grid at `lower`
+    fn cell_center(lower: (f64, f64), column: f64, row: f64) -> (f64, f64) {
+        (
+            lower.0 + (row + 0.5) * MESH_SIZE.0 / 5.0,
+            lower.1 + (column + 0.5) * MESH_SIZE.1 / 5.0,
+        )
+    }
+
+    #[test]
+    fn test_tile_bounds_are_half_open() {
+        let xml = grid_xml(MESH_LOWER, |_, _| 1.0);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"), xml).unwrap();
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let tile = provider
+            .load_tile(&TileKey {
+                product: DemProduct::Dem5A,
+                mesh_code: "5238-40-00".to_string(),
+            })
+            .unwrap();
+
+        let upper = (MESH_LOWER.0 + MESH_SIZE.0, MESH_LOWER.1 + MESH_SIZE.1);
+        assert!(tile.contains(MESH_LOWER.0, MESH_LOWER.1));
+        assert!(!tile.contains(upper.0, MESH_LOWER.1));
+        assert!(!tile.contains(MESH_LOWER.0, upper.1));
+    }
+
+    #[test]
+    fn test_lookup_across_tile_seam() {
+        let east_lower = (MESH_LOWER.0, MESH_LOWER.1 + MESH_SIZE.1);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            grid_xml(MESH_LOWER, |_, _| 10.0),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-01-DEM5A-20161001.xml"),
+            grid_xml(east_lower, |_, _| 20.0),
+        )
+        .unwrap();
+
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        // 境界線上の点は東側のタイルに属し、西側タイルのセルとの間で補間される
+        let (lat, _) = cell_center(MESH_LOWER, 0.0, 2.0);
+        let (elevation, product, quality) = provider
+            .sample_with_product(lat, east_lower.1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(product, DemProduct::Dem5A);
+        assert_eq!(quality, ElevationQuality::Bilinear);
+        assert!((elevation - 15.0).abs() < 1e-6, "got {}", elevation);
+    }
+
+    #[test]
+    fn test_bilinear_interpolation() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            grid_xml(MESH_LOWER, |column, row| (column * 10 + row) as f64),
+        )
+        .unwrap();
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        // セル中心では値そのもの、セル中心間では線形に補間される
+        let (lat, lon) = cell_center(MESH_LOWER, 2.0, 2.0);
+        let (elevation, _, quality) = provider.sample_with_product(lat, lon).unwrap().unwrap();
+        assert_eq!(quality, ElevationQuality::Bilinear);
+        assert!((elevation - 22.0).abs() < 1e-6, "got {}", elevation);
+
+        let (lat, lon) = cell_center(MESH_LOWER, 2.5, 1.25);
+        let (elevation, _, _) = provider.sample_with_product(lat, lon).unwrap().unwrap();
+        assert!((elevation - 26.25).abs() < 1e-6, "got {}", elevation);
+    }
+
+    #[test]
+    fn test_nearest_when_neighbor_cell_has_no_data() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            grid_xml(MESH_LOWER, |column, row| {
+                if (column, row) == (3, 3) {
+                    -9999.0
+                } else {
+                    5.0
+                }
+            }),
+        )
+        .unwrap();
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        let (lat, lon) = cell_center(MESH_LOWER, 2.25, 2.25);
+        let (elevation, _, quality) = provider.sample_with_product(lat, lon).unwrap().unwrap();
+        assert_eq!(quality, ElevationQuality::Nearest);
+        assert_eq!(elevation, 5.0);
+    }
+
+    #[test]
+    fn test_fill_nodata_from_adjacent_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            grid_xml(MESH_LOWER, |column, row| match (column, row) {
+                (2, 2) => -9999.0,
+                (1, 2) => 4.0,
+                (3, 2) => 6.0,
+                (c, r) if (1..=3).contains(&c) && (1..=3).contains(&r) => -9999.0,
+                _ => 100.0,
+            }),
+        )
+        .unwrap();
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        let (lat, lon) = cell_center(MESH_LOWER, 2.0, 2.0);
+        let (elevation, _, quality) = provider.sample_with_product(lat, lon).unwrap().unwrap();
+        assert_eq!(quality, ElevationQuality::Filled);
+        assert_eq!(elevation, 5.0);
+    }
+
+    #[test]
+    fn test_fill_prefers_lower_precedence_data_over_filling() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5A-20161001.xml"),
+            grid_xml(MESH_LOWER, |column, row| {
+                if (column, row) == (2, 2) {
+                    -9999.0
+                } else {
+                    1.0
+                }
+            }),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("FG-GML-5238-40-00-DEM5B-20161001.xml"),
+            grid_xml(MESH_LOWER, |_, _| 2.0),
+        )
+        .unwrap();
+        let provider = ElevationProvider::new(dir.path().to_str().unwrap()).unwrap();
+
+        let (lat, lon) = cell_center(MESH_LOWER, 2.0, 2.0);
+        let (elevation, product, quality) =
+            provider.sample_with_product(lat, lon).unwrap().unwrap();
+        assert_eq!(product, DemProduct::Dem5B);
+        assert_eq!(quality, ElevationQuality::Bilinear);
+        assert_eq!(elevation, 2.0);
     }
 
     #[test]
     fn test_filter_invalid_elevation_value() {
         // Test that -9999 (data absence marker) is filtered to None
-        let mut provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
+        let provider = ElevationProvider::new(&get_fixture_dir()).unwrap();
 
         // Get elevation from fixture
         let result = provider.get_elevation(35.005, 138.005);
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::elevation::ElevationProvider;
+use super::png_tile::PngTileProvider;
+use super::tile_cache::CacheStats;
+use crate::domain::ElevationQuality;
+
+/// On-disk format of elevation data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElevationFormat {
+    /// GSI 基盤地図情報 JPGIS XML (plain files or zip archives)
+    #[default]
+    Jpgis,
+    /// GSI 標高タイル in a `{z}/{x}/{y}.png` directory
+    #[serde(rename = "png")]
+    PngTiles,
+}
+
+/// An elevation value and where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationSample {
+    /// Elevation in meters
+    pub elevation: f64,
+    /// Data that supplied the value (e.g. "DEM5A")
+    pub source: String,
+    pub quality: ElevationQuality,
+}
+
+/// A source of elevation data used to enrich junctions
+///
+/// Lookups take `&self`, so one source can be shared across worker threads.
+pub trait ElevationSource: Send + Sync {
+    /// Gets elevation at a coordinate together with its source and quality
+    ///
+    /// # Returns
+    /// * `Ok(Some(sample))` - Elevation with the data that supplied it
+    /// * `Ok(None)` - No data available at the coordinate
+    /// * `Err(...)` - Read error
+    fn sample(&self, lat: f64, lon: f64) -> Result<Option<ElevationSample>>;
+
+    /// Gets elevation at a coordinate
+    fn get_elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>> {
+        Ok(self.sample(lat, lon)?.map(|sample| sample.elevation))
+    }
+
+    /// Returns true if any tile covers the coordinate, whether or not it has data there
+    ///
+    /// Used to tell junctions outside the data coverage apart from nodata cells.
+    fn has_tile(&self, lat: f64, lon: f64) -> bool;
+
+    /// Returns a key grouping coordinates that are served by the same tiles
+    fn group_key(&self, lat: f64, lon: f64) -> String;
+
+    /// Returns statistics about tile cache usage
+    fn cache_stats(&self) -> CacheStats;
+}
+
+impl ElevationSource for ElevationProvider {
+    fn sample(&self, lat: f64, lon: f64) -> Result<Option<ElevationSample>> {
+        Ok(self
+            .sample_with_product(lat, lon)?
+            .map(|(elevation, product, quality)| ElevationSample {
+                elevation,
+                source: product.as_str().to_string(),
+                quality,
+            }))
+    }
+
+    fn has_tile(&self, lat: f64, lon: f64) -> bool {
+        ElevationProvider::has_tile(self, lat, lon)
+    }
+
+    fn group_key(&self, lat: f64, lon: f64) -> String {
+        ElevationProvider::group_key(self, lat, lon)
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        ElevationProvider::cache_stats(self)
+    }
+}
+
+/// Opens the elevation data in `data_dir` with the given tile cache budget
+pub fn open(
+    data_dir: &str,
+    format: ElevationFormat,
+    cache_capacity_bytes: usize,
+) -> Result<Box<dyn ElevationSource>> {
+    Ok(match format {
+        ElevationFormat::Jpgis => {
+            Box::new(ElevationProvider::new(data_dir)?.with_cache_capacity(cache_capacity_bytes))
+        }
+        ElevationFormat::PngTiles => {
+            Box::new(PngTileProvider::new(data_dir)?.with_cache_capacity(cache_capacity_bytes))
+        }
+    })
+}
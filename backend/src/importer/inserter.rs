@@ -1,7 +1,9 @@
 use anyhow::Result;
 use sqlx::{PgPool, Postgres, Transaction};
 
+use super::boundary::{multipolygon_wkt, AdminArea};
 use super::detector::JunctionForInsert;
+use crate::domain::AdminLevel;
 
 const BATCH_SIZE: usize = 1000;
 
@@ -52,12 +54,15 @@ async fn insert_batch(
          elevation, neighbor_elevation_1, neighbor_elevation_2, neighbor_elevation_3, \
          elevation_diff_1, elevation_diff_2, elevation_diff_3, \
          min_angle_index, min_elevation_diff, max_elevation_diff, \
-         way_1_bridge, way_1_tunnel, way_2_bridge, way_2_tunnel, way_3_bridge, way_3_tunnel) VALUES ",
+         way_1_bridge, way_1_tunnel, way_2_bridge, way_2_tunnel, way_3_bridge, way_3_tunnel, \
+         arm_geometry_1, arm_geometry_2, arm_geometry_3, highway_class, \
+         way_1_name, way_2_name, way_3_name, road_search_text) VALUES ",
     );
 
-    const PARAMS_PER_ROW: usize = 25; // osm_node_id, lon, lat, angle_1, angle_2, angle_3, bearing_1, bearing_2, bearing_3,
+    const PARAMS_PER_ROW: usize = 33; // osm_node_id, lon, lat, angle_1, angle_2, angle_3, bearing_1, bearing_2, bearing_3,
                                       // elevation, neighbor_elevation_1~3, elevation_diff_1~3, min_angle_index, min/max_elevation_diff,
-                                      // way_1_bridge, way_1_tunnel, way_2_bridge, way_2_tunnel, way_3_bridge, way_3_tunnel
+                                      // way_1_bridge, way_1_tunnel, way_2_bridge, way_2_tunnel, way_3_bridge, way_3_tunnel,
+                                      // arm_geometry_1~3, highway_class, way_1_name~way_3_name, road_search_text
 
     for (i, _) in junctions.iter().enumerate() {
         if i > 0 {
@@ -66,7 +71,8 @@ async fn insert_batch(
         let base = i * PARAMS_PER_ROW + 1;
         query.push_str(&format!(
             "(${}, ST_SetSRID(ST_MakePoint(${}, ${}), 4326)::geography, ${}, ${}, ${}, ARRAY[${}, ${}, ${}], \
-             ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
+             ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, \
+             ST_GeogFromText(${}), ST_GeogFromText(${}), ST_GeogFromText(${}), ${}, ${}, ${}, ${}, ${})",
             base,        // osm_node_id
             base + 1,    // lon
             base + 2,    // lat
@@ -91,7 +97,15 @@ async fn insert_batch(
             base + 21,   // way_2_bridge
             base + 22,   // way_2_tunnel
             base + 23,   // way_3_bridge
-            base + 24    // way_3_tunnel
+            base + 24,   // way_3_tunnel
+            base + 25,   // arm_geometry_1
+            base + 26,   // arm_geometry_2
+            base + 27,   // arm_geometry_3
+            base + 28,   // highway_class
+            base + 29,   // way_1_name
+            base + 30,   // way_2_name
+            base + 31,   // way_3_name
+            base + 32    // road_search_text
         ));
     }
 
@@ -125,10 +139,113 @@ async fn insert_batch(
             .bind(junction.way_2_bridge)
             .bind(junction.way_2_tunnel)
             .bind(junction.way_3_bridge)
-            .bind(junction.way_3_tunnel);
+            .bind(junction.way_3_tunnel)
+            .bind(linestring_wkt(&junction.arm_geometries[0]))
+            .bind(linestring_wkt(&junction.arm_geometries[1]))
+            .bind(linestring_wkt(&junction.arm_geometries[2]))
+            .bind(junction.highway_class.as_deref())
+            .bind(junction.road_names[0].as_deref())
+            .bind(junction.road_names[1].as_deref())
+            .bind(junction.road_names[2].as_deref())
+            .bind(junction.road_search_text.as_deref());
     }
 
     q.execute(&mut **tx).await?;
 
     Ok(())
 }
+
+/// Format a (lat, lon) path as WKT `LINESTRING(lon lat, ...)`
+/// Returns None if the path has fewer than 2 points
+fn linestring_wkt(path: &[(f64, f64)]) -> Option<String> {
+    if path.len() < 2 {
+        return None;
+    }
+
+    let coords: Vec<String> = path
+        .iter()
+        .map(|(lat, lon)| format!("{} {}", lon, lat))
+        .collect();
+
+    Some(format!("LINESTRING({})", coords.join(", ")))
+}
+
+/// Insert or update administrative areas
+///
+/// Boundaries are repaired with ST_MakeValid, since assembled rings may self-intersect.
+pub async fn insert_admin_areas(pool: &PgPool, areas: &[AdminArea]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    for area in areas {
+        sqlx::query(
+            "INSERT INTO admin_areas (relation_id, code, name, name_en, admin_level, geometry) \
+             VALUES ($1, $2, $3, $4, $5, \
+               ST_Multi(ST_CollectionExtract(ST_MakeValid(ST_GeomFromText($6, 4326)), 3))::geography) \
+             ON CONFLICT (relation_id) DO UPDATE SET \
+               code = EXCLUDED.code, name = EXCLUDED.name, name_en = EXCLUDED.name_en, \
+               admin_level = EXCLUDED.admin_level, geometry = EXCLUDED.geometry",
+        )
+        .bind(area.relation_id)
+        .bind(&area.code)
+        .bind(&area.name)
+        .bind(&area.name_en)
+        .bind(area.level.osm_level())
+        .bind(multipolygon_wkt(&area.geometry))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    tracing::info!("Inserted {} administrative areas", areas.len());
+
+    Ok(())
+}
+
+/// Assign each junction the prefecture, city and ward it lies in
+///
+/// Returns the number of junctions that lie in at least one area.
+pub async fn assign_admin_areas(pool: &PgPool) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE y_junctions SET \
+           prefecture_code = NULL, prefecture_name = NULL, \
+           city_code = NULL, city_name = NULL, \
+           ward_code = NULL, ward_name = NULL",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for (level, code_column, name_column) in [
+        (AdminLevel::Prefecture, "prefecture_code", "prefecture_name"),
+        (AdminLevel::City, "city_code", "city_name"),
+        (AdminLevel::Ward, "ward_code", "ward_name"),
+    ] {
+        let result = sqlx::query(&format!(
+            "UPDATE y_junctions j SET {code_column} = a.code, {name_column} = a.name \
+             FROM admin_areas a \
+             WHERE a.admin_level = $1 AND ST_Covers(a.geometry, j.location)"
+        ))
+        .bind(level.osm_level())
+        .execute(&mut *tx)
+        .await?;
+
+        tracing::info!(
+            "Assigned {} junctions to a {}",
+            result.rows_affected(),
+            level.as_str()
+        );
+    }
+
+    let (assigned,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM y_junctions \
+         WHERE prefecture_code IS NOT NULL OR city_code IS NOT NULL OR ward_code IS NOT NULL",
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(assigned)
+}
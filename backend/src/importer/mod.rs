@@ -1,11 +1,23 @@
+pub mod boundary;
 pub mod calculator;
+pub mod compiled_dem;
 pub mod detector;
 pub mod elevation;
+pub mod elevation_source;
 pub mod inserter;
 pub mod parser;
+pub mod png_tile;
+pub mod tile_cache;
 
 use anyhow::Result;
+use rayon::prelude::*;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::db::repository::{self, ElevationSkip, ElevationTarget, ElevationUpdate};
+use crate::domain::{ArmProfile, ArmSlope, ElevationSkipReason, ProfileSample};
+use elevation_source::{ElevationFormat, ElevationSample, ElevationSource};
 
 pub async fn import_osm_data(
     pool: &PgPool,
@@ -14,11 +26,19 @@ pub async fn import_osm_data(
     min_lat: f64,
     max_lon: f64,
     max_lat: f64,
+    max_min_angle: i16,
 ) -> Result<usize> {
     tracing::info!("Opening PBF file: {}", input_path);
 
     // Parse PBF and extract Y-junctions
-    let junctions = parser::parse_pbf(input_path, min_lon, min_lat, max_lon, max_lat)?;
+    let junctions = parser::parse_pbf(
+        input_path,
+        min_lon,
+        min_lat,
+        max_lon,
+        max_lat,
+        max_min_angle,
+    )?;
 
     let count = junctions.len();
     tracing::info!("Found {} Y-junctions to insert", count);
@@ -29,132 +49,373 @@ pub async fn import_osm_data(
     Ok(count)
 }
 
-pub async fn import_elevation_data(pool: &PgPool, elevation_dir: &str) -> Result<usize> {
+/// Imports prefecture, city and ward boundaries from a PBF file and assigns them to junctions
+///
+/// Returns the number of junctions that lie in at least one area.
+pub async fn import_admin_areas(pool: &PgPool, input_path: &str) -> Result<i64> {
+    tracing::info!("Extracting administrative areas from {}", input_path);
+
+    let areas = boundary::parse_admin_areas(input_path)?;
+    inserter::insert_admin_areas(pool, &areas).await?;
+
+    let assigned = inserter::assign_admin_areas(pool).await?;
+    tracing::info!("{} junctions lie in an administrative area", assigned);
+
+    Ok(assigned)
+}
+
+/// Options for [`import_elevation_data`]
+#[derive(Debug, Clone)]
+pub struct ElevationImportOptions {
+    /// Format of the elevation data directory
+    pub format: ElevationFormat,
+    /// Memory budget for parsed DEM tiles in bytes
+    pub cache_capacity_bytes: usize,
+    /// Number of worker threads (defaults to the number of CPUs)
+    pub threads: Option<usize>,
+    /// Number of junctions fetched, enriched and committed at a time
+    pub batch_size: i64,
+    /// Skip junctions that already have an elevation
+    pub only_missing: bool,
+    /// Resume after this junction id (as logged for the last committed batch)
+    pub resume_after_id: Option<i64>,
+}
+
+impl Default for ElevationImportOptions {
+    fn default() -> Self {
+        Self {
+            format: ElevationFormat::default(),
+            cache_capacity_bytes: elevation::DEFAULT_CACHE_CAPACITY_BYTES,
+            threads: None,
+            batch_size: 10_000,
+            only_missing: false,
+            resume_after_id: None,
+        }
+    }
+}
+
+/// Result of enriching one junction with elevation data
+enum ElevationOutcome {
+    Updated(ElevationUpdate),
+    Skipped(ElevationSkip),
+}
+
+pub async fn import_elevation_data(
+    pool: &PgPool,
+    elevation_dir: &str,
+    options: &ElevationImportOptions,
+) -> Result<usize> {
     tracing::info!("Starting elevation data import from: {}", elevation_dir);
+    anyhow::ensure!(options.batch_size > 0, "batch_size must be positive");
+
+    // Initialize elevation source
+    let elevation_provider: Arc<dyn ElevationSource> = Arc::from(elevation_source::open(
+        elevation_dir,
+        options.format,
+        options.cache_capacity_bytes,
+    )?);
 
-    // Initialize elevation provider
-    let mut elevation_provider = elevation::ElevationProvider::new(elevation_dir)?;
+    let mut thread_pool = rayon::ThreadPoolBuilder::new();
+    if let Some(threads) = options.threads {
+        thread_pool = thread_pool.num_threads(threads);
+    }
+    let thread_pool = Arc::new(thread_pool.build()?);
 
-    // Fetch all junctions from database using repository
-    let junctions = crate::db::repository::find_all(pool).await?;
+    let mut after_id = options.resume_after_id.unwrap_or(0);
+    let total = repository::count_after_id(pool, after_id, options.only_missing).await?;
 
     tracing::info!(
-        "Found {} Y-junctions to enrich with elevation",
-        junctions.len()
+        "Found {} Y-junctions to enrich with elevation (only_missing={}, after_id={}, {} threads)",
+        total,
+        options.only_missing,
+        after_id,
+        thread_pool.current_num_threads()
     );
 
-    // Collect all elevation updates in memory first
-    let mut elevation_updates = Vec::new();
-    let mut skipped_no_junction_elev = 0;
-    let mut skipped_no_neighbor_elev = 0;
-
-    for (idx, junction) in junctions.iter().enumerate() {
-        // Get junction elevation
-        let junction_elevation = elevation_provider.get_elevation(junction.lat, junction.lon)?;
-
-        // Skip if no elevation data available
-        let Some(junction_elev) = junction_elevation else {
-            skipped_no_junction_elev += 1;
-            if idx < 10 {
-                tracing::warn!(
-                    "Junction {} at ({}, {}) has no elevation data",
-                    junction.id,
-                    junction.lat,
-                    junction.lon
-                );
-            }
-            continue;
+    let mut processed = 0;
+    let mut updated_count = 0;
+    let mut skipped: HashMap<ElevationSkipReason, usize> = HashMap::new();
+
+    // id順にキーセットページングで取得し、バッチごとにコミットする（中断後に再開可能）
+    loop {
+        let junctions = repository::find_batch_after_id(
+            pool,
+            after_id,
+            options.batch_size,
+            options.only_missing,
+        )
+        .await?;
+
+        let Some(last_id) = junctions.last().map(|j| j.id) else {
+            break;
         };
+        let batch_len = junctions.len();
 
-        if idx < 5 {
-            tracing::info!("Junction {} got elevation: {}m", junction.id, junction_elev);
-        }
+        // 標高計算はCPUバウンドなので、非同期ランタイムの外で並列実行する
+        let provider = Arc::clone(&elevation_provider);
+        let workers = Arc::clone(&thread_pool);
+        let outcomes = tokio::task::spawn_blocking(move || {
+            workers.install(|| enrich_junctions(provider.as_ref(), &junctions))
+        })
+        .await?;
 
-        // Calculate neighbor coordinates (approximately 10m away)
-        let neighbor_coords: Vec<(f64, f64)> = junction
-            .bearings
-            .iter()
-            .map(|&bearing| {
-                calculator::calculate_neighbor_coord(
-                    junction.lat,
-                    junction.lon,
-                    bearing as f64,
-                    10.0,
-                )
-            })
-            .collect();
-
-        // Get neighbor elevations
-        let neighbor_elevs: Vec<Option<f64>> = neighbor_coords
-            .iter()
-            .map(
-                |(lat, lon)| match elevation_provider.get_elevation(*lat, *lon) {
-                    Ok(elev) => elev,
-                    Err(e) => {
-                        tracing::warn!("Failed to get elevation at ({}, {}): {}", lat, lon, e);
-                        None
-                    }
-                },
-            )
-            .collect();
-
-        // Only update if all neighbor elevations are available
-        if let [Some(n1), Some(n2), Some(n3)] =
-            [neighbor_elevs[0], neighbor_elevs[1], neighbor_elevs[2]]
-        {
-            let neighbor_elevations = [n1, n2, n3];
-            let angles = [junction.angle_1, junction.angle_2, junction.angle_3];
-
-            let elevation_diffs = detector::JunctionForInsert::calculate_elevation_diffs(
-                junction_elev,
-                &neighbor_elevations,
-            );
-            let (min_diff, max_diff) =
-                detector::JunctionForInsert::calculate_min_max_diffs(&elevation_diffs);
-            let min_angle_index = detector::JunctionForInsert::calculate_min_angle_index(&angles);
-
-            elevation_updates.push(crate::db::repository::ElevationUpdate {
-                id: junction.id,
-                elevation: junction_elev as f32,
-                neighbor_elevations: [n1 as f32, n2 as f32, n3 as f32],
-                elevation_diffs: [
-                    elevation_diffs[0] as f32,
-                    elevation_diffs[1] as f32,
-                    elevation_diffs[2] as f32,
-                ],
-                min_angle_index,
-                min_elevation_diff: min_diff as f32,
-                max_elevation_diff: max_diff as f32,
-            });
-        } else {
-            skipped_no_neighbor_elev += 1;
-            if idx < 5 {
-                tracing::warn!(
-                    "Junction {} missing neighbor elevations: [{:?}, {:?}, {:?}]",
-                    junction.id,
-                    neighbor_elevs[0],
-                    neighbor_elevs[1],
-                    neighbor_elevs[2]
-                );
+        let mut elevation_updates = Vec::new();
+        let mut elevation_skips = Vec::new();
+        for outcome in outcomes {
+            match outcome {
+                ElevationOutcome::Updated(update) => elevation_updates.push(update),
+                ElevationOutcome::Skipped(skip) => {
+                    *skipped.entry(skip.reason).or_default() += 1;
+                    elevation_skips.push(skip);
+                }
             }
         }
-    }
 
-    tracing::info!(
-        "Elevation collection stats: total={}, skipped_no_junction={}, skipped_no_neighbors={}, collected={}",
-        junctions.len(), skipped_no_junction_elev, skipped_no_neighbor_elev, elevation_updates.len()
-    );
+        // 標高とスキップ理由は同じトランザクションで書き、バッチ単位で再開できるようにする
+        let mut tx = pool.begin().await?;
+        let updated = repository::bulk_update_elevations(&mut tx, &elevation_updates).await?;
+        repository::bulk_record_elevation_skips(&mut tx, &elevation_skips).await?;
+        tx.commit().await?;
+        updated_count += updated;
+        processed += batch_len;
+        after_id = last_id;
+
+        tracing::info!(
+            "Committed batch up to id {} ({}/{} junctions processed, {} updated)",
+            after_id,
+            processed,
+            total,
+            updated_count
+        );
+    }
 
+    let skipped_count = |reason| skipped.get(&reason).copied().unwrap_or(0);
     tracing::info!(
-        "Collected {} elevation updates, performing bulk update",
-        elevation_updates.len()
+        "Elevation collection stats: total={}, skipped_no_tile={}, skipped_nodata_at_junction={}, skipped_nodata_at_neighbor={}, updated={}",
+        processed,
+        skipped_count(ElevationSkipReason::NoTile),
+        skipped_count(ElevationSkipReason::NodataAtJunction),
+        skipped_count(ElevationSkipReason::NodataAtNeighbor),
+        updated_count
     );
-
-    // Perform bulk update using repository
-    let updated_count =
-        crate::db::repository::bulk_update_elevations(pool, &elevation_updates).await?;
+    tracing::info!("Tile cache: {}", elevation_provider.cache_stats());
 
     tracing::info!("Updated {} Y-junctions with elevation data", updated_count);
 
     Ok(updated_count)
 }
+
+/// Distance of the neighbor elevation used for the elevation diffs (meters)
+///
+/// The generated `local_slope` column (migration 008) divides by this distance, so
+/// changing it needs a new migration that recreates the column.
+const NEIGHBOR_DISTANCE_M: f64 = 10.0;
+
+/// Distances along each arm at which the elevation profile is sampled (meters)
+const PROFILE_DISTANCES_M: [f64; 4] = [5.0, 10.0, 20.0, 50.0];
+
+/// Enriches junctions in parallel
+///
+/// Junctions are grouped by the mesh code of their location so that each worker handles
+/// whole tiles at a time, and each tile is parsed once while it is hot in the cache.
+fn enrich_junctions(
+    provider: &dyn ElevationSource,
+    junctions: &[ElevationTarget],
+) -> Vec<ElevationOutcome> {
+    let mut groups: HashMap<String, Vec<&ElevationTarget>> = HashMap::new();
+    for junction in junctions {
+        groups
+            .entry(provider.group_key(junction.lat, junction.lon))
+            .or_default()
+            .push(junction);
+    }
+
+    tracing::debug!("Grouped junctions into {} tiles", groups.len());
+
+    groups
+        .into_par_iter()
+        .flat_map_iter(|(_, group)| {
+            group
+                .into_iter()
+                .map(|junction| enrich_junction(provider, junction))
+        })
+        .collect()
+}
+
+fn lookup_elevation(provider: &dyn ElevationSource, lat: f64, lon: f64) -> Option<ElevationSample> {
+    match provider.sample(lat, lon) {
+        Ok(sample) => sample,
+        Err(e) => {
+            tracing::warn!("Failed to get elevation at ({}, {}): {}", lat, lon, e);
+            None
+        }
+    }
+}
+
+/// Samples the elevation profile of one arm
+///
+/// Follows the way geometry when it was recorded at import time, otherwise a straight line
+/// along the bearing. Returns the profile and the elevation at [`NEIGHBOR_DISTANCE_M`].
+fn sample_arm_profile(
+    provider: &dyn ElevationSource,
+    junction: &ElevationTarget,
+    junction_elev: f64,
+    arm_index: usize,
+) -> (ArmProfile, Option<f64>) {
+    let bearing = junction.bearings[arm_index] as f64;
+    let straight_line = |distance| {
+        calculator::calculate_neighbor_coord(junction.lat, junction.lon, bearing, distance)
+    };
+
+    let (path, along_way) = match &junction.arm_geometries[arm_index] {
+        Some(path) if path.len() >= 2 => (path.clone(), true),
+        _ => {
+            let max_distance = PROFILE_DISTANCES_M[PROFILE_DISTANCES_M.len() - 1];
+            (
+                vec![(junction.lat, junction.lon), straight_line(max_distance)],
+                false,
+            )
+        }
+    };
+
+    let samples: Vec<ProfileSample> = PROFILE_DISTANCES_M
+        .iter()
+        .map(|&distance_m| {
+            let sample = calculator::point_along_path(&path, distance_m)
+                .and_then(|(lat, lon)| lookup_elevation(provider, lat, lon));
+            ProfileSample {
+                distance_m,
+                elevation: sample.as_ref().map(|s| s.elevation),
+                quality: sample.map(|s| s.quality),
+            }
+        })
+        .collect();
+
+    // 道が短く10m地点を取れない場合は方位方向の直線上で補う
+    let neighbor_elevation = samples
+        .iter()
+        .find(|s| s.distance_m == NEIGHBOR_DISTANCE_M)
+        .and_then(|s| s.elevation)
+        .or_else(|| {
+            let (lat, lon) = straight_line(NEIGHBOR_DISTANCE_M);
+            lookup_elevation(provider, lat, lon).map(|s| s.elevation)
+        });
+
+    let fit_points: Vec<(f64, f64)> = std::iter::once((0.0, junction_elev))
+        .chain(
+            samples
+                .iter()
+                .filter_map(|s| s.elevation.map(|elev| (s.distance_m, elev))),
+        )
+        .collect();
+    let grade_percent = calculator::calculate_grade_percent(&fit_points);
+
+    let profile = ArmProfile {
+        bearing,
+        along_way,
+        grade_percent,
+        slope: grade_percent.map(ArmSlope::from_grade),
+        samples,
+    };
+
+    (profile, neighbor_elevation)
+}
+
+/// Counts profile samples that had no elevation data
+fn count_nodata_samples(profiles: &[ArmProfile]) -> i16 {
+    profiles
+        .iter()
+        .flat_map(|profile| &profile.samples)
+        .filter(|sample| sample.elevation.is_none())
+        .count() as i16
+}
+
+fn enrich_junction(provider: &dyn ElevationSource, junction: &ElevationTarget) -> ElevationOutcome {
+    // Get junction elevation and the data that supplied it
+    let junction_elevation = match provider.sample(junction.lat, junction.lon) {
+        Ok(elevation) => elevation,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to get elevation at ({}, {}): {}",
+                junction.lat,
+                junction.lon,
+                e
+            );
+            None
+        }
+    };
+
+    // Skip if no elevation data available
+    let Some(ElevationSample {
+        elevation: junction_elev,
+        source: elevation_source,
+        quality: elevation_quality,
+    }) = junction_elevation
+    else {
+        tracing::debug!(
+            "Junction {} at ({}, {}) has no elevation data",
+            junction.id,
+            junction.lat,
+            junction.lon
+        );
+        // タイルの範囲外か、タイル内の欠損値かを区別して記録する
+        let reason = if provider.has_tile(junction.lat, junction.lon) {
+            ElevationSkipReason::NodataAtJunction
+        } else {
+            ElevationSkipReason::NoTile
+        };
+        return ElevationOutcome::Skipped(ElevationSkip {
+            id: junction.id,
+            reason,
+            nodata_samples: None,
+        });
+    };
+
+    let (profiles, neighbor_elevs): (Vec<ArmProfile>, Vec<Option<f64>>) = (0..3)
+        .map(|i| sample_arm_profile(provider, junction, junction_elev, i))
+        .unzip();
+    let nodata_samples = count_nodata_samples(&profiles);
+
+    // Only update if all neighbor elevations are available
+    let [Some(n1), Some(n2), Some(n3)] = [neighbor_elevs[0], neighbor_elevs[1], neighbor_elevs[2]]
+    else {
+        tracing::debug!(
+            "Junction {} missing neighbor elevations: {:?}",
+            junction.id,
+            neighbor_elevs
+        );
+        return ElevationOutcome::Skipped(ElevationSkip {
+            id: junction.id,
+            reason: ElevationSkipReason::NodataAtNeighbor,
+            nodata_samples: Some(nodata_samples),
+        });
+    };
+
+    let neighbor_elevations = [n1, n2, n3];
+
+    let elevation_diffs =
+        detector::JunctionForInsert::calculate_elevation_diffs(junction_elev, &neighbor_elevations);
+    let (min_diff, max_diff) =
+        detector::JunctionForInsert::calculate_min_max_diffs(&elevation_diffs);
+    let min_angle_index = detector::JunctionForInsert::calculate_min_angle_index(&junction.angles);
+    let grades = [0, 1, 2].map(|i| profiles[i].grade_percent.map(|g| g as f32));
+
+    ElevationOutcome::Updated(ElevationUpdate {
+        id: junction.id,
+        elevation: junction_elev as f32,
+        neighbor_elevations: [n1 as f32, n2 as f32, n3 as f32],
+        elevation_diffs: [
+            elevation_diffs[0] as f32,
+            elevation_diffs[1] as f32,
+            elevation_diffs[2] as f32,
+        ],
+        min_angle_index,
+        min_elevation_diff: min_diff as f32,
+        max_elevation_diff: max_diff as f32,
+        elevation_source,
+        elevation_quality: elevation_quality.as_str().to_string(),
+        nodata_samples,
+        grades,
+        elevation_profile: profiles,
+    })
+}
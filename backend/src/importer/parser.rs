@@ -2,16 +2,29 @@ use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 
-use super::calculator::calculate_junction_angles;
-use super::detector::{JunctionForInsert, NodeConnectionCounter, YJunctionWithCoords};
+use super::calculator::{calculate_bearing, calculate_junction_angles, truncate_path};
+use super::detector::{
+    highway_class, road_search_text, JunctionForInsert, NodeConnectionCounter, WayTagInfo,
+    YJunctionWithCoords, SEARCH_NAME_TAGS,
+};
 use crate::domain::junction::AngleType;
 
+/// Maximum number of nodes collected per road arm
+const MAX_ARM_NODES: usize = 50;
+
+/// Length of road arm geometry kept for elevation profiles (meters)
+pub const ARM_GEOMETRY_LENGTH_M: f64 = 50.0;
+
+/// Junctions whose minimum angle is at least this (degrees) are T-junctions and are skipped
+pub const DEFAULT_MAX_MIN_ANGLE: i16 = 60;
+
 pub fn parse_pbf(
     input_path: &str,
     min_lon: f64,
     min_lat: f64,
     max_lon: f64,
     max_lat: f64,
+    max_min_angle: i16,
 ) -> Result<Vec<JunctionForInsert>> {
     tracing::info!(
         "Parsing PBF file with bbox: ({}, {}) to ({}, {})",
@@ -49,6 +62,20 @@ pub fn parse_pbf(
 
                     // Add this way and its nodes to the counter
                     counter.add_way(way.id(), &node_ids, highway_type, bridge, tunnel);
+
+                    // Road names for text search and labels
+                    let name = way
+                        .tags()
+                        .find(|&(k, _)| k == "name")
+                        .map(|(_, v)| v.to_string());
+                    let alt_names: Vec<String> = way
+                        .tags()
+                        .filter(|(k, _)| SEARCH_NAME_TAGS.contains(k))
+                        .map(|(_, v)| v.to_string())
+                        .collect();
+                    if name.is_some() || !alt_names.is_empty() {
+                        counter.set_way_names(way.id(), name, alt_names);
+                    }
                 }
             }
         }
@@ -143,12 +170,11 @@ pub fn parse_pbf(
     // 3rd pass: Get coordinates of neighboring nodes and calculate angles
     tracing::info!("Starting 3rd pass: calculating angles for Y-junctions");
 
-    // Collect all neighboring node IDs
+    // Collect all node IDs along the road arms (the first one is the neighboring node)
     let mut all_neighbor_ids = HashSet::new();
     for junction in &y_junctions {
-        let neighbor_ids = counter.get_neighboring_nodes(junction.node_id);
-        for id in neighbor_ids {
-            all_neighbor_ids.insert(id);
+        for (arm, _) in counter.get_arms(junction.node_id, MAX_ARM_NODES) {
+            all_neighbor_ids.extend(arm);
         }
     }
 
@@ -190,29 +216,44 @@ pub fn parse_pbf(
     let mut failed_calculations = 0;
 
     for junction in &y_junctions {
-        // Get neighboring nodes with their way tags in consistent order
-        let neighbor_data = counter.get_neighbors_with_tags(junction.node_id);
+        // Get road arms with their way tags in consistent order
+        let arm_data = counter.get_arms(junction.node_id, MAX_ARM_NODES);
 
-        if neighbor_data.len() != 3 {
+        if arm_data.len() != 3 {
             failed_calculations += 1;
             continue;
         }
 
-        // Extract neighbor IDs and way tags from the paired data
-        let neighbor_ids: Vec<i64> = neighbor_data.iter().map(|(id, _)| *id).collect();
-        let way_tags: Vec<_> = neighbor_data.iter().map(|(_, tag)| tag).collect();
-
-        // Get coordinates for all 3 neighboring nodes
-        let neighbor_points: Vec<(f64, f64)> = neighbor_ids
-            .iter()
-            .filter_map(|&id| neighbor_coords.get(&id).copied())
+        // Build (lat, lon) geometry of each arm, starting at the junction node.
+        // The path stops at the first node whose coordinates are unknown.
+        let mut arms: Vec<(Vec<(f64, f64)>, WayTagInfo)> = arm_data
+            .into_iter()
+            .map(|(node_ids, tags)| {
+                let mut path = vec![(junction.lat, junction.lon)];
+                path.extend(
+                    node_ids
+                        .iter()
+                        .map_while(|id| neighbor_coords.get(id).copied()),
+                );
+                (truncate_path(&path, ARM_GEOMETRY_LENGTH_M), tags)
+            })
             .collect();
 
-        if neighbor_points.len() != 3 {
+        if arms.iter().any(|(path, _)| path.len() < 2) {
             failed_calculations += 1;
             continue;
         }
 
+        // Sort arms clockwise by bearing so that they line up with the bearings and angles
+        arms.sort_by(|(a, _), (b, _)| {
+            let bearing_a = calculate_bearing(a[0].0, a[0].1, a[1].0, a[1].1);
+            let bearing_b = calculate_bearing(b[0].0, b[0].1, b[1].0, b[1].1);
+            bearing_a.partial_cmp(&bearing_b).unwrap()
+        });
+
+        let neighbor_points: Vec<(f64, f64)> = arms.iter().map(|(path, _)| path[1]).collect();
+        let way_tags: Vec<&WayTagInfo> = arms.iter().map(|(_, tags)| tags).collect();
+
         // Calculate angles and bearings
         if let Some((angles, bearings)) =
             calculate_junction_angles(junction.lat, junction.lon, &neighbor_points)
@@ -239,17 +280,24 @@ pub fn parse_pbf(
                 );
             }
 
-            // 最小角度が60度以上の場合はT字路とみなして除外
-            if min_angle >= 60 {
+            // 最小角度が閾値（既定は60度）以上の場合はT字路とみなして除外
+            if min_angle >= max_min_angle {
                 continue;
             }
 
             successful_calculations += 1;
 
-            // Extract bridge/tunnel flags from way tags (now guaranteed to be in same order as angles)
+            // Extract bridge/tunnel flags from way tags (arms are sorted in the same order as angles)
             let (way_1_bridge, way_1_tunnel) = (way_tags[0].bridge, way_tags[0].tunnel);
             let (way_2_bridge, way_2_tunnel) = (way_tags[1].bridge, way_tags[1].tunnel);
             let (way_3_bridge, way_3_tunnel) = (way_tags[2].bridge, way_tags[2].tunnel);
+            let highway_class = highway_class(way_tags.iter().copied());
+            let road_names = [
+                way_tags[0].name.clone(),
+                way_tags[1].name.clone(),
+                way_tags[2].name.clone(),
+            ];
+            let road_search_text = road_search_text(way_tags.iter().copied());
 
             // Create JunctionForInsert
             junctions_for_insert.push(JunctionForInsert {
@@ -266,12 +314,16 @@ pub fn parse_pbf(
                 min_angle_index: None,
                 min_elevation_diff: None,
                 max_elevation_diff: None,
+                arm_geometries: [arms[0].0.clone(), arms[1].0.clone(), arms[2].0.clone()],
                 way_1_bridge,
                 way_1_tunnel,
                 way_2_bridge,
                 way_2_tunnel,
                 way_3_bridge,
                 way_3_tunnel,
+                highway_class,
+                road_names,
+                road_search_text,
             });
         } else {
             failed_calculations += 1;
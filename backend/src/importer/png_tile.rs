@@ -0,0 +1,381 @@
+use anyhow::{Context, Result};
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::elevation::DEFAULT_CACHE_CAPACITY_BYTES;
+use super::elevation_source::{ElevationSample, ElevationSource};
+use super::tile_cache::{CacheStats, SharedTileCache, Weighted};
+use crate::domain::ElevationQuality;
+
+/// Width and height of a GSI elevation tile in pixels
+const TILE_SIZE: usize = 256;
+
+/// Highest zoom level considered when scanning the tile directory
+const MAX_ZOOM: u8 = 24;
+
+/// Decodes the elevation of one pixel of a GSI elevation PNG tile
+///
+/// x = 2^16·R + 2^8·G + B encodes the elevation in 0.01 m units as a 24-bit two's
+/// complement number, and x = 2^23 (RGB 128, 0, 0) means no data.
+pub fn decode_elevation(r: u8, g: u8, b: u8) -> Option<f64> {
+    const NODATA: u32 = 1 << 23;
+    const OFFSET: f64 = (1u32 << 24) as f64;
+
+    let x = (r as u32) << 16 | (g as u32) << 8 | b as u32;
+    match x.cmp(&NODATA) {
+        std::cmp::Ordering::Less => Some(x as f64 * 0.01),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some((x as f64 - OFFSET) * 0.01),
+    }
+}
+
+/// Converts a coordinate to its Web Mercator tile and pixel position at the given zoom
+///
+/// # Returns
+/// * `(tile_x, tile_y, pixel_x, pixel_y)`
+fn pixel_position(lat: f64, lon: f64, zoom: u8) -> (u32, u32, usize, usize) {
+    let n = (1u64 << zoom) as f64;
+    let x = (lon + 180.0) / 360.0 * n;
+    let y = (1.0 - lat.to_radians().tan().asinh() / PI) / 2.0 * n;
+
+    let max_tile = n - 1.0;
+    let tile_x = x.floor().clamp(0.0, max_tile);
+    let tile_y = y.floor().clamp(0.0, max_tile);
+
+    let max_pixel = (TILE_SIZE - 1) as f64;
+    let pixel_x = ((x - tile_x) * TILE_SIZE as f64)
+        .floor()
+        .clamp(0.0, max_pixel);
+    let pixel_y = ((y - tile_y) * TILE_SIZE as f64)
+        .floor()
+        .clamp(0.0, max_pixel);
+
+    (
+        tile_x as u32,
+        tile_y as u32,
+        pixel_x as usize,
+        pixel_y as usize,
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PngTileKey {
+    zoom: u8,
+    x: u32,
+    y: u32,
+}
+
+/// Decoded GSI elevation PNG tile
+struct PngTile {
+    /// Elevations in meters in row-major order from the north-west corner, NaN for no data
+    elevations: Vec<f32>,
+}
+
+impl Weighted for PngTile {
+    fn weight_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.elevations.capacity() * std::mem::size_of::<f32>()
+    }
+}
+
+impl PngTile {
+    fn get_elevation(&self, pixel_x: usize, pixel_y: usize) -> Option<f64> {
+        self.elevations
+            .get(pixel_y * TILE_SIZE + pixel_x)
+            .filter(|e| !e.is_nan())
+            .map(|&e| e as f64)
+    }
+
+    fn parse(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        // パレットやグレースケールもRGB(A)の8bitに展開する
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().context("Failed to read PNG header")?;
+
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .context("Failed to decode PNG")?;
+
+        anyhow::ensure!(
+            info.width as usize == TILE_SIZE && info.height as usize == TILE_SIZE,
+            "Unexpected tile size {}x{}",
+            info.width,
+            info.height
+        );
+
+        let channels = match info.color_type {
+            png::ColorType::Rgb => 3,
+            png::ColorType::Rgba => 4,
+            other => anyhow::bail!("Unsupported PNG color type: {:?}", other),
+        };
+
+        let elevations = buf[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|pixel| {
+                // 透明ピクセルもデータなしとして扱う
+                if channels == 4 && pixel[3] == 0 {
+                    return f32::NAN;
+                }
+                decode_elevation(pixel[0], pixel[1], pixel[2]).map_or(f32::NAN, |e| e as f32)
+            })
+            .collect();
+
+        Ok(Self { elevations })
+    }
+}
+
+/// Provides elevation data from a directory of GSI elevation PNG tiles (`{z}/{x}/{y}.png`)
+///
+/// When several zoom levels are present, the highest one with data at a coordinate wins.
+pub struct PngTileProvider {
+    root: PathBuf,
+    /// Zoom levels present in the directory, highest first
+    zooms: Vec<u8>,
+    /// Memory-bounded LRU cache of decoded tiles; missing tiles and tiles that failed to
+    /// decode are not retried
+    cache: SharedTileCache<PngTileKey, PngTile>,
+}
+
+impl PngTileProvider {
+    /// Creates a provider for a `{z}/{x}/{y}.png` tile directory
+    ///
+    /// Tiles are located by path on first access rather than indexed up front, since a
+    /// tile set for a whole prefecture can hold hundreds of thousands of files.
+    pub fn new(data_dir: &str) -> Result<Self> {
+        let root = PathBuf::from(data_dir);
+
+        let mut zooms: Vec<u8> = std::fs::read_dir(&root)
+            .context(format!("Failed to read tile directory: {}", data_dir))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u8>().ok())
+            .filter(|&zoom| zoom <= MAX_ZOOM)
+            .collect();
+        zooms.sort_unstable_by(|a, b| b.cmp(a));
+
+        anyhow::ensure!(
+            !zooms.is_empty(),
+            "No zoom level directories found in {}",
+            data_dir
+        );
+
+        tracing::info!(
+            "Initialized PngTileProvider: zoom levels {:?} in {}",
+            zooms,
+            data_dir
+        );
+
+        Ok(Self {
+            root,
+            zooms,
+            cache: SharedTileCache::new(DEFAULT_CACHE_CAPACITY_BYTES),
+        })
+    }
+
+    /// Sets the memory budget for decoded tiles, replacing the default of 256 MiB
+    pub fn with_cache_capacity(self, capacity_bytes: usize) -> Self {
+        Self {
+            cache: SharedTileCache::new(capacity_bytes),
+            ..self
+        }
+    }
+
+    fn tile_path(&self, key: &PngTileKey) -> PathBuf {
+        self.root
+            .join(key.zoom.to_string())
+            .join(key.x.to_string())
+            .join(format!("{}.png", key.y))
+    }
+
+    /// Returns the tile for the given key, decoding and caching it on first access
+    fn load_tile(&self, key: &PngTileKey) -> Option<Arc<PngTile>> {
+        self.cache.get_or_load(key, || {
+            let path = self.tile_path(key);
+            if !path.is_file() {
+                return None;
+            }
+
+            match PngTile::parse(&path) {
+                Ok(tile) => Some(tile),
+                Err(e) => {
+                    tracing::warn!("Failed to decode PNG tile {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+    }
+}
+
+impl ElevationSource for PngTileProvider {
+    /// Returns the value of the pixel containing the coordinate (PNG tiles are not interpolated)
+    fn sample(&self, lat: f64, lon: f64) -> Result<Option<ElevationSample>> {
+        for &zoom in &self.zooms {
+            let (x, y, pixel_x, pixel_y) = pixel_position(lat, lon, zoom);
+            let elevation = self
+                .load_tile(&PngTileKey { zoom, x, y })
+                .and_then(|tile| tile.get_elevation(pixel_x, pixel_y));
+
+            if let Some(elevation) = elevation {
+                return Ok(Some(ElevationSample {
+                    elevation,
+                    source: format!("PNG_Z{}", zoom),
+                    quality: ElevationQuality::Nearest,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn has_tile(&self, lat: f64, lon: f64) -> bool {
+        self.zooms.iter().any(|&zoom| {
+            let (x, y, _, _) = pixel_position(lat, lon, zoom);
+            self.load_tile(&PngTileKey { zoom, x, y }).is_some()
+        })
+    }
+
+    fn group_key(&self, lat: f64, lon: f64) -> String {
+        let zoom = self.zooms[0];
+        let (x, y, _, _) = pixel_position(lat, lon, zoom);
+        format!("{}/{}/{}", zoom, x, y)
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_LAT: f64 = 35.6812;
+    const TEST_LON: f64 = 139.7671;
+
+    /// Encodes an elevation the way GSI does (None → nodata)
+    fn encode_elevation(elevation: Option<f64>) -> [u8; 3] {
+        let x = match elevation {
+            None => 1u32 << 23,
+            Some(e) => ((e / 0.01).round() as i64).rem_euclid(1 << 24) as u32,
+        };
+        [(x >> 16) as u8, (x >> 8) as u8, x as u8]
+    }
+
+    /// Writes a 256x256 RGB tile where every pixel has the given elevation
+    fn write_tile(root: &Path, zoom: u8, x: u32, y: u32, elevation: Option<f64>) {
+        let dir = root.join(zoom.to_string()).join(x.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = File::create(dir.join(format!("{}.png", y))).unwrap();
+
+        let mut encoder = png::Encoder::new(file, TILE_SIZE as u32, TILE_SIZE as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+
+        let pixel = encode_elevation(elevation);
+        let data: Vec<u8> = pixel.repeat(TILE_SIZE * TILE_SIZE);
+        writer.write_image_data(&data).unwrap();
+    }
+
+    fn write_test_tile(root: &Path, zoom: u8, elevation: Option<f64>) {
+        let (x, y, _, _) = pixel_position(TEST_LAT, TEST_LON, zoom);
+        write_tile(root, zoom, x, y, elevation);
+    }
+
+    #[test]
+    fn test_decode_elevation() {
+        assert_eq!(decode_elevation(0, 0, 0), Some(0.0));
+        assert_eq!(decode_elevation(0, 0, 100), Some(1.0));
+        assert!((decode_elevation(0x05, 0xC3, 0x10).unwrap() - 3776.16).abs() < 1e-9);
+        // 2^23 はデータなし
+        assert_eq!(decode_elevation(128, 0, 0), None);
+        // 2^23 を超える値は負の標高
+        assert!((decode_elevation(255, 255, 255).unwrap() + 0.01).abs() < 1e-9);
+        assert!((decode_elevation(255, 254, 12).unwrap() + 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pixel_position() {
+        // ズーム0では全世界が1タイル
+        assert_eq!(pixel_position(0.0, 0.0, 0), (0, 0, 128, 128));
+
+        // 東京駅はズーム15でタイル (29105, 12903)
+        let (x, y, _, _) = pixel_position(TEST_LAT, TEST_LON, 15);
+        assert_eq!((x, y), (29105, 12903));
+    }
+
+    #[test]
+    fn test_png_tile_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_tile(dir.path(), 15, Some(3.25));
+
+        let provider = PngTileProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let result = provider.sample(TEST_LAT, TEST_LON).unwrap();
+
+        let sample = result.expect("Should get elevation from PNG tile");
+        assert!(
+            (sample.elevation - 3.25).abs() < 1e-4,
+            "got {}",
+            sample.elevation
+        );
+        assert_eq!(sample.source, "PNG_Z15");
+        assert_eq!(sample.quality, ElevationQuality::Nearest);
+    }
+
+    #[test]
+    fn test_png_tile_negative_elevation() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_tile(dir.path(), 14, Some(-2.5));
+
+        let provider = PngTileProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let elevation = provider.get_elevation(TEST_LAT, TEST_LON).unwrap().unwrap();
+        assert!((elevation + 2.5).abs() < 1e-4, "got {}", elevation);
+    }
+
+    #[test]
+    fn test_png_tile_nodata_falls_back_to_lower_zoom() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_tile(dir.path(), 15, None);
+        write_test_tile(dir.path(), 14, Some(12.0));
+
+        let provider = PngTileProvider::new(dir.path().to_str().unwrap()).unwrap();
+        let sample = provider.sample(TEST_LAT, TEST_LON).unwrap().unwrap();
+        assert!((sample.elevation - 12.0).abs() < 1e-4);
+        assert_eq!(sample.source, "PNG_Z14");
+    }
+
+    #[test]
+    fn test_png_tile_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_tile(dir.path(), 15, Some(1.0));
+
+        let provider = PngTileProvider::new(dir.path().to_str().unwrap()).unwrap();
+        // 大阪のタイルは存在しない
+        assert_eq!(provider.get_elevation(34.7025, 135.4959).unwrap(), None);
+        assert_eq!(provider.get_elevation(34.7025, 135.4959).unwrap(), None);
+        assert_eq!(provider.cache_stats().entries, 0);
+    }
+
+    #[test]
+    fn test_png_tile_has_tile() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_tile(dir.path(), 15, None);
+
+        let provider = PngTileProvider::new(dir.path().to_str().unwrap()).unwrap();
+        // タイルはあるがデータなし
+        assert!(provider.has_tile(TEST_LAT, TEST_LON));
+        assert_eq!(provider.get_elevation(TEST_LAT, TEST_LON).unwrap(), None);
+        // タイル自体が存在しない
+        assert!(!provider.has_tile(34.7025, 135.4959));
+    }
+
+    #[test]
+    fn test_png_tile_requires_zoom_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(PngTileProvider::new(dir.path().to_str().unwrap()).is_err());
+    }
+}
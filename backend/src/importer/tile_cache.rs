@@ -0,0 +1,326 @@
+use lru::LruCache;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Values stored in a [`TileCache`] report their approximate heap size
+pub trait Weighted {
+    fn weight_bytes(&self) -> usize;
+}
+
+/// Cache usage statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Number of tiles currently held
+    pub entries: usize,
+    /// Approximate bytes currently held
+    pub bytes: usize,
+    /// Memory budget in bytes
+    pub capacity_bytes: usize,
+}
+
+impl CacheStats {
+    /// Fraction of lookups served from the cache (0.0 when there were no lookups)
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hits={}, misses={}, hit_rate={:.1}%, evictions={}, entries={}, memory={:.1}/{:.1} MiB",
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.0,
+            self.evictions,
+            self.entries,
+            self.bytes as f64 / (1024.0 * 1024.0),
+            self.capacity_bytes as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+/// Memory-bounded LRU cache for parsed elevation tiles
+///
+/// The least recently used tiles are evicted once the total weight exceeds the budget.
+/// The most recently inserted tile is always kept, even if it alone exceeds the budget.
+pub struct TileCache<K: Hash + Eq, V> {
+    entries: LruCache<K, Arc<V>>,
+    bytes: usize,
+    capacity_bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<K: Hash + Eq, V: Weighted> TileCache<K, V> {
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            bytes: 0,
+            capacity_bytes,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Looks up a tile and marks it as most recently used
+    pub fn get(&mut self, key: &K) -> Option<Arc<V>> {
+        match self.entries.get(key) {
+            Some(value) => {
+                self.hits += 1;
+                Some(Arc::clone(value))
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Looks up a tile without counting a hit or miss or changing the LRU order
+    pub fn peek(&self, key: &K) -> Option<Arc<V>> {
+        self.entries.peek(key).map(Arc::clone)
+    }
+
+    /// Inserts a tile, evicting least recently used tiles to stay within the budget
+    pub fn insert(&mut self, key: K, value: V) -> Arc<V> {
+        let value = Arc::new(value);
+        self.bytes += value.weight_bytes();
+
+        if let Some(old) = self.entries.put(key, Arc::clone(&value)) {
+            self.bytes -= old.weight_bytes();
+        }
+
+        while self.bytes > self.capacity_bytes && self.entries.len() > 1 {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => {
+                    self.bytes -= evicted.weight_bytes();
+                    self.evictions += 1;
+                }
+                None => break,
+            }
+        }
+
+        value
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            capacity_bytes: self.capacity_bytes,
+        }
+    }
+}
+
+/// [`TileCache`] shared by worker threads that loads each tile at most once
+///
+/// When several threads miss the same tile, one of them loads it while the others wait for
+/// the result, so a tile is never parsed twice or counted against the budget twice. Tiles
+/// whose loader returned `None` are remembered and not retried.
+pub struct SharedTileCache<K: Hash + Eq, V> {
+    state: Mutex<SharedState<K, V>>,
+    /// Notified whenever a tile finishes loading
+    loaded: Condvar,
+}
+
+struct SharedState<K: Hash + Eq, V> {
+    cache: TileCache<K, V>,
+    /// Tiles being loaded by some thread
+    loading: HashSet<K>,
+    /// Tiles that are missing or failed to load
+    unavailable: HashSet<K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Weighted> SharedTileCache<K, V> {
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            state: Mutex::new(SharedState {
+                cache: TileCache::new(capacity_bytes),
+                loading: HashSet::new(),
+                unavailable: HashSet::new(),
+            }),
+            loaded: Condvar::new(),
+        }
+    }
+
+    /// Returns the cached tile, or loads it with `load` if no thread has loaded it yet
+    pub fn get_or_load(&self, key: &K, load: impl FnOnce() -> Option<V>) -> Option<Arc<V>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(tile) = state.cache.get(key) {
+            return Some(tile);
+        }
+
+        // 他のスレッドが読み込み中なら、終わるのを待ってその結果を使う
+        while state.loading.contains(key) {
+            state = self.loaded.wait(state).unwrap();
+        }
+        if let Some(tile) = state.cache.peek(key) {
+            return Some(tile);
+        }
+        if state.unavailable.contains(key) {
+            return None;
+        }
+        state.loading.insert(key.clone());
+        drop(state);
+
+        // 読み込み中はロックを保持しない（他のタイルの参照をブロックしないため）
+        let _loading = LoadingGuard { cache: self, key };
+        let tile = load();
+
+        let mut state = self.state.lock().unwrap();
+        match tile {
+            Some(tile) => Some(state.cache.insert(key.clone(), tile)),
+            None => {
+                state.unavailable.insert(key.clone());
+                None
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.state.lock().unwrap().cache.stats()
+    }
+}
+
+/// Clears the loading mark and wakes the waiting threads, even if the loader panicked
+struct LoadingGuard<'a, K: Hash + Eq, V> {
+    cache: &'a SharedTileCache<K, V>,
+    key: &'a K,
+}
+
+impl<K: Hash + Eq, V> Drop for LoadingGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.cache.state.lock() {
+            state.loading.remove(self.key);
+        }
+        self.cache.loaded.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    struct Blob(usize);
+
+    impl Weighted for Blob {
+        fn weight_bytes(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = TileCache::new(250);
+        cache.insert("a", Blob(100));
+        cache.insert("b", Blob(100));
+
+        // "a" を参照して最近使用にする
+        assert!(cache.get(&"a").is_some());
+
+        cache.insert("c", Blob(100));
+
+        assert!(cache.get(&"a").is_some());
+        assert!(cache.get(&"b").is_none(), "b should have been evicted");
+        assert!(cache.get(&"c").is_some());
+
+        let stats = cache.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.bytes, 200);
+    }
+
+    #[test]
+    fn test_keeps_oversized_entry() {
+        let mut cache = TileCache::new(50);
+        cache.insert("a", Blob(10));
+        cache.insert("b", Blob(100));
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, 100);
+        assert!(cache.get(&"b").is_some());
+    }
+
+    #[test]
+    fn test_hit_miss_stats() {
+        let mut cache = TileCache::new(1000);
+        cache.insert("a", Blob(1));
+
+        assert!(cache.get(&"a").is_some());
+        assert!(cache.get(&"a").is_some());
+        assert!(cache.get(&"x").is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+        assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_replacing_entry_updates_weight() {
+        let mut cache = TileCache::new(1000);
+        cache.insert("a", Blob(100));
+        cache.insert("a", Blob(30));
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, 30);
+    }
+
+    #[test]
+    fn test_shared_cache_loads_each_tile_once() {
+        let cache = SharedTileCache::new(1000);
+        let loads = AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let tile = cache.get_or_load(&"a", || {
+                        loads.fetch_add(1, Ordering::SeqCst);
+                        // 他のスレッドが待つ間に読み込みが終わらないようにする
+                        std::thread::sleep(Duration::from_millis(50));
+                        Some(Blob(100))
+                    });
+                    assert!(tile.is_some());
+                });
+            }
+        });
+
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.stats().bytes, 100);
+    }
+
+    #[test]
+    fn test_shared_cache_remembers_unavailable_tiles() {
+        let cache: SharedTileCache<&str, Blob> = SharedTileCache::new(1000);
+        let loads = AtomicUsize::new(0);
+        let load = || {
+            loads.fetch_add(1, Ordering::SeqCst);
+            None
+        };
+
+        assert!(cache.get_or_load(&"missing", load).is_none());
+        assert!(cache.get_or_load(&"missing", load).is_none());
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+}
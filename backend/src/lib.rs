@@ -1,4 +1,6 @@
 pub mod api;
+pub mod config;
+pub mod dataset;
 pub mod db;
 pub mod domain;
 pub mod importer;
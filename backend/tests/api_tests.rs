@@ -8,7 +8,12 @@ use serde_json::Value;
 use serial_test::serial;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
 use tower::util::ServiceExt;
+use y_junction_backend::api::routes::create_router_with;
+use y_junction_backend::dataset::flatgeobuf::FlatGeobufWriter;
+use y_junction_backend::db::{migrations, MemoryRepository};
+use y_junction_backend::domain::{Junction, JunctionAreas};
 
 // テスト用のosm_node_id自動生成
 static TEST_OSM_NODE_ID_COUNTER: AtomicI64 = AtomicI64::new(1);
@@ -59,12 +64,12 @@ async fn setup_test_db() -> PgPool {
         .expect("Failed to connect to test database");
 
     // マイグレーション実行（テストDB初回実行時にスキーマを作成）
-    sqlx::migrate!("./migrations")
+    migrations::MIGRATOR
         .run(&pool)
         .await
         .expect("Failed to run migrations");
 
-    sqlx::query("TRUNCATE TABLE y_junctions RESTART IDENTITY CASCADE")
+    sqlx::query("TRUNCATE TABLE y_junctions, admin_areas RESTART IDENTITY CASCADE")
         .execute(&pool)
         .await
         .expect("Failed to truncate table");
@@ -93,6 +98,12 @@ struct TestJunctionData {
     way_2_tunnel: bool,
     way_3_bridge: bool,
     way_3_tunnel: bool,
+    grades: Option<[f64; 3]>,
+    highway_class: Option<String>,
+    elevation_source: Option<String>,
+    elevation_quality: Option<String>,
+    elevation_nodata_samples: Option<i16>,
+    elevation_skip_reason: Option<String>,
 }
 
 impl TestJunctionData {
@@ -117,6 +128,12 @@ impl TestJunctionData {
             way_2_tunnel: false,
             way_3_bridge: false,
             way_3_tunnel: false,
+            grades: None,
+            highway_class: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
         }
     }
 
@@ -141,6 +158,12 @@ impl TestJunctionData {
             way_2_tunnel: false,
             way_3_bridge: false,
             way_3_tunnel: false,
+            grades: None,
+            highway_class: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
         }
     }
 
@@ -165,6 +188,12 @@ impl TestJunctionData {
             way_2_tunnel: false,
             way_3_bridge: false,
             way_3_tunnel: false,
+            grades: None,
+            highway_class: None,
+            elevation_source: None,
+            elevation_quality: None,
+            elevation_nodata_samples: None,
+            elevation_skip_reason: None,
         }
     }
 
@@ -174,6 +203,30 @@ impl TestJunctionData {
         self
     }
 
+    fn with_grades(mut self, grades: [f64; 3]) -> Self {
+        self.grades = Some(grades);
+        self
+    }
+
+    fn with_highway_class(mut self, highway_class: &str) -> Self {
+        self.highway_class = Some(highway_class.to_string());
+        self
+    }
+
+    fn with_elevation_source(mut self, source: &str, quality: &str, nodata_samples: i16) -> Self {
+        self.elevation_source = Some(source.to_string());
+        self.elevation_quality = Some(quality.to_string());
+        self.elevation_nodata_samples = Some(nodata_samples);
+        self
+    }
+
+    // 標高を取得できなかった（skip_reason がなければ未処理の）Y字路にする
+    fn without_elevation(mut self, skip_reason: Option<&str>) -> Self {
+        self.elevation = None;
+        self.elevation_skip_reason = skip_reason.map(str::to_string);
+        self
+    }
+
     fn with_bridge_tunnel(
         mut self,
         way_1_bridge: bool,
@@ -191,6 +244,49 @@ impl TestJunctionData {
         self.way_3_tunnel = way_3_tunnel;
         self
     }
+
+    // データベースの生成列（最小角高低差・局所勾配など）も計算したY字路
+    fn to_junction(&self, id: i64) -> Junction {
+        let min_angle_elevation_diff = self.neighbor_elevations.and_then(|e| {
+            let (a, b) = match self.min_angle_index? {
+                1 => (e[0], e[1]),
+                2 => (e[1], e[2]),
+                3 => (e[2], e[0]),
+                _ => return None,
+            };
+            Some((a - b).abs())
+        });
+
+        Junction {
+            id,
+            osm_node_id: self.osm_node_id,
+            lat: self.lat,
+            lon: self.lon,
+            angle_1: self.angle_1,
+            angle_2: self.angle_2,
+            angle_3: self.angle_3,
+            bearings: self.bearings.to_vec(),
+            created_at: chrono::Utc::now(),
+            elevation: self.elevation,
+            min_elevation_diff: self.min_elevation_diff,
+            max_elevation_diff: self.max_elevation_diff,
+            min_angle_elevation_diff,
+            // migrations/008_add_local_slope.sql と同じ式（10.0 はインポーターの NEIGHBOR_DISTANCE_M）
+            local_slope: self.max_elevation_diff.map(|diff| diff / 10.0 * 100.0),
+            elevation_source: self.elevation_source.clone(),
+            elevation_quality: self.elevation_quality.clone(),
+            elevation_nodata_samples: self.elevation_nodata_samples,
+            elevation_skip_reason: self.elevation_skip_reason.clone(),
+            arm_grades: self.grades.map_or([None; 3], |g| g.map(Some)),
+            elevation_profile: None,
+            distance_m: None,
+            areas: JunctionAreas::default(),
+            road_names: [None, None, None],
+            highway_class: self.highway_class.clone(),
+            has_bridge: self.way_1_bridge || self.way_2_bridge || self.way_3_bridge,
+            has_tunnel: self.way_1_tunnel || self.way_2_tunnel || self.way_3_tunnel,
+        }
+    }
 }
 
 // テストヘルパー: テストデータ挿入
@@ -203,6 +299,9 @@ async fn insert_test_junction(pool: &PgPool, data: TestJunctionData) -> i64 {
             elevation_diff_1, elevation_diff_2, elevation_diff_3,
             min_angle_index, min_elevation_diff, max_elevation_diff,
             way_1_bridge, way_1_tunnel, way_2_bridge, way_2_tunnel, way_3_bridge, way_3_tunnel,
+            grade_1, grade_2, grade_3,
+            highway_class, elevation_source, elevation_quality,
+            elevation_nodata_samples, elevation_skip_reason,
             created_at
         )
         VALUES (
@@ -211,6 +310,9 @@ async fn insert_test_junction(pool: &PgPool, data: TestJunctionData) -> i64 {
             $14, $15, $16,
             $17, $18, $19,
             $20, $21, $22, $23, $24, $25,
+            $26, $27, $28,
+            $29, $30, $31,
+            $32, $33,
             NOW()
         )
         RETURNING id
@@ -241,6 +343,14 @@ async fn insert_test_junction(pool: &PgPool, data: TestJunctionData) -> i64 {
     .bind(data.way_2_tunnel)
     .bind(data.way_3_bridge)
     .bind(data.way_3_tunnel)
+    .bind(data.grades.map(|g| g[0]))
+    .bind(data.grades.map(|g| g[1]))
+    .bind(data.grades.map(|g| g[2]))
+    .bind(data.highway_class)
+    .bind(data.elevation_source)
+    .bind(data.elevation_quality)
+    .bind(data.elevation_nodata_samples)
+    .bind(data.elevation_skip_reason)
     .fetch_one(pool)
     .await
     .expect("Failed to insert test junction");
@@ -253,6 +363,70 @@ fn create_test_app(pool: PgPool) -> Router {
     y_junction_backend::api::routes::create_router(pool)
 }
 
+// テストデータの保存先（PostgreSQL またはメモリ上のリポジトリ）
+enum TestStore {
+    Postgres(PgPool),
+    Memory(Vec<Junction>),
+}
+
+impl TestStore {
+    async fn postgres() -> Self {
+        Self::Postgres(setup_test_db().await)
+    }
+
+    fn memory() -> Self {
+        Self::Memory(Vec::new())
+    }
+
+    // id は PostgreSQL と同じく 1 からの連番
+    async fn insert(&mut self, data: TestJunctionData) -> i64 {
+        match self {
+            Self::Postgres(pool) => insert_test_junction(pool, data).await,
+            Self::Memory(junctions) => {
+                let id = junctions.len() as i64 + 1;
+                junctions.push(data.to_junction(id));
+                id
+            }
+        }
+    }
+
+    fn app(self) -> Router {
+        match self {
+            Self::Postgres(pool) => create_test_app(pool),
+            Self::Memory(junctions) => {
+                create_router_with(Arc::new(MemoryRepository::new(junctions)))
+            }
+        }
+    }
+}
+
+// `async fn name(store: TestStore)` のテストを両方の保存先で実行する
+// （メモリ上のテストはデータベースなしで並列に、PostgreSQL のテストは直列に実行する）
+macro_rules! backend_tests {
+    ($($name:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(super::TestStore::memory()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            use serial_test::serial;
+
+            $(
+                #[tokio::test]
+                #[serial]
+                async fn $name() {
+                    super::$name(super::TestStore::postgres().await).await;
+                }
+            )*
+        }
+    };
+}
+
 // テストヘルパー: HTTPリクエストを送信してレスポンスを取得
 async fn send_request(app: Router, uri: &str) -> (StatusCode, Value) {
     let response = app
@@ -267,26 +441,56 @@ async fn send_request(app: Router, uri: &str) -> (StatusCode, Value) {
     (status, json)
 }
 
-// ========== GET /api/junctions のテスト（正常系） ==========
+// テストヘルパー: HTTPリクエストを送信してレスポンスのヘッダーとボディをそのまま取得
+async fn send_raw_request(
+    app: Router,
+    uri: &str,
+) -> (StatusCode, axum::http::HeaderMap, axum::body::Bytes) {
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_bbox() {
-    let pool = setup_test_db().await;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    (status, headers, body)
+}
+
+// テストヘルパー: JSONボディ付きのPOSTリクエストを送信してレスポンスを取得
+async fn send_json_request(app: Router, uri: &str, body: &Value) -> (StatusCode, Value) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+
+    (status, json)
+}
+
+// ========== GET /api/junctions のテスト（正常系） ==========
 
+async fn test_get_junctions_with_bbox(mut store: TestStore) {
     // bbox範囲内のデータ
-    insert_test_junction(
-        &pool,
-        TestJunctionData::sharp_type().with_location(35.0, 139.0),
-    )
-    .await;
-    insert_test_junction(
-        &pool,
-        TestJunctionData::sharp_type().with_location(36.0, 140.0),
-    )
-    .await;
+    store
+        .insert(TestJunctionData::sharp_type().with_location(35.0, 139.0))
+        .await;
+    store
+        .insert(TestJunctionData::sharp_type().with_location(36.0, 140.0))
+        .await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     let (status, json) = send_request(app, "/api/junctions?bbox=139.0,35.0,140.0,36.0").await;
 
@@ -296,15 +500,11 @@ async fn test_get_junctions_with_bbox() {
     assert_eq!(json["features"].as_array().unwrap().len(), 2);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_angle_type_filter() {
-    let pool = setup_test_db().await;
-
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::verysharp_type()).await;
+async fn test_get_junctions_with_angle_type_filter(mut store: TestStore) {
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::verysharp_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // angle_type=sharp でフィルタリング
     let (status, json) = send_request(
@@ -317,18 +517,14 @@ async fn test_get_junctions_with_angle_type_filter() {
     assert_eq!(json["total_count"], 1);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_min_angle_filter() {
-    let pool = setup_test_db().await;
-
+async fn test_get_junctions_with_min_angle_filter(mut store: TestStore) {
     // angle_1 = 30
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
 
     // angle_1 = 60
-    insert_test_junction(&pool, TestJunctionData::normal_type()).await;
+    store.insert(TestJunctionData::normal_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // min_angle_lt=50 でフィルタリング（angle_1 < 50）
     let (status, json) = send_request(
@@ -341,17 +537,13 @@ async fn test_get_junctions_with_min_angle_filter() {
     assert_eq!(json["total_count"], 1);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_limit() {
-    let pool = setup_test_db().await;
-
+async fn test_get_junctions_with_limit(mut store: TestStore) {
     // 3件挿入
     for _ in 0..3 {
-        insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+        store.insert(TestJunctionData::sharp_type()).await;
     }
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // limit=2 で制限
     let (status, json) =
@@ -364,11 +556,8 @@ async fn test_get_junctions_with_limit() {
 
 // ========== GET /api/junctions のテスト（異常系） ==========
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_invalid_bbox_format() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_junctions_invalid_bbox_format(store: TestStore) {
+    let app = store.app();
 
     // bbox のフォーマットが不正（3つのパラメータしかない）
     let (status, json) = send_request(app, "/api/junctions?bbox=139.76,35.68,139.77").await;
@@ -380,11 +569,8 @@ async fn test_get_junctions_invalid_bbox_format() {
     );
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_invalid_bbox_range() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_junctions_invalid_bbox_range(store: TestStore) {
+    let app = store.app();
 
     // bbox の範囲が不正（min_lon >= max_lon）
     let (status, json) = send_request(app, "/api/junctions?bbox=140.0,35.0,139.0,36.0").await;
@@ -393,11 +579,8 @@ async fn test_get_junctions_invalid_bbox_range() {
     assert_eq!(json["error"], "Invalid bbox range");
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_bbox_out_of_range() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_junctions_bbox_out_of_range(store: TestStore) {
+    let app = store.app();
 
     // bbox が有効範囲外（lon > 180）
     let (status, json) = send_request(app, "/api/junctions?bbox=181.0,35.0,182.0,36.0").await;
@@ -406,11 +589,8 @@ async fn test_get_junctions_bbox_out_of_range() {
     assert_eq!(json["error"], "bbox out of valid range");
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_invalid_angle_type() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_junctions_invalid_angle_type(store: TestStore) {
+    let app = store.app();
 
     // angle_type が不正
     let (status, json) = send_request(
@@ -423,11 +603,8 @@ async fn test_get_junctions_invalid_angle_type() {
     assert_eq!(json["error"], "Invalid angle_type");
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_invalid_limit() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_junctions_invalid_limit(store: TestStore) {
+    let app = store.app();
 
     // limit が負の数
     let (status, json) =
@@ -439,14 +616,10 @@ async fn test_get_junctions_invalid_limit() {
 
 // ========== GET /api/junctions/:id のテスト ==========
 
-#[tokio::test]
-#[serial]
-async fn test_get_junction_by_id_success() {
-    let pool = setup_test_db().await;
-
-    let id = insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+async fn test_get_junction_by_id_success(mut store: TestStore) {
+    let id = store.insert(TestJunctionData::sharp_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     let (status, json) = send_request(app, &format!("/api/junctions/{}", id)).await;
 
@@ -455,11 +628,8 @@ async fn test_get_junction_by_id_success() {
     assert_eq!(json["properties"]["angles"][0], 35);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junction_by_id_not_found() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_junction_by_id_not_found(store: TestStore) {
+    let app = store.app();
 
     let (status, json) = send_request(app, "/api/junctions/99999").await;
 
@@ -469,19 +639,15 @@ async fn test_get_junction_by_id_not_found() {
 
 // ========== GET /api/stats のテスト ==========
 
-#[tokio::test]
-#[serial]
-async fn test_get_stats_with_data() {
-    let pool = setup_test_db().await;
-
+async fn test_get_stats_with_data(mut store: TestStore) {
     // sharp タイプ × 2
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
 
     // verysharp タイプ × 1
-    insert_test_junction(&pool, TestJunctionData::verysharp_type()).await;
+    store.insert(TestJunctionData::verysharp_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     let (status, json) = send_request(app, "/api/stats").await;
 
@@ -490,11 +656,8 @@ async fn test_get_stats_with_data() {
     assert!(json["by_type"].is_object());
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_stats_no_data() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_get_stats_no_data(store: TestStore) {
+    let app = store.app();
 
     let (status, json) = send_request(app, "/api/stats").await;
 
@@ -503,13 +666,62 @@ async fn test_get_stats_no_data() {
     assert!(json["by_type"].is_object());
 }
 
+async fn test_get_stats_with_filters_and_histograms(mut store: TestStore) {
+    store
+        .insert(TestJunctionData::verysharp_type().with_highway_class("primary"))
+        .await; // 20°
+    store.insert(TestJunctionData::sharp_type()).await; // 35°
+    store
+        .insert(TestJunctionData::sharp_type().with_location(36.5, 140.5))
+        .await; // 範囲外
+
+    let app = store.app();
+
+    let (status, json) = send_request(app.clone(), "/api/stats?bbox=138.9,34.9,139.1,35.1").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 2);
+    assert_eq!(json["by_type"]["verysharp"], 1);
+    assert_eq!(json["by_type"]["sharp"], 1);
+
+    let min_angle = json["histograms"]["min_angle"].as_array().unwrap();
+    assert_eq!(min_angle.len(), 12);
+    assert_eq!(min_angle[4]["from"], 20.0);
+    assert_eq!(min_angle[4]["to"], 25.0);
+    assert_eq!(min_angle[4]["count"], 1);
+    assert_eq!(min_angle[7]["count"], 1);
+    assert_eq!(min_angle[0]["count"], 0);
+
+    // 最小角高低差はどちらも10m（最後のビンは上限なし）
+    let elevation_diff = json["histograms"]["elevation_diff"].as_array().unwrap();
+    let last = elevation_diff.last().unwrap();
+    assert_eq!(last["from"], 10.0);
+    assert!(last["to"].is_null());
+    assert_eq!(last["count"], 2);
+
+    assert_eq!(json["histograms"]["highway_class"]["primary"], 1);
+    assert_eq!(json["histograms"]["highway_class"]["unknown"], 1);
+
+    // GET /api/junctions と同じフィルタが効く
+    let (status, json) = send_request(app.clone(), "/api/stats?angle_type=sharp").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 2);
+    assert!(json["by_type"]["verysharp"].is_null());
+
+    let (status, json) = send_request(app.clone(), "/api/stats?bbox=invalid").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].is_string());
+
+    // 一致したすべてのY字路の統計なので、件数や並び順は指定できない
+    let (status, json) = send_request(app, "/api/stats?angle_type=sharp&limit=50").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "limit cannot be used with stats");
+}
+
 // ========== エラーレスポンスフォーマットのテスト ==========
 
-#[tokio::test]
-#[serial]
-async fn test_error_response_format() {
-    let pool = setup_test_db().await;
-    let app = create_test_app(pool);
+async fn test_error_response_format(store: TestStore) {
+    let app = store.app();
 
     let (status, json) = send_request(app, "/api/junctions?bbox=invalid").await;
 
@@ -520,16 +732,12 @@ async fn test_error_response_format() {
 
 // ========== 最小角の高低差フィルタのテスト ==========
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_min_angle_elevation_diff_filter() {
-    let pool = setup_test_db().await;
-
+async fn test_get_junctions_with_min_angle_elevation_diff_filter(mut store: TestStore) {
     // min_angle_elevation_diff は GENERATED カラムなので、テストデータ挿入後にDBで計算される
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::normal_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::normal_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // min_angle_elevation_diff >= 0 でフィルタリング（全件取得）
     let (status, json) = send_request(
@@ -543,14 +751,10 @@ async fn test_get_junctions_with_min_angle_elevation_diff_filter() {
     assert_eq!(json["features"].as_array().unwrap().len(), 2);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_response_includes_elevation_data() {
-    let pool = setup_test_db().await;
-
-    let id = insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+async fn test_get_junctions_response_includes_elevation_data(mut store: TestStore) {
+    let id = store.insert(TestJunctionData::sharp_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     let (status, json) = send_request(app, &format!("/api/junctions/{}", id)).await;
 
@@ -566,16 +770,12 @@ async fn test_get_junctions_response_includes_elevation_data() {
     assert!(properties["min_angle_elevation_diff"].is_number());
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_combined_filters_with_elevation() {
-    let pool = setup_test_db().await;
-
-    insert_test_junction(&pool, TestJunctionData::verysharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::normal_type()).await;
+async fn test_get_junctions_combined_filters_with_elevation(mut store: TestStore) {
+    store.insert(TestJunctionData::verysharp_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::normal_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // angle_type=sharp AND min_angle_elevation_diff=0 で複合フィルタリング
     let (status, json) = send_request(
@@ -588,15 +788,11 @@ async fn test_get_junctions_combined_filters_with_elevation() {
     assert_eq!(json["total_count"], 1); // sharp タイプが1件
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_max_angle_elevation_diff_filter() {
-    let pool = setup_test_db().await;
-
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::normal_type()).await;
+async fn test_get_junctions_with_max_angle_elevation_diff_filter(mut store: TestStore) {
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::normal_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // max_angle_elevation_diff <= 100 でフィルタリング（全件取得）
     let (status, json) = send_request(
@@ -610,15 +806,11 @@ async fn test_get_junctions_with_max_angle_elevation_diff_filter() {
     assert_eq!(json["features"].as_array().unwrap().len(), 2);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_elevation_diff_range() {
-    let pool = setup_test_db().await;
-
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
-    insert_test_junction(&pool, TestJunctionData::normal_type()).await;
+async fn test_get_junctions_with_elevation_diff_range(mut store: TestStore) {
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::normal_type()).await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // 範囲指定: 0 <= min_angle_elevation_diff <= 100
     let (status, json) = send_request(
@@ -632,12 +824,8 @@ async fn test_get_junctions_with_elevation_diff_range() {
     assert_eq!(json["features"].as_array().unwrap().len(), 2);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_invalid_elevation_diff_range() {
-    let pool = setup_test_db().await;
-
-    let app = create_test_app(pool);
+async fn test_get_junctions_with_invalid_elevation_diff_range(store: TestStore) {
+    let app = store.app();
 
     // min > max エラー
     let (status, json) = send_request(
@@ -653,12 +841,8 @@ async fn test_get_junctions_with_invalid_elevation_diff_range() {
         .contains("min_angle_elevation_diff must be <= max_angle_elevation_diff"));
 }
 
-#[tokio::test]
-#[serial]
-async fn test_get_junctions_with_max_elevation_diff_negative() {
-    let pool = setup_test_db().await;
-
-    let app = create_test_app(pool);
+async fn test_get_junctions_with_max_elevation_diff_negative(store: TestStore) {
+    let app = store.app();
 
     // max < 0 エラー
     let (status, json) = send_request(
@@ -674,66 +858,81 @@ async fn test_get_junctions_with_max_elevation_diff_negative() {
         .contains("max_angle_elevation_diff must be >= 0"));
 }
 
-#[tokio::test]
-#[serial]
-async fn test_bridge_tunnel_excluded_with_elevation_filter() {
-    let pool = setup_test_db().await;
-
+async fn test_bridge_tunnel_excluded_with_include_toggles(mut store: TestStore) {
     // Insert normal junction
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
 
-    // Insert junction with bridge (should be excluded when using elevation filter)
-    insert_test_junction(
-        &pool,
-        TestJunctionData::sharp_type().with_bridge_tunnel(true, false, false, false, false, false),
+    // Insert junction with bridge
+    store
+        .insert(
+            TestJunctionData::sharp_type()
+                .with_bridge_tunnel(true, false, false, false, false, false),
+        )
+        .await;
+
+    // Insert junction with tunnel
+    store
+        .insert(
+            TestJunctionData::sharp_type()
+                .with_bridge_tunnel(false, true, false, false, false, false),
+        )
+        .await;
+
+    let app = store.app();
+
+    // An elevation filter alone no longer excludes bridges and tunnels
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&min_angle_elevation_diff=0",
     )
     .await;
 
-    // Insert junction with tunnel (should be excluded when using elevation filter)
-    insert_test_junction(
-        &pool,
-        TestJunctionData::sharp_type().with_bridge_tunnel(false, true, false, false, false, false),
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["features"].as_array().unwrap().len(), 3);
+
+    // Exclude bridges only
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&include_bridges=false",
     )
     .await;
 
-    let app = create_test_app(pool);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["features"].as_array().unwrap().len(), 2);
 
-    // With elevation filter: bridges and tunnels should be excluded
+    // Exclude both: only the normal junction is returned
     let (status, json) = send_request(
         app,
-        "/api/junctions?bbox=138.0,34.0,140.0,36.0&min_angle_elevation_diff=0",
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&min_angle_elevation_diff=0\
+         &include_bridges=false&include_tunnels=false",
     )
     .await;
 
     assert_eq!(status, StatusCode::OK);
-    let features = json["features"].as_array().unwrap();
-    // Only 1 junction should be returned (the normal one)
-    assert_eq!(features.len(), 1);
+    assert_eq!(json["features"].as_array().unwrap().len(), 1);
 }
 
-#[tokio::test]
-#[serial]
-async fn test_bridge_tunnel_included_without_elevation_filter() {
-    let pool = setup_test_db().await;
-
+async fn test_bridge_tunnel_included_without_elevation_filter(mut store: TestStore) {
     // Insert normal junction
-    insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::sharp_type()).await;
 
     // Insert junction with bridge
-    insert_test_junction(
-        &pool,
-        TestJunctionData::sharp_type().with_bridge_tunnel(true, false, false, false, false, false),
-    )
-    .await;
+    store
+        .insert(
+            TestJunctionData::sharp_type()
+                .with_bridge_tunnel(true, false, false, false, false, false),
+        )
+        .await;
 
     // Insert junction with tunnel
-    insert_test_junction(
-        &pool,
-        TestJunctionData::sharp_type().with_bridge_tunnel(false, true, false, false, false, false),
-    )
-    .await;
+    store
+        .insert(
+            TestJunctionData::sharp_type()
+                .with_bridge_tunnel(false, true, false, false, false, false),
+        )
+        .await;
 
-    let app = create_test_app(pool);
+    let app = store.app();
 
     // Without elevation filter: all junctions should be included
     let (status, json) = send_request(app, "/api/junctions?bbox=138.0,34.0,140.0,36.0").await;
@@ -743,3 +942,1148 @@ async fn test_bridge_tunnel_included_without_elevation_filter() {
     // All 3 junctions should be returned
     assert_eq!(features.len(), 3);
 }
+
+// ========== 道路ごとの勾配のテスト ==========
+
+async fn test_get_junctions_with_min_arm_grade_filter(mut store: TestStore) {
+    // 9.5%で上る道路を含むY字路と、緩やかな勾配のみのY字路
+    store
+        .insert(TestJunctionData::sharp_type().with_grades([9.5, -1.0, -4.0]))
+        .await;
+    store
+        .insert(TestJunctionData::normal_type().with_grades([3.0, 1.0, -7.0]))
+        .await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&min_arm_grade=8",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 1);
+    let properties = &json["features"][0]["properties"];
+    assert_eq!(properties["angle_type"], "sharp");
+    assert_eq!(properties["arm_slopes"][0], "uphill");
+    assert_eq!(properties["arm_slopes"][1], "flat");
+    assert_eq!(properties["arm_slopes"][2], "downhill");
+}
+
+// ========== 標高の取得状況のテスト ==========
+
+async fn test_get_elevation_stats(mut store: TestStore) {
+    store
+        .insert(TestJunctionData::sharp_type().with_elevation_source("DEM5A", "bilinear", 2))
+        .await;
+    let no_tile = store
+        .insert(TestJunctionData::normal_type().without_elevation(Some("no_tile")))
+        .await;
+    // 未処理のY字路
+    store
+        .insert(TestJunctionData::verysharp_type().without_elevation(None))
+        .await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(app.clone(), "/api/stats/elevation").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 3);
+    assert_eq!(json["with_elevation"], 1);
+    assert_eq!(json["unprocessed"], 1);
+    assert_eq!(json["by_source"]["DEM5A"], 1);
+    assert_eq!(json["by_quality"]["bilinear"], 1);
+    assert_eq!(json["skipped"]["no_tile"], 1);
+    assert_eq!(json["with_nodata_samples"], 1);
+
+    let (status, json) = send_request(app, &format!("/api/junctions/{}", no_tile)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["properties"]["elevation_skip_reason"], "no_tile");
+    assert!(json["properties"]["elevation"].is_null());
+}
+
+// ========== 標高・高低差・局所勾配の範囲フィルタのテスト ==========
+
+async fn test_get_junctions_with_elevation_range_filter(mut store: TestStore) {
+    store.insert(TestJunctionData::verysharp_type()).await; // 50m
+    store.insert(TestJunctionData::sharp_type()).await; // 100m
+    store.insert(TestJunctionData::normal_type()).await; // 200m
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&elevation_gte=80&elevation_lte=150",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 1);
+    assert_eq!(json["features"][0]["properties"]["elevation"], 100.0);
+}
+
+async fn test_get_junctions_with_elevation_diff_and_slope_filters(mut store: TestStore) {
+    // max_elevation_diff 5m（局所勾配50%）と10m（局所勾配100%）
+    store.insert(TestJunctionData::sharp_type()).await;
+    store.insert(TestJunctionData::normal_type()).await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&max_elevation_diff_gte=8",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 1);
+    assert_eq!(json["features"][0]["properties"]["angle_type"], "normal");
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&local_slope_lte=60",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 1);
+    let properties = &json["features"][0]["properties"];
+    assert_eq!(properties["angle_type"], "sharp");
+    assert_eq!(properties["local_slope"], 50.0);
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&min_elevation_diff_gte=0&min_elevation_diff_lte=0",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 2);
+}
+
+async fn test_get_junctions_with_invalid_range_filters(store: TestStore) {
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&elevation_gte=200&elevation_lte=100",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "elevation_gte must be <= elevation_lte");
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&local_slope_lte=-1",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "local_slope_lte must be >= 0");
+}
+
+// ========== 並び順とカーソルページングのテスト ==========
+
+async fn test_get_junctions_paginates_with_cursor(mut store: TestStore) {
+    let normal = store.insert(TestJunctionData::normal_type()).await; // 60°
+    let verysharp = store.insert(TestJunctionData::verysharp_type()).await; // 20°
+    let sharp = store.insert(TestJunctionData::sharp_type()).await; // 35°
+
+    let app = store.app();
+
+    let mut ids = Vec::new();
+    let mut uri = "/api/junctions?bbox=138.0,34.0,140.0,36.0&sort=min_angle&limit=2".to_string();
+    loop {
+        let (status, json) = send_request(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        // 件数は最初のページにだけ付き、カーソルで続きを取得したページでは null
+        if ids.is_empty() {
+            assert_eq!(json["total_count"].as_i64().unwrap(), 3);
+        } else {
+            assert!(json["total_count"].is_null());
+        }
+
+        for feature in json["features"].as_array().unwrap() {
+            ids.push(feature["properties"]["id"].as_i64().unwrap());
+        }
+
+        match json["next_cursor"].as_str() {
+            Some(cursor) => {
+                uri = format!(
+                    "/api/junctions?bbox=138.0,34.0,140.0,36.0&sort=min_angle&limit=2&cursor={}",
+                    cursor
+                );
+            }
+            None => break,
+        }
+    }
+
+    assert_eq!(ids, vec![verysharp, sharp, normal]);
+}
+
+async fn test_get_junctions_sorted_by_distance(mut store: TestStore) {
+    let far = store
+        .insert(TestJunctionData::sharp_type().with_location(35.5, 139.5))
+        .await;
+    let near = store
+        .insert(TestJunctionData::sharp_type().with_location(35.01, 139.01))
+        .await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&sort=distance&center=35.0,139.0",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    let features = json["features"].as_array().unwrap();
+    assert_eq!(features[0]["properties"]["id"].as_i64().unwrap(), near);
+    assert_eq!(features[1]["properties"]["id"].as_i64().unwrap(), far);
+    assert!(json["next_cursor"].is_null());
+}
+
+async fn test_get_junctions_invalid_sort_and_cursor(store: TestStore) {
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&sort=distance",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "sort=distance requires center");
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&sort=random",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "Invalid sort");
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&cursor=garbage",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "Invalid cursor");
+}
+
+// ========== 半径検索と最近傍検索のテスト ==========
+
+async fn test_get_junctions_within_radius(mut store: TestStore) {
+    // 基準点 (35.0, 139.0) から約550mと約11km
+    let near = store
+        .insert(TestJunctionData::sharp_type().with_location(35.005, 139.0))
+        .await;
+    store
+        .insert(TestJunctionData::sharp_type().with_location(35.1, 139.0))
+        .await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?center=35.0,139.0&radius_m=1000&angle_type=sharp",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 1);
+    let properties = &json["features"][0]["properties"];
+    assert_eq!(properties["id"].as_i64().unwrap(), near);
+    let distance_m = properties["distance_m"].as_f64().unwrap();
+    assert!((distance_m - 554.0).abs() < 5.0, "got {}", distance_m);
+}
+
+async fn test_get_nearest_junctions(mut store: TestStore) {
+    let sharp_far = store
+        .insert(TestJunctionData::sharp_type().with_location(35.02, 139.0))
+        .await;
+    let sharp_near = store
+        .insert(TestJunctionData::sharp_type().with_location(35.01, 139.0))
+        .await;
+    // 最も近いが normal なのでフィルタで除外される
+    store
+        .insert(TestJunctionData::normal_type().with_location(35.001, 139.0))
+        .await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions/nearest?lat=35.0&lon=139.0&k=5&angle_type=verysharp,sharp",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    let features = json["features"].as_array().unwrap();
+    assert_eq!(features.len(), 2);
+    assert_eq!(
+        features[0]["properties"]["id"].as_i64().unwrap(),
+        sharp_near
+    );
+    assert_eq!(features[1]["properties"]["id"].as_i64().unwrap(), sharp_far);
+    assert!(
+        features[0]["properties"]["distance_m"].as_f64().unwrap()
+            < features[1]["properties"]["distance_m"].as_f64().unwrap()
+    );
+}
+
+async fn test_get_junctions_invalid_radius_search(store: TestStore) {
+    let app = store.app();
+
+    let (status, json) = send_request(app.clone(), "/api/junctions?radius_m=1000").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "radius_m requires center");
+
+    let (status, json) = send_request(app.clone(), "/api/junctions").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bbox or center and radius_m is required");
+
+    let (status, json) =
+        send_request(app.clone(), "/api/junctions/nearest?lat=35.0&lon=139.0&k=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "k must be between 1 and 100");
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions/nearest?lat=35.0&lon=139.0&limit=5&sort=min_angle",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "limit and sort cannot be used with nearest");
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions/nearest?lat=35.0&lon=139.0&bbox=138.0,34.0,140.0,36.0",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bbox cannot be used with nearest");
+}
+
+// ========== POST /api/junctions/search のテスト ==========
+
+async fn test_search_junctions_within_polygon(mut store: TestStore) {
+    // 三角形の内側と外側（bbox では両方含まれる位置）
+    let inside = store
+        .insert(TestJunctionData::sharp_type().with_location(35.1, 139.1))
+        .await;
+    store
+        .insert(TestJunctionData::sharp_type().with_location(35.8, 139.8))
+        .await;
+    store
+        .insert(TestJunctionData::normal_type().with_location(35.1, 139.2))
+        .await;
+
+    let app = store.app();
+
+    let body = serde_json::json!({
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [[[139.0, 35.0], [140.0, 35.0], [139.0, 36.0], [139.0, 35.0]]]
+        },
+        "angle_type": "verysharp,sharp",
+        "limit": 10
+    });
+    let (status, json) = send_json_request(app, "/api/junctions/search", &body).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["type"], "FeatureCollection");
+    assert_eq!(json["total_count"].as_i64().unwrap(), 1);
+    assert_eq!(
+        json["features"][0]["properties"]["id"].as_i64().unwrap(),
+        inside
+    );
+}
+
+async fn test_search_junctions_invalid_geometry(store: TestStore) {
+    let app = store.app();
+
+    let body = serde_json::json!({
+        "geometry": { "type": "LineString", "coordinates": [[139.0, 35.0], [140.0, 36.0]] }
+    });
+    let (status, json) = send_json_request(app.clone(), "/api/junctions/search", &body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json["error"],
+        "geometry must be a GeoJSON Polygon or MultiPolygon"
+    );
+
+    // 自己交差する蝶ネクタイ型
+    let body = serde_json::json!({
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [[[139.0, 35.0], [140.0, 36.0], [140.0, 35.0], [139.0, 36.0], [139.0, 35.0]]]
+        }
+    });
+    let (status, json) = send_json_request(app, "/api/junctions/search", &body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json["error"],
+        "geometry is not a valid polygon (e.g. self-intersecting)"
+    );
+}
+
+// ========== GET /api/tiles/:z/:x/:y.mvt のテスト ==========
+
+async fn test_get_tile(mut store: TestStore) {
+    // ズーム15のタイル (29105, 12903) に含まれる東京駅付近
+    store
+        .insert(TestJunctionData::sharp_type().with_location(35.681236, 139.767125))
+        .await;
+
+    let app = store.app();
+
+    let (status, headers, body) =
+        send_raw_request(app.clone(), "/api/tiles/15/29105/12903.mvt").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers["content-type"],
+        "application/vnd.mapbox-vector-tile"
+    );
+    assert_eq!(headers["cache-control"], "public, max-age=3600");
+    assert!(!body.is_empty());
+    // レイヤー名とプロパティ名がタイルに含まれる
+    let contains = |needle: &[u8]| body.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(b"junctions"));
+    assert!(contains(b"angle_type"));
+
+    // フィルタに合わないY字路はタイルに含まれない
+    let (status, _, body) = send_raw_request(
+        app.clone(),
+        "/api/tiles/15/29105/12903.mvt?angle_type=normal",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.is_empty());
+
+    // 隣のタイルは空
+    let (status, _, body) = send_raw_request(app, "/api/tiles/15/29106/12903.mvt").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.is_empty());
+}
+
+async fn test_get_tile_invalid_path(store: TestStore) {
+    let app = store.app();
+
+    let (status, _, _) = send_raw_request(app.clone(), "/api/tiles/15/29105/12903.png").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, json) = send_request(app, "/api/tiles/1/2/0.mvt").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "Invalid tile coordinates");
+}
+
+async fn test_get_tile_rejects_list_parameters(store: TestStore) {
+    let app = store.app();
+
+    // エラーには指定されたパラメータだけが挙がる
+    for (query, params) in [
+        ("bbox=138.0,34.0,140.0,36.0&limit=10", "bbox and limit"),
+        ("center=35.68,139.76&radius_m=500", "center and radius_m"),
+        ("sort=min_angle", "sort"),
+        ("sampling=grid&cursor=abc", "cursor and sampling"),
+    ] {
+        let (status, json) = send_request(
+            app.clone(),
+            &format!("/api/tiles/15/29105/12903.mvt?{}", query),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+        assert_eq!(
+            json["error"],
+            format!("{} cannot be used with tiles", params)
+        );
+    }
+}
+
+// ========== GET /api/junctions/clusters のテスト ==========
+
+async fn test_get_junction_clusters(mut store: TestStore) {
+    // ズーム8（セル約39km）で同じセルに入る2件と、離れた1件
+    let verysharp = store
+        .insert(TestJunctionData::verysharp_type().with_location(35.0, 139.0))
+        .await;
+    store
+        .insert(TestJunctionData::sharp_type().with_location(35.001, 139.001))
+        .await;
+    store
+        .insert(TestJunctionData::sharp_type().with_location(35.5, 139.5))
+        .await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions/clusters?bbox=138.0,34.0,140.0,36.0&zoom=8",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 3);
+    let features = json["features"].as_array().unwrap();
+    assert_eq!(features.len(), 2);
+    // 件数の多いセルが先頭
+    let properties = &features[0]["properties"];
+    assert_eq!(properties["count"], 2);
+    assert_eq!(properties["min_angle"], 20);
+    assert_eq!(properties["sharpest_id"].as_i64().unwrap(), verysharp);
+    assert_eq!(properties["by_type"]["verysharp"], 1);
+    assert_eq!(properties["by_type"]["sharp"], 1);
+    assert_eq!(properties["by_type"]["normal"], 0);
+    let lat = features[0]["geometry"]["coordinates"][1].as_f64().unwrap();
+    assert!((lat - 35.0005).abs() < 1e-6);
+
+    // フィルタはクラスタリングの前に適用される
+    let (status, json) = send_request(
+        app,
+        "/api/junctions/clusters?bbox=138.0,34.0,140.0,36.0&zoom=8&angle_type=sharp",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 2);
+    assert_eq!(json["features"].as_array().unwrap().len(), 2);
+}
+
+async fn test_get_junction_clusters_invalid_query(store: TestStore) {
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions/clusters?bbox=138.0,34.0,140.0,36.0&zoom=30",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "zoom must be between 0 and 22");
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions/clusters?bbox=138.0,34.0,140.0,36.0&zoom=8&limit=10&sampling=grid",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json["error"],
+        "limit and sampling cannot be used with clusters"
+    );
+}
+
+// ========== 間引き（サンプリング）のテスト ==========
+
+async fn test_get_junctions_sampled_when_exceeding_limit(mut store: TestStore) {
+    let normal = store
+        .insert(TestJunctionData::normal_type().with_location(35.0, 139.0))
+        .await;
+    let verysharp = store
+        .insert(TestJunctionData::verysharp_type().with_location(35.0, 139.01))
+        .await;
+    let sharp = store
+        .insert(TestJunctionData::sharp_type().with_location(35.5, 139.5))
+        .await;
+
+    let app = store.app();
+
+    // sharpest: 最も鋭い2件
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&limit=2&sampling=sharpest",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"].as_i64().unwrap(), 3);
+    assert_eq!(json["sampled"], true);
+    assert_eq!(json["sampling"], "sharpest");
+    assert!(json["next_cursor"].is_null());
+    let ids: Vec<i64> = json["features"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["properties"]["id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![verysharp, sharp]);
+
+    // grid: 近接する2件からは鋭い方だけが選ばれる
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&limit=2&sampling=grid",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["sampled"], true);
+    let ids: Vec<i64> = json["features"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["properties"]["id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![verysharp, sharp]);
+    assert!(!ids.contains(&normal));
+
+    // random: 件数だけ確認
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&limit=2&sampling=random",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["features"].as_array().unwrap().len(), 2);
+
+    // limit 以内なら間引かない
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&limit=10&sampling=grid",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["sampled"], false);
+    assert!(json["sampling"].is_null());
+    assert_eq!(json["features"].as_array().unwrap().len(), 3);
+}
+
+// テストヘルパー: レスポンスの Y字路の id
+fn feature_ids(json: &Value) -> Vec<i64> {
+    json["features"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["properties"]["id"].as_i64().unwrap())
+        .collect()
+}
+
+async fn test_get_junctions_grid_sampling_tops_up_to_limit(mut store: TestStore) {
+    // 近接する3件と離れた1件: limit=3 では 2x2 のセルのうち2つしか埋まらない
+    let verysharp = store
+        .insert(TestJunctionData::verysharp_type().with_location(35.0, 139.0))
+        .await;
+    let sharp = store
+        .insert(TestJunctionData::sharp_type().with_location(35.0, 139.001))
+        .await;
+    let clustered_normal = store
+        .insert(TestJunctionData::normal_type().with_location(35.0, 139.002))
+        .await;
+    let far_normal = store
+        .insert(TestJunctionData::normal_type().with_location(35.5, 139.5))
+        .await;
+
+    let app = store.app();
+
+    // 各セルで最も鋭い2件に、残りから最も鋭い1件を補う
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&limit=3&sampling=grid",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["sampled"], true);
+    assert_eq!(feature_ids(&json), vec![verysharp, sharp, far_normal]);
+    assert!(!feature_ids(&json).contains(&clustered_normal));
+}
+
+async fn test_get_junctions_grid_sampling_at_single_location(mut store: TestStore) {
+    // 全件が同じ位置（セルの大きさが決まらない）
+    let normal = store.insert(TestJunctionData::normal_type()).await;
+    let verysharp = store.insert(TestJunctionData::verysharp_type()).await;
+    let sharp = store.insert(TestJunctionData::sharp_type()).await;
+
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&limit=2&sampling=grid",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["sampled"], true);
+    assert_eq!(feature_ids(&json), vec![verysharp, sharp]);
+    assert!(!feature_ids(&json).contains(&normal));
+}
+
+async fn test_get_junctions_invalid_sampling(store: TestStore) {
+    let app = store.app();
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&sampling=every_other",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "Invalid sampling");
+
+    let (status, json) = send_request(
+        app,
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&sampling=grid&cursor=eyJzb3J0IjoiaWQifQ",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "sampling cannot be used with cursor");
+}
+
+// ========== 行政区域のテスト ==========
+
+// (min_lon, min_lat)-(max_lon, max_lat) の矩形の行政区域を追加
+async fn insert_test_area(
+    pool: &PgPool,
+    relation_id: i64,
+    code: &str,
+    name: &str,
+    admin_level: i16,
+    bounds: (f64, f64, f64, f64),
+) {
+    sqlx::query(
+        "INSERT INTO admin_areas (relation_id, code, name, admin_level, geometry) \
+         VALUES ($1, $2, $3, $4, ST_Multi(ST_MakeEnvelope($5, $6, $7, $8, 4326))::geography)",
+    )
+    .bind(relation_id)
+    .bind(code)
+    .bind(name)
+    .bind(admin_level)
+    .bind(bounds.0)
+    .bind(bounds.1)
+    .bind(bounds.2)
+    .bind(bounds.3)
+    .execute(pool)
+    .await
+    .expect("Failed to insert admin area");
+}
+
+// 都道府県 JP-13 の中に市 13101 と 13102、JP-13 の外に JP-14
+async fn setup_test_areas(pool: &PgPool) -> (i64, i64, i64) {
+    let in_city_a = insert_test_junction(
+        pool,
+        TestJunctionData::verysharp_type().with_location(35.05, 139.05),
+    )
+    .await;
+    let in_city_b = insert_test_junction(
+        pool,
+        TestJunctionData::sharp_type().with_location(35.05, 139.15),
+    )
+    .await;
+    let other_pref = insert_test_junction(
+        pool,
+        TestJunctionData::verysharp_type().with_location(35.05, 139.35),
+    )
+    .await;
+    insert_test_junction(
+        pool,
+        TestJunctionData::verysharp_type().with_location(35.06, 139.06),
+    )
+    .await;
+
+    insert_test_area(pool, 1, "JP-13", "東京都", 4, (139.0, 35.0, 139.3, 35.3)).await;
+    insert_test_area(pool, 2, "JP-14", "神奈川県", 4, (139.3, 35.0, 139.6, 35.3)).await;
+    insert_test_area(pool, 3, "13101", "千代田区", 7, (139.0, 35.0, 139.1, 35.1)).await;
+    insert_test_area(pool, 4, "13102", "中央区", 7, (139.1, 35.0, 139.2, 35.1)).await;
+
+    y_junction_backend::importer::inserter::assign_admin_areas(pool)
+        .await
+        .expect("Failed to assign admin areas");
+
+    (in_city_a, in_city_b, other_pref)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_junctions_include_areas_and_filter_by_area() {
+    let pool = setup_test_db().await;
+    let (in_city_a, in_city_b, other_pref) = setup_test_areas(&pool).await;
+    let app = create_test_app(pool);
+
+    let (status, json) = send_request(app.clone(), &format!("/api/junctions/{}", in_city_b)).await;
+    assert_eq!(status, StatusCode::OK);
+    let areas = &json["properties"]["areas"];
+    assert_eq!(areas["prefecture"]["code"], "JP-13");
+    assert_eq!(areas["prefecture"]["name"], "東京都");
+    assert_eq!(areas["city"]["code"], "13102");
+    assert!(areas["ward"].is_null());
+
+    // area だけで（bbox なしで）検索できる
+    let (status, json) = send_request(app.clone(), "/api/junctions?area=13101").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 2);
+    let ids: Vec<i64> = json["features"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["properties"]["id"].as_i64().unwrap())
+        .collect();
+    assert!(ids.contains(&in_city_a));
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=138.0,34.0,140.0,36.0&area=JP-14",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 1);
+    assert_eq!(
+        json["features"][0]["properties"]["id"].as_i64().unwrap(),
+        other_pref
+    );
+
+    let (status, json) = send_request(app, "/api/stats?area=JP-13").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 3);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_areas_with_counts() {
+    let pool = setup_test_db().await;
+    setup_test_areas(&pool).await;
+    let app = create_test_app(pool);
+
+    let (status, json) = send_request(app.clone(), "/api/areas").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["level"], "prefecture");
+    let areas = json["areas"].as_array().unwrap();
+    assert_eq!(areas.len(), 2);
+    assert_eq!(areas[0]["code"], "JP-13");
+    assert_eq!(areas[0]["total_count"], 3);
+    assert_eq!(areas[0]["by_type"]["verysharp"], 2);
+    assert_eq!(areas[0]["by_type"]["sharp"], 1);
+    assert_eq!(areas[1]["code"], "JP-14");
+    assert_eq!(areas[1]["total_count"], 1);
+
+    let (status, json) = send_request(app.clone(), "/api/areas?level=7").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["level"], "city");
+    assert_eq!(json["areas"].as_array().unwrap().len(), 2);
+
+    // 区の境界がなければ空
+    let (status, json) = send_request(app.clone(), "/api/areas?level=ward").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["areas"].as_array().unwrap().is_empty());
+
+    let (status, json) = send_request(app, "/api/areas?level=town").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "Invalid level");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_area_ranking() {
+    let pool = setup_test_db().await;
+    setup_test_areas(&pool).await;
+    let app = create_test_app(pool);
+
+    // VerySharp が多い市区町村
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/areas/ranking?level=city&angle_type=verysharp",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let areas = json["areas"].as_array().unwrap();
+    assert_eq!(areas.len(), 1);
+    assert_eq!(areas[0]["code"], "13101");
+    assert_eq!(areas[0]["name"], "千代田区");
+    assert_eq!(areas[0]["total_count"], 2);
+
+    let (status, json) =
+        send_request(app.clone(), "/api/areas/ranking?level=prefecture&limit=1").await;
+    assert_eq!(status, StatusCode::OK);
+    let areas = json["areas"].as_array().unwrap();
+    assert_eq!(areas.len(), 1);
+    assert_eq!(areas[0]["code"], "JP-13");
+    assert_eq!(areas[0]["total_count"], 3);
+
+    // 都道府県内の市区町村のランキング
+    let (status, json) =
+        send_request(app.clone(), "/api/areas/ranking?level=city&area=JP-14").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["areas"].as_array().unwrap().is_empty());
+
+    // 件数は上限で切り詰めずにエラーにする
+    let (status, json) =
+        send_request(app.clone(), "/api/areas/ranking?level=prefecture&limit=500").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "limit must be between 1 and 100");
+
+    let (status, json) = send_request(
+        app,
+        "/api/areas/ranking?level=prefecture&sort=min_angle&sampling=grid",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json["error"],
+        "sort and sampling cannot be used with ranking"
+    );
+}
+
+// ========== 道路名検索のテスト ==========
+
+#[tokio::test]
+#[serial]
+async fn test_get_junctions_by_road_name() {
+    let pool = setup_test_db().await;
+
+    let on_nakasendo = insert_test_junction(&pool, TestJunctionData::sharp_type()).await;
+    let other = insert_test_junction(&pool, TestJunctionData::verysharp_type()).await;
+
+    sqlx::query(
+        "UPDATE y_junctions SET way_1_name = '旧中山道', way_3_name = '旧中山道', \
+         way_2_name = '県道1号', \
+         road_search_text = E'旧中山道\\nKyu-Nakasendo\\nきゅうなかせんどう\\n県道1号' \
+         WHERE id = $1",
+    )
+    .bind(on_nakasendo)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("UPDATE y_junctions SET way_1_name = '100%通り', road_search_text = '100%通り' WHERE id = $1")
+        .bind(other)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_test_app(pool);
+
+    // 名前・英語名・読みのいずれでも検索できる（bbox なしでも可）
+    for q in ["中山道", "nakasendo", "なかせんどう"] {
+        let uri = format!("/api/junctions?q={}", urlencode(q));
+        let (status, json) = send_request(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK, "q={}", q);
+        assert_eq!(json["total_count"], 1, "q={}", q);
+        let properties = &json["features"][0]["properties"];
+        assert_eq!(properties["id"].as_i64().unwrap(), on_nakasendo);
+        assert_eq!(properties["label"], "旧中山道と県道1号の分岐");
+    }
+
+    // LIKE のワイルドカードは文字として扱う
+    let (status, json) =
+        send_request(app.clone(), &format!("/api/junctions?q={}", urlencode("%"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 1);
+    assert_eq!(
+        json["features"][0]["properties"]["id"].as_i64().unwrap(),
+        other
+    );
+
+    let (status, json) = send_request(app, "/api/junctions?q=%20").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "q must be 1 to 100 characters");
+}
+
+// クエリパラメータ用のパーセントエンコード
+fn urlencode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// ========== エクスポートのテスト ==========
+
+async fn test_export_junctions_in_each_format(mut store: TestStore) {
+    for _ in 0..3 {
+        store.insert(TestJunctionData::sharp_type()).await;
+    }
+    store.insert(TestJunctionData::verysharp_type()).await;
+
+    let app = store.app();
+
+    // 条件に一致するすべての行が返る
+    let (status, headers, body) = send_raw_request(
+        app.clone(),
+        "/api/junctions/export?bbox=138.0,34.0,140.0,36.0&angle_type=sharp",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "application/geo+json");
+    assert!(headers["content-disposition"]
+        .to_str()
+        .unwrap()
+        .contains("y-junctions.geojson"));
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let features = json["features"].as_array().unwrap();
+    assert_eq!(features.len(), 3);
+    assert!(features[0]["properties"]["streetview_url"].is_string());
+
+    let (status, headers, body) = send_raw_request(
+        app.clone(),
+        "/api/junctions/export?format=csv&bbox=138.0,34.0,140.0,36.0",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/csv"));
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(csv.trim_end().split("\r\n").count(), 5);
+
+    let (status, _, body) = send_raw_request(
+        app.clone(),
+        "/api/junctions/export?format=kml&bbox=138.0,34.0,140.0,36.0",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let kml = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(kml.matches("<Placemark>").count(), 4);
+
+    let (status, _, body) = send_raw_request(
+        app.clone(),
+        "/api/junctions/export?format=gpx&bbox=138.0,34.0,140.0,36.0&angle_type=verysharp",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let gpx = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(gpx.matches("<wpt ").count(), 1);
+    assert!(gpx.contains("<type>verysharp</type>"));
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions/export?format=shp&bbox=138.0,34.0,140.0,36.0",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "Invalid format");
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions/export?format=csv&bbox=138.0,34.0,140.0,36.0&limit=10",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "limit cannot be used with export");
+
+    let (status, json) = send_request(app, "/api/junctions/export?format=csv").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bbox or center and radius_m is required");
+}
+
+// ========== スキーマのバージョン確認のテスト ==========
+
+#[tokio::test]
+#[serial]
+async fn test_schema_status_after_migrations() {
+    let pool = setup_test_db().await;
+
+    let status = migrations::schema_status(&pool).await.unwrap();
+    assert_eq!(status.pending(), 0);
+    assert!(status.unknown.is_empty());
+    assert_eq!(status.current_version(), status.expected_version());
+    assert!(status.check().is_ok());
+
+    // 起動時の確認も通る（適用済みなら何もしない）
+    migrations::prepare_schema(&pool, true).await.unwrap();
+}
+
+// ========== データセットファイルから配信するモード（データベースなし）のテスト ==========
+
+#[tokio::test]
+async fn test_router_serves_dataset_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("y-junctions.fgb");
+    let mut writer = FlatGeobufWriter::new("y_junctions");
+    let fixtures = [
+        TestJunctionData::verysharp_type().with_location(35.5, 139.5),
+        TestJunctionData::sharp_type().with_location(35.6, 139.6),
+        TestJunctionData::normal_type().with_location(34.7, 135.5),
+    ];
+    for (id, data) in (1..).zip(fixtures) {
+        writer.add(&data.with_highway_class("tertiary").to_junction(id));
+    }
+    writer
+        .finish(std::fs::File::create(&path).unwrap())
+        .unwrap();
+
+    let junctions = y_junction_backend::dataset::read_junctions(&path)
+        .await
+        .unwrap();
+    let app = create_router_with(Arc::new(MemoryRepository::new(junctions)));
+
+    let (status, json) = send_request(
+        app.clone(),
+        "/api/junctions?bbox=139.0,35.0,140.0,36.0&angle_type=verysharp,sharp",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 2);
+    assert_eq!(json["features"][0]["properties"]["id"], 1);
+    assert_eq!(json["features"][1]["properties"]["angle_type"], "sharp");
+
+    let (status, json) = send_request(app.clone(), "/api/junctions/3").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["properties"]["id"], 3);
+
+    let (status, _) = send_request(app.clone(), "/api/junctions/4").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, json) = send_request(app.clone(), "/api/stats").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_count"], 3);
+    assert_eq!(json["by_type"]["normal"], 1);
+    assert_eq!(json["histograms"]["highway_class"]["tertiary"], 3);
+
+    let (status, json) = send_request(app, "/api/junctions/nearest?lat=35.5&lon=139.5&k=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["features"][0]["properties"]["id"], 1);
+}
+
+backend_tests! {
+    test_get_junctions_with_bbox,
+    test_get_junctions_with_angle_type_filter,
+    test_get_junctions_with_min_angle_filter,
+    test_get_junctions_with_limit,
+    test_get_junctions_invalid_bbox_format,
+    test_get_junctions_invalid_bbox_range,
+    test_get_junctions_bbox_out_of_range,
+    test_get_junctions_invalid_angle_type,
+    test_get_junctions_invalid_limit,
+    test_get_junction_by_id_success,
+    test_get_junction_by_id_not_found,
+    test_get_stats_with_data,
+    test_get_stats_no_data,
+    test_get_stats_with_filters_and_histograms,
+    test_error_response_format,
+    test_get_junctions_with_min_angle_elevation_diff_filter,
+    test_get_junctions_response_includes_elevation_data,
+    test_get_junctions_combined_filters_with_elevation,
+    test_get_junctions_with_max_angle_elevation_diff_filter,
+    test_get_junctions_with_elevation_diff_range,
+    test_get_junctions_with_invalid_elevation_diff_range,
+    test_get_junctions_with_max_elevation_diff_negative,
+    test_bridge_tunnel_excluded_with_include_toggles,
+    test_bridge_tunnel_included_without_elevation_filter,
+    test_get_junctions_with_min_arm_grade_filter,
+    test_get_elevation_stats,
+    test_get_junctions_with_elevation_range_filter,
+    test_get_junctions_with_elevation_diff_and_slope_filters,
+    test_get_junctions_with_invalid_range_filters,
+    test_get_junctions_paginates_with_cursor,
+    test_get_junctions_sorted_by_distance,
+    test_get_junctions_invalid_sort_and_cursor,
+    test_get_junctions_within_radius,
+    test_get_nearest_junctions,
+    test_get_junctions_invalid_radius_search,
+    test_search_junctions_within_polygon,
+    test_search_junctions_invalid_geometry,
+    test_get_tile,
+    test_get_tile_invalid_path,
+    test_get_tile_rejects_list_parameters,
+    test_get_junction_clusters,
+    test_get_junction_clusters_invalid_query,
+    test_get_junctions_sampled_when_exceeding_limit,
+    test_get_junctions_grid_sampling_tops_up_to_limit,
+    test_get_junctions_grid_sampling_at_single_location,
+    test_get_junctions_invalid_sampling,
+    test_export_junctions_in_each_format,
+}
@@ -1,4 +1,14 @@
-import type { Junction, JunctionFeatureCollection, Stats, FilterParams } from '../types';
+import type {
+  Junction,
+  JunctionFeatureCollection,
+  JunctionClusterCollection,
+  Stats,
+  AdminLevel,
+  AreasResponse,
+  ExportFormat,
+  FilterParams,
+  SearchGeometry,
+} from '../types';
 
 const BASE_URL = import.meta.env.VITE_API_URL || 'http://localhost:8080/api';
 
@@ -14,6 +24,61 @@ export class ApiError extends Error {
   }
 }
 
+// 絞り込み条件をクエリパラメータに追加
+function appendFilterParams(
+  params: URLSearchParams,
+  filters?: Omit<FilterParams, 'bbox'>
+): void {
+  if (filters?.angle_type && filters.angle_type.length > 0) {
+    // カンマ区切りの文字列として送信（バックエンドの期待形式）
+    params.append('angle_type', filters.angle_type.join(','));
+  }
+  if (filters?.min_angle_lt !== undefined) {
+    params.append('min_angle_lt', filters.min_angle_lt.toString());
+  }
+  if (filters?.min_angle_gt !== undefined) {
+    params.append('min_angle_gt', filters.min_angle_gt.toString());
+  }
+  if (filters?.limit !== undefined) {
+    params.append('limit', filters.limit.toString());
+  }
+  if (filters?.min_angle_elevation_diff !== undefined) {
+    params.append('min_angle_elevation_diff', filters.min_angle_elevation_diff.toString());
+  }
+  if (filters?.max_angle_elevation_diff !== undefined) {
+    params.append('max_angle_elevation_diff', filters.max_angle_elevation_diff.toString());
+  }
+  if (filters?.min_arm_grade !== undefined) {
+    params.append('min_arm_grade', filters.min_arm_grade.toString());
+  }
+
+  // 範囲フィルタと橋・トンネルの切り替え
+  const optionalParams = [
+    'elevation_gte',
+    'elevation_lte',
+    'min_elevation_diff_gte',
+    'min_elevation_diff_lte',
+    'max_elevation_diff_gte',
+    'max_elevation_diff_lte',
+    'local_slope_gte',
+    'local_slope_lte',
+    'include_bridges',
+    'include_tunnels',
+    'sort',
+    'center',
+    'cursor',
+    'sampling',
+    'area',
+    'q',
+  ] as const;
+  for (const key of optionalParams) {
+    const value = filters?.[key];
+    if (value !== undefined) {
+      params.append(key, value.toString());
+    }
+  }
+}
+
 // Y字路一覧を取得
 export async function fetchJunctions(
   bbox: string,
@@ -21,32 +86,123 @@ export async function fetchJunctions(
 ): Promise<JunctionFeatureCollection> {
   try {
     const params = new URLSearchParams({ bbox });
+    appendFilterParams(params, filters);
 
-    if (filters?.angle_type && filters.angle_type.length > 0) {
-      // カンマ区切りの文字列として送信（バックエンドの期待形式）
-      params.append('angle_type', filters.angle_type.join(','));
+    const url = `${BASE_URL}/junctions?${params.toString()}`;
+    const response = await fetch(url);
+
+    if (!response.ok) {
+      throw new ApiError(`Failed to fetch junctions: ${response.statusText}`, response.status);
     }
-    if (filters?.min_angle_lt !== undefined) {
-      params.append('min_angle_lt', filters.min_angle_lt.toString());
+
+    const data: JunctionFeatureCollection = await response.json();
+    return data;
+  } catch (error) {
+    if (error instanceof ApiError) {
+      throw error;
     }
-    if (filters?.min_angle_gt !== undefined) {
-      params.append('min_angle_gt', filters.min_angle_gt.toString());
+    throw new ApiError(
+      `Network error: ${error instanceof Error ? error.message : 'Unknown error'}`
+    );
+  }
+}
+
+// 低ズーム用にグリッドで集計したクラスタを取得
+export async function fetchJunctionClusters(
+  bbox: string,
+  zoom: number,
+  filters?: Omit<FilterParams, 'bbox'>
+): Promise<JunctionClusterCollection> {
+  try {
+    const params = new URLSearchParams({ bbox, zoom: zoom.toString() });
+    appendFilterParams(params, filters);
+
+    const url = `${BASE_URL}/junctions/clusters?${params.toString()}`;
+    const response = await fetch(url);
+
+    if (!response.ok) {
+      throw new ApiError(`Failed to fetch clusters: ${response.statusText}`, response.status);
     }
-    if (filters?.limit !== undefined) {
-      params.append('limit', filters.limit.toString());
+
+    const data: JunctionClusterCollection = await response.json();
+    return data;
+  } catch (error) {
+    if (error instanceof ApiError) {
+      throw error;
     }
-    if (filters?.min_angle_elevation_diff !== undefined) {
-      params.append('min_angle_elevation_diff', filters.min_angle_elevation_diff.toString());
+    throw new ApiError(
+      `Network error: ${error instanceof Error ? error.message : 'Unknown error'}`
+    );
+  }
+}
+
+// ベクトルタイルのURLテンプレートを作成（{z}/{x}/{y} は地図ライブラリが置き換える）
+export function buildTileUrlTemplate(filters?: Omit<FilterParams, 'bbox'>): string {
+  const params = new URLSearchParams();
+  appendFilterParams(params, filters);
+  const query = params.toString();
+
+  return `${BASE_URL}/tiles/{z}/{x}/{y}.mvt${query ? `?${query}` : ''}`;
+}
+
+// 多角形の範囲内のY字路を取得
+export async function searchJunctions(
+  geometry: SearchGeometry,
+  filters?: Omit<FilterParams, 'bbox'>
+): Promise<JunctionFeatureCollection> {
+  try {
+    // angle_type はクエリパラメータと同じくカンマ区切りで送る
+    const { angle_type, ...rest } = filters ?? {};
+    const body = {
+      geometry,
+      ...rest,
+      ...(angle_type && angle_type.length > 0 ? { angle_type: angle_type.join(',') } : {}),
+    };
+
+    const response = await fetch(`${BASE_URL}/junctions/search`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(body),
+    });
+
+    if (!response.ok) {
+      throw new ApiError(`Failed to search junctions: ${response.statusText}`, response.status);
     }
-    if (filters?.max_angle_elevation_diff !== undefined) {
-      params.append('max_angle_elevation_diff', filters.max_angle_elevation_diff.toString());
+
+    const data: JunctionFeatureCollection = await response.json();
+    return data;
+  } catch (error) {
+    if (error instanceof ApiError) {
+      throw error;
     }
+    throw new ApiError(
+      `Network error: ${error instanceof Error ? error.message : 'Unknown error'}`
+    );
+  }
+}
 
-    const url = `${BASE_URL}/junctions?${params.toString()}`;
+// nearest で指定できない一覧用のパラメータ（件数は k、並び順は距離で決まる）
+type NearestFilterParams = Omit<
+  FilterParams,
+  'bbox' | 'limit' | 'sort' | 'center' | 'cursor' | 'sampling'
+>;
+
+// 指定地点に近いY字路を近い順に取得
+export async function fetchNearestJunctions(
+  lat: number,
+  lon: number,
+  k: number,
+  filters?: NearestFilterParams
+): Promise<JunctionFeatureCollection> {
+  try {
+    const params = new URLSearchParams({ lat: lat.toString(), lon: lon.toString(), k: k.toString() });
+    appendFilterParams(params, filters);
+
+    const url = `${BASE_URL}/junctions/nearest?${params.toString()}`;
     const response = await fetch(url);
 
     if (!response.ok) {
-      throw new ApiError(`Failed to fetch junctions: ${response.statusText}`, response.status);
+      throw new ApiError(`Failed to fetch nearest junctions: ${response.statusText}`, response.status);
     }
 
     const data: JunctionFeatureCollection = await response.json();
@@ -83,10 +239,28 @@ export async function fetchJunctionById(id: number): Promise<Junction> {
   }
 }
 
-// 統計情報を取得
-export async function fetchStats(): Promise<Stats> {
+// エクスポート用のURLを作成（ブラウザでダウンロードさせるため fetch はしない）
+export function buildExportUrl(
+  format: ExportFormat,
+  bbox: string,
+  filters?: Omit<FilterParams, 'bbox' | 'limit' | 'cursor' | 'sampling'>
+): string {
+  const params = new URLSearchParams({ format, bbox });
+  appendFilterParams(params, filters);
+  return `${BASE_URL}/junctions/export?${params.toString()}`;
+}
+
+// 統計情報を取得（bbox を省略するとデータベース全体）
+export async function fetchStats(
+  bbox?: string,
+  filters?: Omit<FilterParams, 'bbox' | 'limit' | 'sort' | 'cursor' | 'sampling'>
+): Promise<Stats> {
   try {
-    const url = `${BASE_URL}/stats`;
+    const params = new URLSearchParams(bbox ? { bbox } : {});
+    appendFilterParams(params, filters);
+
+    const query = params.toString();
+    const url = query ? `${BASE_URL}/stats?${query}` : `${BASE_URL}/stats`;
     const response = await fetch(url);
 
     if (!response.ok) {
@@ -104,3 +278,57 @@ export async function fetchStats(): Promise<Stats> {
     );
   }
 }
+
+// 行政区域の一覧を取得（件数付き、コード順）
+export async function fetchAreas(level: AdminLevel = 'prefecture'): Promise<AreasResponse> {
+  try {
+    const params = new URLSearchParams({ level });
+    const url = `${BASE_URL}/areas?${params.toString()}`;
+    const response = await fetch(url);
+
+    if (!response.ok) {
+      throw new ApiError(`Failed to fetch areas: ${response.statusText}`, response.status);
+    }
+
+    const data: AreasResponse = await response.json();
+    return data;
+  } catch (error) {
+    if (error instanceof ApiError) {
+      throw error;
+    }
+    throw new ApiError(
+      `Network error: ${error instanceof Error ? error.message : 'Unknown error'}`
+    );
+  }
+}
+
+// 条件に一致するY字路が多い順に行政区域を取得
+export async function fetchAreaRanking(
+  level: AdminLevel,
+  filters?: Omit<FilterParams, 'sort' | 'cursor' | 'sampling'>
+): Promise<AreasResponse> {
+  try {
+    const params = new URLSearchParams({ level });
+    if (filters?.bbox) {
+      params.append('bbox', filters.bbox);
+    }
+    appendFilterParams(params, filters);
+
+    const url = `${BASE_URL}/areas/ranking?${params.toString()}`;
+    const response = await fetch(url);
+
+    if (!response.ok) {
+      throw new ApiError(`Failed to fetch area ranking: ${response.statusText}`, response.status);
+    }
+
+    const data: AreasResponse = await response.json();
+    return data;
+  } catch (error) {
+    if (error instanceof ApiError) {
+      throw error;
+    }
+    throw new ApiError(
+      `Network error: ${error instanceof Error ? error.message : 'Unknown error'}`
+    );
+  }
+}
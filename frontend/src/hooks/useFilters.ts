@@ -56,6 +56,12 @@ export function useFilters() {
       params.max_angle_elevation_diff = elevationDiffRange[1];
     }
 
+    // 橋・トンネルでは地表の標高差が意味を持たないため、標高差で絞り込むときは除外する
+    if (params.min_angle_elevation_diff !== undefined || params.max_angle_elevation_diff !== undefined) {
+      params.include_bridges = false;
+      params.include_tunnels = false;
+    }
+
     return params;
   }, [angleTypes, minAngleRange, elevationDiffRange]);
 
@@ -56,6 +56,7 @@ const MOCK_DATA: JunctionFeatureCollection = {
     },
   ],
   total_count: 3,
+  next_cursor: null,
 };
 
 interface UseJunctionsOptions {
@@ -1,6 +1,21 @@
 // AngleType
 export type AngleType = 'verysharp' | 'sharp' | 'normal';
 
+// 分岐点から見た道路の勾配方向
+export type ArmSlope = 'uphill' | 'downhill' | 'flat';
+
+// 一覧の並び順
+// limit を超えた場合の間引き方
+export type JunctionSampling = 'grid' | 'sharpest' | 'random';
+
+export type JunctionSort = 'id' | 'min_angle' | 'elevation_diff' | 'distance';
+
+// 標高値の求め方
+export type ElevationQuality = 'bilinear' | 'nearest' | 'filled';
+
+// 標高を取得できなかった理由
+export type ElevationSkipReason = 'no_tile' | 'nodata_at_junction' | 'nodata_at_neighbor';
+
 // Junction (単体取得時のレスポンス)
 export interface Junction {
   id: number;
@@ -29,6 +44,17 @@ export interface JunctionProperties {
   streetview_url: string;
   bearings: number[];
   min_angle_elevation_diff?: number;
+  local_slope?: number | null;
+  distance_m?: number | null; // 検索の基準点からの距離（半径検索・最近傍検索時）
+  areas?: JunctionAreas; // Y字路が含まれる行政区域
+  road_names?: [string | null, string | null, string | null]; // 各道路の name（bearings と同じ順）
+  label?: string; // 道路名と行政区域から作った表示名
+  arm_grades?: [number | null, number | null, number | null];
+  arm_slopes?: [ArmSlope | null, ArmSlope | null, ArmSlope | null];
+  elevation_source?: string | null;
+  elevation_quality?: ElevationQuality | null;
+  elevation_nodata_samples?: number | null;
+  elevation_skip_reason?: ElevationSkipReason | null;
 }
 
 export interface JunctionFeature {
@@ -40,11 +66,66 @@ export interface JunctionFeature {
 export interface JunctionFeatureCollection {
   type: 'FeatureCollection';
   features: JunctionFeature[];
+  total_count: number | null; // カーソルで取得した2ページ目以降は null
+  next_cursor: string | null; // 次のページのカーソル（最後のページでは null）
+  sampled?: boolean; // limit を超えたため間引いた結果か（nearest では省略）
+  sampling?: JunctionSampling | null;
+}
+
+// 低ズーム時のクラスタ（グリッドのセルごとの集計）
+export interface JunctionClusterProperties {
+  count: number;
+  min_angle: number;
+  sharpest_id: number;
+  by_type: {
+    verysharp: number;
+    sharp: number;
+    normal: number;
+  };
+}
+
+export interface JunctionClusterFeature {
+  type: 'Feature';
+  geometry: GeoJSONPoint;
+  properties: JunctionClusterProperties;
+}
+
+export interface JunctionClusterCollection {
+  type: 'FeatureCollection';
+  features: JunctionClusterFeature[];
   total_count: number;
+  cell_size_m: number;
 }
 
-// 統計情報
-export interface Stats {
+// ヒストグラムのビン（[from, to)、最後のビンは to が null のことがある）
+export interface HistogramBin {
+  from: number;
+  to: number | null;
+  count: number;
+}
+
+// エクスポート形式
+export type ExportFormat = 'geojson' | 'csv' | 'kml' | 'gpx';
+
+// 行政区域（都道府県・市区町村・政令指定都市の区）
+export type AdminLevel = 'prefecture' | 'city' | 'ward';
+
+export interface AreaRef {
+  code: string;
+  name: string;
+}
+
+export interface JunctionAreas {
+  prefecture: AreaRef | null;
+  city: AreaRef | null;
+  ward: AreaRef | null;
+}
+
+// GET /api/areas, GET /api/areas/ranking の各行政区域
+export interface AreaSummary {
+  code: string;
+  name: string;
+  level: AdminLevel;
   total_count: number;
   by_type: {
     verysharp: number;
@@ -53,6 +134,26 @@ export interface Stats {
   };
 }
 
+export interface AreasResponse {
+  level: AdminLevel;
+  areas: AreaSummary[];
+}
+
+// 統計情報
+export interface Stats {
+  total_count: number;
+  by_type: {
+    verysharp?: number;
+    sharp?: number;
+    normal?: number;
+  };
+  histograms: {
+    min_angle: HistogramBin[]; // 5°刻み
+    elevation_diff: HistogramBin[]; // 1m刻み（最小角高低差）
+    highway_class: Record<string, number>; // 最も上位の道路種別ごとの件数
+  };
+}
+
 // フィルタパラメータ
 export interface FilterParams {
   bbox?: string; // "min_lon,min_lat,max_lon,max_lat"
@@ -61,9 +162,31 @@ export interface FilterParams {
   min_angle_gt?: number;
   min_angle_elevation_diff?: number;
   max_angle_elevation_diff?: number; // 範囲検索用
+  min_arm_grade?: number; // いずれかの道路の上り勾配（%）の下限
+  elevation_gte?: number; // 標高（m）の範囲
+  elevation_lte?: number;
+  min_elevation_diff_gte?: number; // 最小高低差（m）の範囲
+  min_elevation_diff_lte?: number;
+  max_elevation_diff_gte?: number; // 最大高低差（m）の範囲
+  max_elevation_diff_lte?: number;
+  local_slope_gte?: number; // 局所勾配（%）の範囲
+  local_slope_lte?: number;
+  include_bridges?: boolean; // 未指定時は含める
+  include_tunnels?: boolean;
+  sort?: JunctionSort;
+  center?: string; // "lat,lon"（sort=distance のとき必須）
+  cursor?: string; // 前のレスポンスの next_cursor
+  sampling?: JunctionSampling; // limit を超えた場合の間引き方（cursor とは併用不可）
+  area?: string; // 都道府県・市区町村・区のコード
+  q?: string; // 道路名（name, name:en, 読み）の部分一致検索
   limit?: number;
 }
 
+// 検索範囲（GeoJSON Polygon / MultiPolygon）
+export type SearchGeometry =
+  | { type: 'Polygon'; coordinates: [number, number][][] }
+  | { type: 'MultiPolygon'; coordinates: [number, number][][][] };
+
 // 地図のbounds
 export interface LatLngBounds {
   north: number;